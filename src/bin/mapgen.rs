@@ -0,0 +1,242 @@
+use std::collections::BTreeMap;
+
+use bracket_lib::prelude::RandomNumberGenerator;
+use my_rl::map_builders::{self, invariants, PREFAB_TERRAIN, RECIPES};
+use my_rl::{raws, rng, Map, TileType, MAP_HEIGHT, MAP_WIDTH};
+
+/* mapgen
+ *
+ * Builds one level without the game and prints it: the map as text, where the
+ * player starts, the stairs, and what was spawned where. Given the same seed
+ * and depth (and no --biome or --recipe), it's the level the game builds.
+ *
+ *   mapgen --seed <n> [--depth <n>] [--width <n>] [--height <n>]
+ *          [--biome <name>] [--recipe <name>] [--snapshots]
+ *   mapgen --list
+ *   mapgen --stats [--seeds <n>] [--depth <n>] [--width <n>] [--height <n>]
+ *
+ * --biome and --recipe stand in for what the depth would roll; a recipe
+ * needn't be one of the biome's own. --snapshots also prints the map as it
+ * stood after each step of the build. --list prints the recipes in
+ * raws/map_recipes.yaml, then the biomes and the recipes each builds with.
+ * --stats builds every recipe with seeds 0 to n - 1 (100 by default) and
+ * reports, per recipe and per starting builder, how open the levels are, how
+ * many rooms and spawns they have, and how many broke an invariant (see
+ * map_builders/invariants.rs), with the first few of those.
+ */
+
+const USAGE: &str = "usage: mapgen --seed <n> [--depth <n>] [--width <n>] [--height <n>] [--biome <name>] [--recipe <name>] [--snapshots]
+       mapgen --list
+       mapgen --stats [--seeds <n>] [--depth <n>] [--width <n>] [--height <n>]";
+
+fn main() {
+    if let Err(e) = raws::load_raws(raws::RAWS_PATH) {
+        eprintln!("Using built-in raws; {}", e);
+    }
+    if let Err(e) = map_builders::load_prefabs(map_builders::PREFABS_PATH) {
+        eprintln!("Using built-in prefabs; {}", e);
+    }
+    if let Err(e) = map_builders::load_recipes(map_builders::RECIPES_PATH) {
+        eprintln!("Using built-in map recipes; {}", e);
+    }
+
+    if has_flag("--list") {
+        let recipes = RECIPES.lock().unwrap();
+        for name in recipes.names() {
+            println!("{}", name);
+        }
+        println!();
+        for biome in recipes.biomes() {
+            let builds_with: Vec<&str> = recipes.biome_recipes(&biome.name).into_iter().map(|r| r.as_str()).collect();
+            println!("{}: {}", biome.name, builds_with.join(", "));
+        }
+        return;
+    }
+
+    if has_flag("--stats") {
+        let seeds = arg_value("--seeds").map_or(100, |n| number(&n, "--seeds"));
+        let depth = arg_value("--depth").map_or(1, |d| number(&d, "--depth"));
+        let width = arg_value("--width").map_or(MAP_WIDTH, |w| number(&w, "--width"));
+        let height = arg_value("--height").map_or(MAP_HEIGHT, |h| number(&h, "--height"));
+        stats(seeds, depth, width, height);
+        return;
+    }
+
+    let seed = match arg_value("--seed") {
+        Some(seed) => number(&seed, "--seed"),
+        None => usage_error("--seed is required"),
+    };
+    let depth = arg_value("--depth").map_or(1, |d| number(&d, "--depth"));
+    let width = arg_value("--width").map_or(MAP_WIDTH, |w| number(&w, "--width"));
+    let height = arg_value("--height").map_or(MAP_HEIGHT, |h| number(&h, "--height"));
+    if depth < 1 || width < 16 || height < 16 {
+        usage_error("depth must be at least 1, and width and height at least 16");
+    }
+
+    //The same stream and the same draws as generate_world_map, so the game agrees.
+    let mut rng = rng::mapgen_rng(seed, depth);
+    let biome = match arg_value("--biome") {
+        Some(biome) => biome,
+        None => match RECIPES.lock().unwrap().biome_for(depth, &mut rng) {
+            Some(biome) => biome,
+            None => usage_error(&format!("no biome for depth {}; pick one with --biome", depth)),
+        },
+    };
+    let recipe = match arg_value("--recipe") {
+        Some(recipe) => recipe,
+        None => match RECIPES.lock().unwrap().recipe_in(&biome, depth, &mut rng) {
+            Some(recipe) => recipe,
+            None => usage_error(&format!("no biome called \"{}\" (see --list)", biome)),
+        },
+    };
+    let mut chain = match map_builders::themed_builder(&biome, Some(&recipe), depth, &mut rng, width, height) {
+        Some(chain) => chain,
+        None if !RECIPES.lock().unwrap().contains(&recipe) => usage_error(&format!("no recipe called \"{}\" (see --list)", recipe)),
+        None => usage_error(&format!("no biome called \"{}\" (see --list)", biome)),
+    };
+    chain.build_data.take_snapshots = has_flag("--snapshots");
+    chain.build_map(&mut rng);
+    let data = &chain.build_data;
+
+    for (i, snapshot) in data.snapshot_history.iter().enumerate() {
+        println!("snapshot {} of {}: {}", i + 1, data.snapshot_history.len(), snapshot.step);
+        println!("{}", render(&snapshot.map, None));
+    }
+
+    println!("seed {}, depth {}, {}x{}, biome {}, recipe {}", seed, depth, width, height, biome, recipe);
+    let start = data.starting_position.as_ref().map(|start| (start.x, start.y));
+    println!("{}", render(&data.map, start));
+    match start {
+        Some((x, y)) => println!("start: {}, {}", x, y),
+        None => println!("start: none"),
+    }
+    println!("stairs down: {}", tiles_at(&data.map, TileType::StairsDown));
+    println!("stairs up: {}", tiles_at(&data.map, TileType::StairsUp));
+    println!("spawns: {}", data.spawn_list.len());
+    for (idx, name) in data.spawn_list.iter() {
+        let (x, y) = (*idx as i32 % data.map.width, *idx as i32 / data.map.width);
+        println!("  {}, {} {}", x, y, name);
+    }
+}
+
+//Running totals for one recipe or starting builder.
+#[derive(Default)]
+struct Totals {
+    maps: usize,
+    broken: usize,
+    open: f32,
+    rooms: usize,
+    with_rooms: usize,
+    spawns: usize,
+    doors: usize,
+}
+
+impl Totals {
+    fn add(&mut self, stats: &invariants::MapStats, broken: bool) {
+        self.maps += 1;
+        self.broken += broken as usize;
+        self.open += stats.open_ratio;
+        if let Some(rooms) = stats.rooms {
+            self.rooms += rooms;
+            self.with_rooms += 1;
+        }
+        self.spawns += stats.spawns;
+        self.doors += stats.doors;
+    }
+
+    fn print(&self, name: &str) {
+        let per_map = |n: usize| n as f32 / self.maps.max(1) as f32;
+        let rooms = match self.with_rooms {
+            0 => "-".to_string(),
+            with_rooms => format!("{:.1}", self.rooms as f32 / with_rooms as f32),
+        };
+        println!("{:<40} {:>5} {:>6} {:>5.0}% {:>6} {:>7.1} {:>6.1}", name, self.maps, self.broken,
+                 100.0 * self.open / self.maps.max(1) as f32, rooms, per_map(self.spawns), per_map(self.doors));
+    }
+}
+
+fn stats(seeds: u64, depth: i32, width: i32, height: i32) {
+    let recipes: Vec<String> = RECIPES.lock().unwrap().names().cloned().collect();
+    let mut by_recipe: BTreeMap<String, Totals> = BTreeMap::new();
+    let mut by_starter: BTreeMap<String, Totals> = BTreeMap::new();
+    let mut examples = Vec::new();
+    std::panic::set_hook(Box::new(|_| {})); //panics are counted, not printed
+
+    for recipe in recipes.iter() {
+        for seed in 0..seeds {
+            let built = std::panic::catch_unwind(|| {
+                let mut rng = RandomNumberGenerator::seeded(seed);
+                let mut chain = map_builders::named_builder(recipe, depth, &mut rng, width, height).unwrap();
+                chain.build_map(&mut rng);
+                (invariants::stats(&chain.build_data), invariants::violations(&chain.build_data), chain.steps)
+            });
+            let (stats, problems, starter) = match built {
+                Ok((stats, problems, steps)) => (stats, problems, steps[0].clone()),
+                Err(_) => (invariants::MapStats::default(), vec!["panicked".to_string()], "(panicked)".to_string()),
+            };
+            if !problems.is_empty() && examples.len() < 10 {
+                examples.push(format!("{} seed {}: {}", recipe, seed, problems.join("; ")));
+            }
+            by_recipe.entry(recipe.clone()).or_default().add(&stats, !problems.is_empty());
+            by_starter.entry(starter).or_default().add(&stats, !problems.is_empty());
+        }
+    }
+
+    println!("{} seeds per recipe, depth {}, {}x{}", seeds, depth, width, height);
+    println!("{:<40} {:>5} {:>6} {:>6} {:>6} {:>7} {:>6}", "", "maps", "broken", "open", "rooms", "spawns", "doors");
+    for (name, totals) in by_recipe.iter() {
+        totals.print(name);
+    }
+    println!();
+    for (name, totals) in by_starter.iter() {
+        totals.print(name);
+    }
+    for example in examples.iter() {
+        println!("{}", example);
+    }
+}
+
+//One character per tile, as prefabs spell them; `@` marks the start.
+fn render(map: &Map, start: Option<(i32, i32)>) -> String {
+    let mut out = String::with_capacity(((map.width + 1) * map.height) as usize);
+    for y in 0..map.height {
+        for x in 0..map.width {
+            let tile = map.tiles[map.xy_idx(x, y)];
+            let ch = match PREFAB_TERRAIN.iter().find(|(_, terrain)| *terrain == tile) {
+                _ if start == Some((x, y)) => '@',
+                Some((ch, _)) => *ch,
+                None => tile.glyph().0,
+            };
+            out.push(ch);
+        }
+        out.push('\n');
+    }
+    out
+}
+
+fn tiles_at(map: &Map, tile: TileType) -> String {
+    let found: Vec<String> = map.tiles.iter().enumerate()
+        .filter(|(_, t)| **t == tile)
+        .map(|(idx, _)| format!("{}, {}", idx as i32 % map.width, idx as i32 / map.width))
+        .collect();
+    if found.is_empty() { "none".to_string() } else { found.join("; ") }
+}
+
+fn number<T: std::str::FromStr>(value: &str, flag: &str) -> T {
+    value.parse().unwrap_or_else(|_| usage_error(&format!("{} wants a number, not \"{}\"", flag, value)))
+}
+
+fn usage_error(message: &str) -> ! {
+    eprintln!("mapgen: {}\n{}", message, USAGE);
+    std::process::exit(2);
+}
+
+fn arg_value(flag: &str) -> Option<String> {
+    let args: Vec<String> = std::env::args().collect();
+    let pos = args.iter().position(|arg| arg == flag)?;
+    args.get(pos + 1).cloned()
+}
+
+fn has_flag(flag: &str) -> bool {
+    std::env::args().any(|arg| arg == flag)
+}
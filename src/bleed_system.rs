@@ -1,11 +1,11 @@
 use specs::prelude::*;
-use super::{RunState, DamageQueue, Bleeding, DamageAtom, Position, Map};
+use super::{initiative_system::GameClock, DamageQueue, Bleeding, DamageAtom, Position, Map};
 
 pub struct BleedSystem {} //Damage over Time System
 
 impl<'a> System<'a> for BleedSystem {
     type SystemData = ( Entities<'a>,
-                        ReadExpect<'a, RunState>,
+                        ReadExpect<'a, GameClock>,
                         ReadStorage<'a, Position>,
                         ReadStorage<'a, Bleeding>,
                         WriteStorage<'a, DamageQueue>,
@@ -13,9 +13,10 @@ impl<'a> System<'a> for BleedSystem {
                       );
 
     fn run (&mut self, data: Self::SystemData) {
-        let (entities, runstate, pos, bleed_storage, mut damage_queues, mut map) = data;
+        let (entities, clock, pos, bleed_storage, mut damage_queues, mut map) = data;
 
-        if *runstate != RunState::GameworldTurn { return; }
+        //Bleeding is per turn of game time, not per action.
+        if !clock.is_new_turn() { return; }
 
         for (ent, pos, _bleed) in (&entities, &pos, &bleed_storage).join() {
             
@@ -98,30 +98,24 @@ pub fn render_camera(ecs: &World, ctx : &mut BTerm) {
 }
 
 fn get_tile_glyph(idx: usize, map: &Map) -> (bracket_lib::prelude::FontCharType, RGB, RGB) {
-    let glyph;
-    let mut fg;
+    let tile = map.tiles[idx].looks_like();
+    let ch = tile.glyph().0;
+    let mut fg = map.biome.colour(tile);
     let mut bg = RGB::from_f32(0., 0., 0.);
-
-    match map.tiles[idx] {
-        TileType::Floor => {
-            glyph = bracket_lib::prelude::to_cp437('.');
-            fg = RGB::from_f32(0.0, 0.5, 0.5);
-        }
-        TileType::Wall => {
-            let x = idx as i32 % map.width;
-            let y = idx as i32 / map.width;
-            glyph = wall_glyph(&*map, x, y);
-            fg = RGB::from_f32(0., 1.0, 0.);
-        }
-        TileType::StairsDown => {
-            glyph = bracket_lib::prelude::to_cp437('>');
-            fg = RGB::from_f32(0., 1.0, 1.0);
-        }
-    }
+    let glyph = if tile == TileType::Wall {
+        let x = idx as i32 % map.width;
+        let y = idx as i32 / map.width;
+        wall_glyph(&*map, x, y)
+    } else {
+        bracket_lib::prelude::to_cp437(ch)
+    };
     if map.bloodstains.contains(&idx) { bg = RGB::from_f32(0.75, 0., 0.); }
     if !map.visible_tiles[idx] { 
         fg = fg.to_greyscale();
         bg = RGB::from_f32(0., 0., 0.); // Don't show stains out of visual range
+    } else if !map.illuminated_tiles.contains(&idx) {
+        // Dimmer biomes look it, but not so dim that they're hard to read; torchlight isn't dimmed
+        fg = fg.lerp(RGB::from_f32(0., 0., 0.), (1.0 - map.biome.ambient_light) / 2.0);
     }
 
     (glyph, fg, bg)
@@ -160,7 +154,7 @@ fn wall_glyph(map: &Map, x: i32, y: i32) -> bracket_lib::prelude::FontCharType {
 
 fn is_revealed_wall(map: &Map, x: i32, y: i32) -> bool {
     let idx = map.xy_idx(x, y);
-    map.tiles[idx] == TileType::Wall && map.revealed_tiles[idx]
+    map.tiles[idx].looks_like() == TileType::Wall && map.revealed_tiles[idx]
 }
 
 pub fn render_debug_map(map : &Map, ctx : &mut BTerm) {
@@ -1,5 +1,6 @@
 use std::sync::Mutex;
 
+use serde::{Deserialize, Serialize};
 use specs::World;
 
 use super::RunState;
@@ -33,12 +34,17 @@ use super::gui::look_n_feel::Dir;
  * and process this queue via Commandable::process(&self, ecs: &mut World ... ) -> ...
  */
 
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
 pub enum Command {
     Grab,
     Move { dir: Dir },
+    Save,
     Select,
     Undo,
     Wait,
+    Stairs,
+    Close,
+    Search,
 }
 
 pub trait Commandable {
@@ -0,0 +1,96 @@
+use std::collections::BTreeMap;
+
+use serde_json::Value;
+use specs::error::NoError;
+use specs::prelude::*;
+use specs::saveload::{DeserializeComponents, SerializeComponents, SimpleMarker, SimpleMarkerAllocator};
+
+use super::components::*;
+use super::saveload_system::SaveError;
+
+/* Component Registry
+ *
+ * The one list of components. Everything in it is registered with the World;
+ * `saved` components are also written to and read back from save files, under
+ * their type name (see saveload_system.rs). `transient` components only mean
+ * something for the frame or turn they exist in, and a loaded game starts
+ * without them.
+ *
+ * A new component goes in one of the two lists, and nowhere else; the
+ * component_registry test fails for any component in components.rs that is
+ * in neither.
+ */
+
+macro_rules! component_registry {
+    (saved: [ $( $saved:ty ),* $(,)? ], transient: [ $( $transient:ty ),* $(,)? ] $(,)?) => {
+        pub const SAVED_COMPONENTS: &[&str] = &[ $( stringify!($saved) ),* ];
+        pub const TRANSIENT_COMPONENTS: &[&str] = &[ $( stringify!($transient) ),* ];
+
+        pub fn register_components(ecs: &mut World) {
+            $( ecs.register::<$saved>(); )*
+            $( ecs.register::<$transient>(); )*
+            ecs.register::<SimpleMarker<SerializeMe>>();
+        }
+
+        //Each saved storage's marked entities, keyed by component name.
+        pub(crate) fn serialize_components(ecs: &World) -> Result<BTreeMap<String, Value>, SaveError> {
+            let mut components = BTreeMap::new();
+            let data = ( ecs.entities(), ecs.read_storage::<SimpleMarker<SerializeMe>>() );
+            $(
+            components.insert(
+                stringify!($saved).to_string(),
+                SerializeComponents::<NoError, SimpleMarker<SerializeMe>>::serialize(
+                    &( ecs.read_storage::<$saved>(), ),
+                    &data.0,
+                    &data.1,
+                    serde_json::value::Serializer,
+                )?,
+            );
+            )*
+            Ok(components)
+        }
+
+        //A component missing from the save is one the save predates; it loads as empty.
+        pub(crate) fn deserialize_components(ecs: &mut World, components: &BTreeMap<String, Value>)
+                                             -> Result<(), SaveError> {
+            let mut data = ( &mut ecs.entities(), &mut ecs.write_storage::<SimpleMarker<SerializeMe>>(),
+                             &mut ecs.write_resource::<SimpleMarkerAllocator<SerializeMe>>() );
+            $(
+            if let Some(list) = components.get(stringify!($saved)) {
+                DeserializeComponents::<NoError, _>::deserialize(
+                    &mut ( &mut ecs.write_storage::<$saved>(), ),
+                    &mut data.0, // entities
+                    &mut data.1, // marker
+                    &mut data.2, // allocater
+                    list,
+                )
+                .map_err(|error| SaveError::Component { name: stringify!($saved), error })?;
+            }
+            )*
+            Ok(())
+        }
+    };
+}
+
+component_registry! {
+    saved: [
+        SerializationHelper,
+        //universal
+        Position, OtherLevelPosition, Renderable, Name, Info, Menuable, BlocksTile, BlocksVisibility, Hidden,
+        //creatures
+        Player, Hostile, Creature, Viewshed, Stats, BasicAttack, Resistances, Immunities, Hunger, Energy,
+        Bleeding, Healing, Confusion, Aflame, JustMoved, DamageQueue,
+        //items
+        Item, InBackpack, Consumable, Useable, Ranged, AoE, DamageOnUse, Heals, MagicMapper,
+        Throwable, Equippable, Equipped, Weapon, BlocksAttacks, Flammable, Lightsource, Key,
+        //props
+        Door, EntryTrigger,
+        //intents
+        MeleeIntent, PickUpIntent, UseItemIntent, DropItemIntent, EquipIntent, UnequipIntent, ThrowIntent, DoorIntent,
+        //AI
+        Behavior, Home, Fear, Terror, Inquisitive, Alert, Berzerk, Hungry,
+    ],
+    transient: [
+        Particle, //unmarked, and gone in a fraction of a second anyway
+    ],
+}
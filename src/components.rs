@@ -14,7 +14,9 @@ pub struct SerializeMe;
 //special component for serializing game data
 #[derive(Component, Serialize, Deserialize, Clone)]
 pub struct SerializationHelper {
-    pub map: super::map::Map
+    pub map: super::map::Map,
+    #[serde(default)] //saves from before the level store had one level only
+    pub levels: super::dungeon::LevelStore
 }
 
 //Does the Component macro know to use NullStorage?...
@@ -54,11 +56,17 @@ pub struct Hidden {}
 
 #[derive(Component, Debug, Serialize, Deserialize, Clone)]
 pub struct JustMoved {}
+
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Key {} //item component; opens one locked door, and is used up
 //--------------------------------------------------------
 
+//Closed doors block movement and sight; see door_system.rs.
 #[derive(Component, Debug, Serialize, Deserialize, Clone)]
 pub struct Door { 
-    pub open: bool 
+    pub open: bool,
+    #[serde(default)]
+    pub locked: bool,
 }
 
 #[derive(Component, Debug, Serialize, Deserialize, Clone)]
@@ -103,6 +111,14 @@ pub struct Position {
     pub y: i32
 }
 
+//Stands in for Position while the entity's level is not the current one; see dungeon.rs.
+#[derive(Component, ConvertSaveload, Clone, Copy)]
+pub struct OtherLevelPosition {
+    pub x: i32,
+    pub y: i32,
+    pub depth: i32
+}
+
 #[derive(Component, Debug, ConvertSaveload, Clone)]
 pub struct Stats { //creature component
     //Resources
@@ -179,6 +195,21 @@ pub struct Hunger {
     pub clock: i32,
 }
 
+#[derive(Component, Debug, Serialize, Deserialize, Clone, Copy)]
+pub struct Energy { //creature component; see initiative_system.rs
+    pub speed: i32,   //energy gained per game tick
+    pub current: i32, //may act once this reaches ACTION_COST
+}
+
+impl Energy {
+    //Entities without Energy act on the old one-action-per-turn rule, so this is a no-op for them.
+    pub fn spend(storage: &mut WriteStorage<Energy>, actor: Entity, cost: i32) {
+        if let Some(energy) = storage.get_mut(actor) {
+            energy.current -= cost;
+        }
+    }
+}
+
 #[derive(Component, Debug, ConvertSaveload)]
 pub struct InBackpack { //item component
     pub owner: Entity
@@ -408,6 +439,12 @@ pub struct EquipIntent {
 #[derive(Component, Debug, ConvertSaveload)]
 pub struct UnequipIntent {
     pub item: Entity
+}
+
+#[derive(Component, Debug, ConvertSaveload)]
+pub struct DoorIntent {
+    pub door: Entity,
+    pub open: bool, //false to close it
 }//----------------------------------------------
 
 #[derive(Component, Debug, ConvertSaveload)]
@@ -1,32 +1,31 @@
 use specs::prelude::*;
 use std::cmp::max;
 use bracket_lib::prelude::RandomNumberGenerator;
-use super::{Stats, DamageQueue, DamageAtom, Player, Name, gui::gamelog,
-            Resistances, RunState, Bleeding, particle_system::ParticleBuilder, Position};
+use super::rng::CombatRng;
+use super::events::{DamageKind, EventQueue, GameEvent};
+use super::{Stats, DamageQueue, DamageAtom, Player, Resistances, RunState, Bleeding};
 
 pub struct DamageSystem {}
 
 impl<'a> System<'a> for DamageSystem {
     type SystemData = ( Entities<'a>,
-                        WriteExpect<'a, ParticleBuilder>,
+                        WriteExpect<'a, EventQueue>,
+                        WriteExpect<'a, CombatRng>,
                         WriteStorage<'a, Stats>,
                         WriteStorage<'a, DamageQueue>,
                         WriteStorage<'a, Bleeding>,
                         ReadStorage<'a, Resistances>,
-                        ReadStorage<'a, Name>,
-                        ReadStorage<'a, Position>,
                       );
 
     fn run (&mut self, data: Self::SystemData) {
-        let (entities, mut particle_builder, mut stats, mut damage_queues, mut bleeding_storage,
-             resistances, names, positions) = data;
+        let (entities, mut events, mut rng, mut stats, mut damage_queues, mut bleeding_storage,
+             resistances) = data;
         
         let mut to_bleed = Vec::<Entity>::new();
-        let mut logger = gamelog::Logger::new();
 
         //Apply resistanes to dmg_queue and dmg_queue to stats.
-        for (ent, name, pos, stats, d_q, res, bleeding) in
-            (&entities, &names, &positions, &mut stats, &mut damage_queues,
+        for (ent, stats, d_q, res, bleeding) in
+            (&entities, &mut stats, &mut damage_queues,
              (&resistances).maybe(), (&bleeding_storage).maybe()).join() {
             
             //If this entity has resistances, apply them to damage_queue
@@ -75,24 +74,20 @@ impl<'a> System<'a> for DamageSystem {
                     }
                 }
                 
-                if !is_bleeding && bleed_roll(dmg) { 
+                if !is_bleeding && bleed_roll(&mut rng, dmg) { 
                     to_bleed.push(ent);
-                    logger.append(format!("{} is bleeding.", &name.name));
-                        
+                    is_bleeding = true;
+                    events.push(GameEvent::BleedingStarted { target: ent });
                 }
             }
            
             if hp_dmg > 0 {
                 stats.hp = max(0, stats.hp - hp_dmg);
-                logger.append(format!("{} suffers {} damage.", &name.name, hp_dmg));
-
-                //spawn particle
-                particle_builder.request(pos.x, pos.y, bracket_lib::prelude::RGB::named(bracket_lib::prelude::ORANGE),
-                    bracket_lib::prelude::RGB::named(bracket_lib::prelude::BLACK), bracket_lib::prelude::to_cp437('‼'), 200.0);
+                events.push(GameEvent::Damaged { target: ent, amount: hp_dmg, kind: DamageKind::Health });
             }
             if fp_dmg > 0 {
                 stats.fp = max(0, stats.fp - fp_dmg);
-                logger.append(format!("{} suffers {} fatigue.", &name.name, fp_dmg));
+                events.push(GameEvent::Damaged { target: ent, amount: fp_dmg, kind: DamageKind::Fatigue });
             }
 
         }
@@ -102,7 +97,6 @@ impl<'a> System<'a> for DamageSystem {
         }
 
         damage_queues.clear();
-        logger.log();
     }
 }
 
@@ -113,18 +107,14 @@ pub fn delete_the_dead(ecs: &mut World) {
         let stats = ecs.read_storage::<Stats>();
         let players = ecs.read_storage::<Player>();
         let entities = ecs.entities();
-        let names = ecs.read_storage::<Name>();
-        let mut logger = gamelog::Logger::new();
+        let mut events = ecs.write_resource::<EventQueue>();
 
         for (entity, stats) in (&entities, &stats).join() {
             if stats.hp < 1 {
                 let player = players.get(entity);
                 match player {
                     None => {
-                        let corpse_name = names.get(entity);
-                        if let Some(corpse_name) = corpse_name {
-                            logger.append(format!("{} has died.", &corpse_name.name));
-                        }
+                        events.push(GameEvent::Died { entity });
                         dead.push(entity)
                     }
                     Some(_) => {
@@ -134,16 +124,17 @@ pub fn delete_the_dead(ecs: &mut World) {
                 }
             }
         }
-        logger.log();
     }
 
+    //Subscribers still need to look the dead up by name.
+    super::events::publish(ecs);
+
     for victim in dead {
         ecs.delete_entity(victim).expect("Unable to delete dead entity.");
     }
 }
 
-fn bleed_roll( dmg: &DamageAtom ) -> bool {
-    let mut rng = RandomNumberGenerator::new();
+fn bleed_roll( rng: &mut RandomNumberGenerator, dmg: &DamageAtom ) -> bool {
     let mut result: bool = false;
     let bleed_range = rng.range(1,10);
     
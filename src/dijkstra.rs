@@ -0,0 +1,185 @@
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
+
+use super::Map;
+
+/* Dijkstra maps and flow fields
+ *
+ * A DijkstraMap holds, for every tile, the cost of the cheapest walk to it
+ * from the nearest of its sources, weighted by terrain the way A* weighs it
+ * (see Map::exits_where()). new() walks the tiles the game says are open:
+ * anything not in Map::blocked, closed doors, which can be opened on the way,
+ * and tiles blocked only by a creature, who will have moved on by then.
+ * over() walks whatever its caller says, for map builders, whose `blocked`
+ * is usually out of date.
+ *
+ * What a map is for depends on its sources:
+ * - the player, for monsters closing in: step downhill();
+ * - the same map turned by flee(), for running away: step downhill() on that;
+ * - the start, for map builders: what's reachable(), and what's farthest();
+ * - every unrevealed tile, for a walker that explores.
+ *
+ * FlowFields is the resource that keeps the maps the AI steers by, one per
+ * Flow, and builds one again only when its sources or the map's terrain
+ * (Map::terrain_version) change. Creatures moving don't count; downhill()
+ * steps around whoever is in the way.
+ */
+
+//The cost of a tile nothing reaches.
+pub const UNREACHABLE: f32 = f32::MAX;
+
+//How much further than the nearest way in a fleeing walker goes looking for a
+//way out; a little over 1 keeps it from cornering itself.
+const FLEE_BIAS: f32 = 1.2;
+
+#[derive(Clone, Debug)]
+pub struct DijkstraMap {
+    pub width: i32,
+    pub height: i32,
+    pub costs: Vec<f32>,
+}
+
+impl DijkstraMap {
+    //From `sources` over the tiles that aren't blocked, creatures aside, as far as `max_cost`.
+    pub fn new(map: &Map, sources: &[usize], max_cost: f32) -> DijkstraMap {
+        DijkstraMap::over(map, sources, max_cost, |idx| map.is_open_terrain(idx))
+    }
+
+    //From `sources` over the tiles `passable` allows, as far as `max_cost`.
+    pub fn over(map: &Map, sources: &[usize], max_cost: f32, passable: impl Fn(usize) -> bool) -> DijkstraMap {
+        let seeds: Vec<(usize, f32)> = sources.iter().map(|idx| (*idx, 0.0)).collect();
+        DijkstraMap::spread(map, &seeds, max_cost, passable)
+    }
+
+    //The map for getting away from this one's sources: downhill on it leads
+    //away from them, around them if need be, rather than into a dead end.
+    pub fn flee(&self, map: &Map) -> DijkstraMap {
+        let seeds: Vec<(usize, f32)> = self.costs.iter().enumerate()
+            .filter(|(_, cost)| **cost != UNREACHABLE)
+            .map(|(idx, cost)| (idx, -cost * FLEE_BIAS))
+            .collect();
+        DijkstraMap::spread(map, &seeds, UNREACHABLE, |idx| map.is_open_terrain(idx))
+    }
+
+    pub fn cost(&self, idx: usize) -> Option<f32> {
+        self.costs.get(idx).copied().filter(|cost| *cost != UNREACHABLE)
+    }
+
+    pub fn reachable(&self, idx: usize) -> bool {
+        self.cost(idx).is_some()
+    }
+
+    //The reachable tile `wanted` allows that's costliest to get to; the first, on a tie.
+    pub fn farthest(&self, wanted: impl Fn(usize) -> bool) -> Option<usize> {
+        let mut farthest: Option<(usize, f32)> = None;
+        for (idx, cost) in self.costs.iter().enumerate() {
+            if *cost != UNREACHABLE && wanted(idx) && farthest.map_or(true, |(_, best)| *cost > best) {
+                farthest = Some((idx, *cost));
+            }
+        }
+        farthest.map(|(idx, _)| idx)
+    }
+
+    //The open tile next to `from` that's cheapest from here, if it's cheaper than
+    //`from`. Tiles someone is standing on aren't open.
+    pub fn downhill(&self, map: &Map, from: usize) -> Option<usize> {
+        let mut best: Option<(usize, f32)> = None;
+        for (idx, _) in map.exits_where(from, |idx| map.is_open(idx)) {
+            if let Some(cost) = self.cost(idx) {
+                if best.map_or(true, |(_, lowest)| cost < lowest) {
+                    best = Some((idx, cost));
+                }
+            }
+        }
+        match (best, self.cost(from)) {
+            (Some((_, cost)), Some(here)) if cost >= here => None,
+            (best, _) => best.map(|(idx, _)| idx),
+        }
+    }
+
+    //Dijkstra's algorithm, from tiles that start at a cost of their own.
+    fn spread(map: &Map, seeds: &[(usize, f32)], max_cost: f32, passable: impl Fn(usize) -> bool) -> DijkstraMap {
+        let mut costs = vec![UNREACHABLE; map.tiles.len()];
+        let mut open = BinaryHeap::new();
+        for (idx, cost) in seeds.iter() {
+            if *idx < costs.len() && *cost < costs[*idx] {
+                costs[*idx] = *cost;
+                open.push(Step { idx: *idx, cost: *cost });
+            }
+        }
+
+        while let Some(Step { idx, cost }) = open.pop() {
+            if cost > costs[idx] {
+                continue; //already got here cheaper
+            }
+            for (next, step_cost) in map.exits_where(idx, &passable) {
+                let next_cost = cost + step_cost;
+                if next_cost < costs[next] && next_cost <= max_cost {
+                    costs[next] = next_cost;
+                    open.push(Step { idx: next, cost: next_cost });
+                }
+            }
+        }
+
+        DijkstraMap { width: map.width, height: map.height, costs }
+    }
+}
+
+//A tile waiting in Dijkstra's queue; the cheapest comes out first, then the lowest index.
+#[derive(PartialEq)]
+struct Step {
+    idx: usize,
+    cost: f32,
+}
+
+impl Eq for Step {}
+
+impl Ord for Step {
+    fn cmp(&self, other: &Step) -> Ordering {
+        other.cost.partial_cmp(&self.cost).unwrap_or(Ordering::Equal).then_with(|| other.idx.cmp(&self.idx))
+    }
+}
+
+impl PartialOrd for Step {
+    fn partial_cmp(&self, other: &Step) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+//What a flow field leads to.
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
+pub enum Flow {
+    ToPlayer,
+    FromPlayer,
+}
+
+struct FlowField {
+    sources: Vec<usize>,
+    terrain_version: u64,
+    map: DijkstraMap,
+}
+
+#[derive(Default)]
+pub struct FlowFields {
+    fields: HashMap<Flow, FlowField>,
+}
+
+impl FlowFields {
+    //The flow field for `flow` from `sources`, built again if they, or the
+    //map's terrain, have changed since it was last asked for.
+    pub fn get(&mut self, flow: Flow, map: &Map, sources: &[usize]) -> &DijkstraMap {
+        let stale = match self.fields.get(&flow) {
+            Some(field) => field.sources != sources || field.terrain_version != map.terrain_version,
+            None => true,
+        };
+        if stale {
+            let toward = DijkstraMap::new(map, sources, UNREACHABLE);
+            let field = match flow {
+                Flow::ToPlayer => toward,
+                Flow::FromPlayer => toward.flee(map),
+            };
+            self.fields.insert(flow, FlowField { sources: sources.to_vec(), terrain_version: map.terrain_version, map: field });
+        }
+        &self.fields[&flow].map
+    }
+}
@@ -0,0 +1,102 @@
+use bracket_lib::prelude::to_cp437;
+use specs::prelude::*;
+
+use super::events::{EventQueue, GameEvent};
+use super::initiative_system::ACTION_COST;
+use super::{BlocksTile, BlocksVisibility, Door, DoorIntent, Energy, InBackpack, Key, Map, Renderable, Viewshed};
+
+/* Doors
+ *
+ * A closed door blocks movement (BlocksTile) and sight (BlocksVisibility); an
+ * open one blocks neither. Bumping into a closed door asks to open it, for the
+ * player (player.rs) and monsters alike (hostile_ai_system.rs), and the Close
+ * command asks to close one next to the player. Either way the asking is a
+ * DoorIntent, which DoorSystem resolves in the Actions stage for the price of
+ * an action.
+ *
+ * A locked door only opens for someone carrying a Key, which unlocks it for
+ * good and is used up. Monsters carry none, so paths go through the doors in
+ * Map::closed_doors, which are only the ones that aren't locked. Opening,
+ * closing or unlocking one changes the way, so the flow fields are rebuilt.
+ */
+
+const OPEN_GLYPH: char = '/';
+const CLOSED_GLYPH: char = '+';
+
+//A key `holder` is carrying, if they have one.
+pub fn key_carried_by(holder: Entity, entities: &Entities, keys: &ReadStorage<Key>,
+                      backpacks: &ReadStorage<InBackpack>) -> Option<Entity> {
+    (entities, keys, backpacks).join()
+        .find(|(_, _, backpack)| backpack.owner == holder)
+        .map(|(key, _, _)| key)
+}
+
+pub struct DoorSystem {}
+
+impl<'a> System<'a> for DoorSystem {
+    #[allow(clippy::type_complexity)]
+    type SystemData = ( Entities<'a>,
+                        WriteExpect<'a, EventQueue>,
+                        WriteStorage<'a, DoorIntent>,
+                        WriteStorage<'a, Door>,
+                        WriteStorage<'a, BlocksTile>,
+                        WriteStorage<'a, BlocksVisibility>,
+                        WriteStorage<'a, Renderable>,
+                        ReadStorage<'a, Key>,
+                        ReadStorage<'a, InBackpack>,
+                        WriteStorage<'a, Viewshed>,
+                        WriteStorage<'a, Energy>,
+                        WriteExpect<'a, Map>,
+                      );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (entities, mut events, mut intents, mut doors, mut blockers, mut sight_blockers,
+             mut renderables, keys, backpacks, mut viewsheds, mut energies, mut map) = data;
+
+        for (actor, intent) in (&entities, &intents).join() {
+            let door = match doors.get_mut(intent.door) {
+                Some(door) if door.open != intent.open => door,
+                _ => continue,
+            };
+
+            if intent.open && door.locked {
+                match key_carried_by(actor, &entities, &keys, &backpacks) {
+                    Some(key) => {
+                        entities.delete(key).expect("Unable to use up a key.");
+                        door.locked = false;
+                        events.push(GameEvent::DoorUnlocked { door: intent.door, by: actor });
+                    }
+                    None => {
+                        //Finding out costs nothing.
+                        events.push(GameEvent::DoorLocked { door: intent.door, by: actor });
+                        continue;
+                    }
+                }
+            }
+
+            door.open = intent.open;
+            if door.open {
+                blockers.remove(intent.door);
+                sight_blockers.remove(intent.door);
+                events.push(GameEvent::DoorOpened { door: intent.door, by: actor });
+            } else {
+                blockers.insert(intent.door, BlocksTile {}).expect("Unable to insert BlocksTile component.");
+                sight_blockers.insert(intent.door, BlocksVisibility {})
+                    .expect("Unable to insert BlocksVisibility component.");
+                events.push(GameEvent::DoorClosed { door: intent.door, by: actor });
+            }
+            if let Some(renderable) = renderables.get_mut(intent.door) {
+                renderable.glyph = to_cp437(if door.open { OPEN_GLYPH } else { CLOSED_GLYPH });
+            }
+            Energy::spend(&mut energies, actor, ACTION_COST);
+            map.terrain_changed();
+
+            //Whoever could see through the doorway, or now can, sees something else.
+            for viewshed in (&mut viewsheds).join() {
+                viewshed.dirty = true;
+            }
+        }
+
+        intents.clear();
+    }
+}
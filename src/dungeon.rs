@@ -0,0 +1,106 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use specs::prelude::*;
+
+use super::{generate_world_map, place_player, Map, OtherLevelPosition, Player, Position, TileType};
+
+/* Dungeon
+ *
+ * Levels persist. Leaving a level puts its Map in the LevelStore under its
+ * depth, and takes everything standing on it out of play: each entity's
+ * Position becomes an OtherLevelPosition with that depth, so no system that
+ * joins on Position sees it. Coming back puts the Map back and turns the
+ * level's OtherLevelPositions back into Positions, exactly as they were left.
+ * Only a depth that has never been visited is generated.
+ *
+ * The player comes along, and so does everything without a Position: what
+ * they carry and what they wear.
+ *
+ * The store holds every visited level but the current one, which is the Map
+ * resource as always. Both are saved with the SerializationHelper.
+ */
+
+#[derive(Default, Serialize, Deserialize, Clone)]
+pub struct LevelStore {
+    levels: BTreeMap<i32, Map>,
+}
+
+impl LevelStore {
+    pub fn contains(&self, depth: i32) -> bool {
+        self.levels.contains_key(&depth)
+    }
+
+    pub fn get(&self, depth: i32) -> Option<&Map> {
+        self.levels.get(&depth)
+    }
+
+    //The stored depths, shallowest first.
+    pub fn depths(&self) -> Vec<i32> {
+        self.levels.keys().copied().collect()
+    }
+}
+
+//Leaves the current level for new_depth, placing the player on the staircase
+//that leads back: the up stairs when going down, the down stairs when going up.
+pub fn change_level(ecs: &mut World, new_depth: i32) {
+    let old_depth = ecs.fetch::<Map>().depth;
+    freeze_level(ecs, old_depth);
+
+    let stored = ecs.write_resource::<LevelStore>().levels.remove(&new_depth);
+    match stored {
+        Some(mut map) => {
+            map.tile_content = vec![Vec::new(); (map.width * map.height) as usize];
+            *ecs.write_resource::<Map>() = map;
+            thaw_level(ecs, new_depth);
+        }
+        None => generate_world_map(ecs, new_depth),
+    }
+
+    let arrival = if new_depth > old_depth { TileType::StairsUp } else { TileType::StairsDown };
+    let stairs = {
+        let map = ecs.fetch::<Map>();
+        map.tiles.iter().position(|tile| *tile == arrival)
+            .map(|idx| (idx as i32 % map.width, idx as i32 / map.width))
+    };
+    //A level without the matching stairs (one generated going up, from a save
+    //that predates the store) keeps the player on its starting position.
+    if let Some((x, y)) = stairs {
+        place_player(ecs, x, y);
+    }
+}
+
+fn freeze_level(ecs: &mut World, depth: i32) {
+    let map = (*ecs.fetch::<Map>()).clone();
+    ecs.write_resource::<LevelStore>().levels.insert(depth, map);
+
+    let entities = ecs.entities();
+    let players = ecs.read_storage::<Player>();
+    let mut positions = ecs.write_storage::<Position>();
+    let mut other_level_positions = ecs.write_storage::<OtherLevelPosition>();
+
+    let leaving: Vec<(Entity, Position)> = (&entities, &positions, !&players).join()
+        .map(|(entity, pos, ())| (entity, *pos))
+        .collect();
+    for (entity, pos) in leaving {
+        positions.remove(entity);
+        other_level_positions
+            .insert(entity, OtherLevelPosition { x: pos.x, y: pos.y, depth })
+            .expect("Unable to insert OtherLevelPosition");
+    }
+}
+
+fn thaw_level(ecs: &mut World, depth: i32) {
+    let entities = ecs.entities();
+    let mut positions = ecs.write_storage::<Position>();
+    let mut other_level_positions = ecs.write_storage::<OtherLevelPosition>();
+
+    let arriving: Vec<(Entity, OtherLevelPosition)> = (&entities, &other_level_positions).join()
+        .filter(|(_, pos)| pos.depth == depth)
+        .map(|(entity, pos)| (entity, *pos))
+        .collect();
+    for (entity, pos) in arriving {
+        other_level_positions.remove(entity);
+        positions.insert(entity, Position { x: pos.x, y: pos.y }).expect("Unable to insert Position");
+    }
+}
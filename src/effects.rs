@@ -0,0 +1,229 @@
+use std::collections::VecDeque;
+
+use bracket_lib::prelude::{field_of_view, to_cp437, FontCharType, Point, BLACK, ORANGE, RGB};
+use specs::prelude::*;
+
+use super::events::{EventQueue, GameEvent};
+use super::particle_system::ParticleBuilder;
+use super::{Confusion, Consumable, DamageAtom, DamageOnUse, DamageQueue, Healing, Heals, MagicMapper,
+            Map, Position, RunState};
+
+/* Effects
+ *
+ * Anything that does something *to* someone (an item being used, a trap going
+ * off, a thrown rock, a sword swing) pushes an EffectRequest onto the
+ * EffectQueue instead of reaching into its targets' components itself.
+ * EffectSystem resolves the whole queue once per dispatch, after the Attacks
+ * stage (see schedule.rs).
+ *
+ * A request is an effect plus a Targets descriptor; the descriptor is turned into
+ * entities against the tile index when the effect is applied.
+ *
+ * EffectType::Use stands for "whatever this entity does when used", and expands
+ * into one effect per effect component it carries (DamageOnUse, Heals,
+ * Confusion, MagicMapper). A new capability is a component plus an arm in
+ * use_effects() and apply(); the systems that queue Use don't change.
+ */
+
+#[derive(PartialEq, Clone, Debug)]
+pub enum EffectType {
+    Damage { atoms: Vec<DamageAtom> },
+    Heal { duration: i32, amount: i32 },
+    Confuse { turns: i32 },
+    MagicMap,
+    Particle { glyph: FontCharType, fg: RGB, bg: RGB, lifetime: f32 },
+    Use { item: Entity },
+}
+
+#[derive(PartialEq, Clone, Debug)]
+pub enum Targets {
+    Creator, //whoever caused the effect
+    Tile { pt: Point },
+    Area { tiles: Vec<Point> },
+    Entities { list: Vec<Entity> },
+}
+
+#[derive(PartialEq, Clone, Debug)]
+pub struct EffectRequest {
+    pub creator: Option<Entity>,
+    pub effect: EffectType,
+    pub targets: Targets,
+}
+
+#[derive(Default)]
+pub struct EffectQueue {
+    requests: VecDeque<EffectRequest>,
+}
+
+impl EffectQueue {
+    pub fn push(&mut self, creator: Option<Entity>, effect: EffectType, targets: Targets) {
+        self.requests.push_back(EffectRequest { creator, effect, targets });
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.requests.is_empty()
+    }
+}
+
+//Tiles caught in a blast of the given radius, centered on (and blocked like) line of sight.
+pub fn area_of_effect(map: &Map, center: Point, radius: i32) -> Vec<Point> {
+    let mut blast_tiles = field_of_view(center, radius, map);
+    blast_tiles.retain(|p| p.x > 0 && p.x < map.width - 1 && p.y > 0 && p.y < map.height - 1);
+    blast_tiles
+}
+
+pub struct EffectSystem {}
+
+impl<'a> System<'a> for EffectSystem {
+    #[allow(clippy::type_complexity)]
+    type SystemData = ( ReadExpect<'a, Map>,
+                        Read<'a, LazyUpdate>,
+                        WriteExpect<'a, EffectQueue>,
+                        WriteExpect<'a, EventQueue>,
+                        WriteExpect<'a, ParticleBuilder>,
+                        WriteExpect<'a, RunState>,
+                        WriteStorage<'a, DamageQueue>,
+                        WriteStorage<'a, Healing>,
+                        WriteStorage<'a, Confusion>,
+                        ReadStorage<'a, Position>,
+                        ReadStorage<'a, DamageOnUse>,
+                        ReadStorage<'a, Heals>,
+                        ReadStorage<'a, MagicMapper>,
+                        ReadStorage<'a, Consumable>,
+                      );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (map, lazy, mut queue, mut events, mut particle_builder, mut runstate,
+             mut damage_queues, mut healing, mut confusion, positions, damage_on_use, heals,
+             magic_mappers, consumables) = data;
+
+        while let Some(request) = queue.requests.pop_front() {
+            let targets = resolve_targets(&map, &request);
+
+            match &request.effect {
+                EffectType::Use { item } => {
+                    let item = *item;
+                    let effects = use_effects(item, &damage_on_use, &heals, &confusion, &magic_mappers);
+
+                    if let Targets::Area { tiles } = &request.targets {
+                        for pt in tiles.iter() {
+                            particle_builder.request(pt.x, pt.y, RGB::named(ORANGE), RGB::named(BLACK),
+                                to_cp437('░'), 200.0);
+                        }
+                    }
+
+                    if let Some(user) = request.creator {
+                        for effect in effects.iter() {
+                            for target in targets.iter() {
+                                match effect {
+                                    EffectType::Damage { .. } => {
+                                        events.push(GameEvent::ItemUsed { item, user, target: *target })
+                                    }
+                                    EffectType::Confuse { .. } => {
+                                        events.push(GameEvent::Confused { item, user, target: *target })
+                                    }
+                                    _ => {}
+                                }
+                            }
+                        }
+                    }
+
+                    //Expanded effects go on the queue, so they resolve in this same run.
+                    for effect in effects {
+                        queue.push(request.creator, effect, Targets::Entities { list: targets.clone() });
+                    }
+
+                    //Consumables are used up once they affect anything; nothing to hit, nothing spent.
+                    if !targets.is_empty() && consumables.get(item).is_some() {
+                        lazy.exec_mut(move |ecs| {
+                            ecs.delete_entity(item).expect("Delete failed.");
+                        });
+                    }
+                }
+                EffectType::Particle { glyph, fg, bg, lifetime } => {
+                    for pt in target_tiles(&positions, &request) {
+                        particle_builder.request(pt.x, pt.y, *fg, *bg, *glyph, *lifetime);
+                    }
+                }
+                EffectType::MagicMap => {
+                    *runstate = RunState::MagicMapReveal { row: 0 };
+                    if let Some(user) = request.creator {
+                        events.push(GameEvent::MagicMapped { user });
+                    }
+                }
+                effect => apply(effect, &targets, &mut damage_queues, &mut healing, &mut confusion),
+            }
+        }
+    }
+}
+
+fn resolve_targets(map: &Map, request: &EffectRequest) -> Vec<Entity> {
+    let on_tile = |pt: &Point| {
+        if pt.x < 0 || pt.x >= map.width || pt.y < 0 || pt.y >= map.height {
+            return Vec::new();
+        }
+        map.tile_content[map.xy_idx(pt.x, pt.y)].clone()
+    };
+
+    match &request.targets {
+        Targets::Creator => request.creator.into_iter().collect(),
+        Targets::Tile { pt } => on_tile(pt),
+        Targets::Area { tiles } => tiles.iter().flat_map(on_tile).collect(),
+        Targets::Entities { list } => list.clone(),
+    }
+}
+
+//Particles go where they're aimed, whether or not anyone is standing there.
+fn target_tiles(positions: &ReadStorage<Position>, request: &EffectRequest) -> Vec<Point> {
+    let at = |e: &Entity| positions.get(*e).map(|pos| Point::new(pos.x, pos.y));
+    match &request.targets {
+        Targets::Creator => request.creator.iter().filter_map(at).collect(),
+        Targets::Tile { pt } => vec![*pt],
+        Targets::Area { tiles } => tiles.clone(),
+        Targets::Entities { list } => list.iter().filter_map(at).collect(),
+    }
+}
+
+//One effect per effect component on the item, in a fixed order.
+fn use_effects(item: Entity, damage_on_use: &ReadStorage<DamageOnUse>, heals: &ReadStorage<Heals>,
+               confusion: &WriteStorage<Confusion>, magic_mappers: &ReadStorage<MagicMapper>) -> Vec<EffectType> {
+    let mut effects = Vec::new();
+    if let Some(d) = damage_on_use.get(item) {
+        effects.push(EffectType::Damage { atoms: d.dmg_atoms.clone() });
+    }
+    if let Some(h) = heals.get(item) {
+        effects.push(EffectType::Heal { duration: h.duration, amount: h.amount });
+    }
+    if let Some(c) = confusion.get(item) {
+        effects.push(EffectType::Confuse { turns: c.turns });
+    }
+    if magic_mappers.get(item).is_some() {
+        effects.push(EffectType::MagicMap);
+    }
+    effects
+}
+
+fn apply(effect: &EffectType, targets: &[Entity], damage_queues: &mut WriteStorage<DamageQueue>,
+         healing: &mut WriteStorage<Healing>, confusion: &mut WriteStorage<Confusion>) {
+    match effect {
+        EffectType::Damage { atoms } => {
+            for target in targets.iter() {
+                for atom in atoms.iter() {
+                    DamageQueue::queue_damage(damage_queues, *target, *atom);
+                }
+            }
+        }
+        EffectType::Heal { duration, amount } => {
+            for target in targets.iter() {
+                healing.insert(*target, Healing { duration: *duration, amount: *amount })
+                    .expect("Unable to insert Healing component.");
+            }
+        }
+        EffectType::Confuse { turns } => {
+            for target in targets.iter() {
+                confusion.insert(*target, Confusion { turns: *turns }).expect("Unable to insert status.");
+            }
+        }
+        EffectType::MagicMap | EffectType::Particle { .. } | EffectType::Use { .. } => {} //handled by EffectSystem
+    }
+}
@@ -1,11 +1,14 @@
 use specs::prelude::*;
 use super::{EquipIntent, UnequipIntent, InBackpack, Equippable, Equipped, Weapon, BasicAttack,
-            Resistances, Name, gui::gamelog, Creature, Position};
+            Resistances, Creature, Position, EquipmentSlot, Energy,
+            events::{EventQueue, GameEvent}, initiative_system::{EQUIP_COST, EQUIP_ARMOR_COST}};
 
 pub struct EquipSystem {}
 
 impl<'a> System<'a> for EquipSystem {
+    #[allow(clippy::type_complexity)]
     type SystemData = ( Entities<'a>,
+                        WriteExpect<'a, EventQueue>,
                         WriteStorage<'a, Equipped>,
                         WriteStorage<'a, EquipIntent>,
                         WriteStorage<'a, UnequipIntent>,
@@ -15,15 +18,13 @@ impl<'a> System<'a> for EquipSystem {
                         WriteStorage<'a, Position>,
                         ReadStorage<'a, Equippable>,
                         ReadStorage<'a, Weapon>,
-                        ReadStorage<'a, Name>,
                         ReadStorage<'a, Creature>,
+                        WriteStorage<'a, Energy>,
                       );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (entities, mut equipped, mut equip_intents, mut unequip_intents, mut in_backpack,
-            mut basic_attacks, mut resistances, mut positions, equippables, weapons, names, creature) = data;
-
-        let mut logger = gamelog::Logger::new();
+        let (entities, mut events, mut equipped, mut equip_intents, mut unequip_intents, mut in_backpack,
+            mut basic_attacks, mut resistances, mut positions, equippables, weapons, creature, mut energies) = data;
 
         //Equipping Logic:
         //This join will iterate over all living creatures.
@@ -34,6 +35,12 @@ impl<'a> System<'a> for EquipSystem {
             if let Some(intent) = equip_intent {
                 let ent_to_equip : Entity = intent.item;
                 let target_slot = equippables.get(ent_to_equip).unwrap().slot; 
+                let cost = match target_slot {
+                    EquipmentSlot::Helm | EquipmentSlot::Armor |
+                    EquipmentSlot::Boots | EquipmentSlot::Gloves => EQUIP_ARMOR_COST,
+                    _ => EQUIP_COST,
+                };
+                Energy::spend(&mut energies, owner, cost);
                 let mut ent_to_unequip = None;
                 
                 //For each equipped equipment...
@@ -63,8 +70,7 @@ impl<'a> System<'a> for EquipSystem {
                 positions.remove(ent_to_equip);
                 equipped.insert(ent_to_equip, Equipped {owner: owner, slot: target_slot})
                         .expect("Unable to insert Equipped component.");
-                logger.append(format!("{} equipped {}.",
-                        names.get(owner).unwrap().name, names.get(ent_to_equip).unwrap().name));
+                events.push(GameEvent::Equipped { item: ent_to_equip, owner });
 
                 //if equipped entity has a Weapon component...
                 if let Some(w) = weapons.get(ent_to_equip) {// w == weapon
@@ -103,8 +109,7 @@ impl<'a> System<'a> for EquipSystem {
                         .expect("Unable to insert InBackpack component.");
                 }
                 
-                logger.append(format!("{} unequipped {}.",
-                        names.get(owner).unwrap().name, names.get(ent).unwrap().name));
+                events.push(GameEvent::Unequipped { item: ent, owner });
 
                 //if unequipped entity has a Resistance component...
                 if let Some(resists_to_remove) = resistances.get(ent) {
@@ -121,6 +126,5 @@ impl<'a> System<'a> for EquipSystem {
         
         unequip_intents.clear();
         equip_intents.clear();
-        logger.log();
     }
 }
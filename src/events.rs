@@ -0,0 +1,382 @@
+use bracket_lib::prelude::{to_cp437, BLACK, KHAKI, ORANGE, RGB, WHITE};
+use specs::prelude::*;
+
+use super::gui::gamelog;
+use super::particle_system::ParticleBuilder;
+use super::{HungerState, Map, Name, Player, Position, TileType};
+
+/* Game Events
+ *
+ * Systems don't talk to the game log (or the particle spawner, or anything
+ * else that merely reacts to gameplay) directly. They push a GameEvent onto
+ * the EventQueue resource, and after each run of the schedule publish() hands
+ * every queued event, in order, to each Subscriber.
+ *
+ * Subscribers registered by new_world():
+ * - LogSubscriber          English text for the game log
+ * - ParticleSubscriber     hit sparks
+ * - StatisticsSubscriber   keeps the Statistics resource
+ * - AchievementSubscriber  keeps the Achievements resource; runs after Statistics
+ *
+ * Systems take the queue as WriteExpect rather than sharing it, so events come
+ * out in the same order on every run (see rng.rs for why that matters).
+ *
+ * Events name entities, not strings. Subscribers read whatever they need from
+ * the World when they are notified, so anything an event refers to must still
+ * be alive at publish() time: deletions wait for maintain(), or for
+ * delete_the_dead(), both of which come after.
+ */
+
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum DamageKind {
+    Health,
+    Fatigue,
+}
+
+#[derive(PartialEq, Clone, Debug)]
+pub enum GameEvent {
+    Damaged { target: Entity, amount: i32, kind: DamageKind },
+    BleedingStarted { target: Entity },
+    BleedingStopped { target: Entity },
+    Healed { target: Entity, amount: i32 },
+    Died { entity: Entity },
+    HungerChanged { entity: Entity, from: HungerState, to: HungerState },
+    ItemPickedUp { item: Entity, by: Entity },
+    ItemDropped { item: Entity, by: Entity },
+    ItemUsed { item: Entity, user: Entity, target: Entity },
+    Confused { item: Entity, user: Entity, target: Entity },
+    MagicMapped { user: Entity },
+    Equipped { item: Entity, owner: Entity },
+    Unequipped { item: Entity, owner: Entity },
+    Thrown { item: Entity, thrower: Entity, target: Option<Entity> },
+    TrapTriggered { trap: Entity, victim: Entity },
+    DoorOpened { door: Entity, by: Entity },
+    DoorClosed { door: Entity, by: Entity },
+    DoorUnlocked { door: Entity, by: Entity },
+    DoorLocked { door: Entity, by: Entity }, //tried to open it without a key
+    Spotted { entity: Entity },
+    SecretFound { by: Entity, secret: TileType }, //the tile it was; it's floor now
+    AchievementUnlocked { achievement: Achievement },
+}
+
+#[derive(Default)]
+pub struct EventQueue {
+    events: Vec<GameEvent>,
+}
+
+impl EventQueue {
+    pub fn push(&mut self, event: GameEvent) {
+        self.events.push(event);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+}
+
+pub trait Subscriber: Send + Sync {
+    fn notify(&mut self, ecs: &World, event: &GameEvent);
+    fn flush(&mut self, _ecs: &World) { /*optional; called once per publish()*/ }
+}
+
+#[derive(Default)]
+pub struct Subscribers {
+    list: Vec<Box<dyn Subscriber>>,
+}
+
+impl Subscribers {
+    pub fn add(&mut self, subscriber: Box<dyn Subscriber>) {
+        self.list.push(subscriber);
+    }
+}
+
+//Subscribers may queue events of their own (e.g. achievements); those are
+//published in a follow-up round, up to this many.
+const MAX_PUBLISH_ROUNDS: usize = 4;
+
+pub fn publish(ecs: &World) {
+    for _ in 0..MAX_PUBLISH_ROUNDS {
+        let events = std::mem::take(&mut ecs.fetch_mut::<EventQueue>().events);
+        if events.is_empty() {
+            return;
+        }
+
+        let mut subscribers = ecs.fetch_mut::<Subscribers>();
+        for event in events.iter() {
+            for subscriber in subscribers.list.iter_mut() {
+                subscriber.notify(ecs, event);
+            }
+        }
+        for subscriber in subscribers.list.iter_mut() {
+            subscriber.flush(ecs);
+        }
+    }
+}
+
+pub fn insert_event_resources(ecs: &mut World) {
+    let mut subscribers = Subscribers::default();
+    subscribers.add(Box::new(LogSubscriber::default()));
+    subscribers.add(Box::new(ParticleSubscriber {}));
+    subscribers.add(Box::new(StatisticsSubscriber {}));
+    subscribers.add(Box::new(AchievementSubscriber {}));
+
+    ecs.insert(EventQueue::default());
+    ecs.insert(subscribers);
+    ecs.insert(Statistics::default());
+    ecs.insert(Achievements::default());
+}
+
+//-----------------------------------------------------------------
+//---------------------------- Game Log ---------------------------
+//-----------------------------------------------------------------
+//Everything published together becomes one log entry.
+#[derive(Default)]
+pub struct LogSubscriber {
+    lines: Vec<(String, (u8, u8, u8))>,
+}
+
+impl LogSubscriber {
+    fn append<T: ToString>(&mut self, text: T) {
+        self.lines.push((text.to_string(), WHITE));
+    }
+}
+
+impl Subscriber for LogSubscriber {
+    fn notify(&mut self, ecs: &World, event: &GameEvent) {
+        let names = ecs.read_storage::<Name>();
+        let player = *ecs.fetch::<Entity>();
+        let name = |e: &Entity| names.get(*e).map_or("Something".to_string(), |n| n.name.clone());
+        let positions = ecs.read_storage::<Position>();
+        let map = ecs.fetch::<Map>();
+        let seen = |e: &Entity| positions.get(*e).is_some_and(|pos| map.visible_tiles[map.xy_idx(pos.x, pos.y)]);
+
+        match event {
+            GameEvent::Damaged { target, amount, kind: DamageKind::Health } => {
+                self.append(format!("{} suffers {} damage.", name(target), amount))
+            }
+            GameEvent::Damaged { target, amount, kind: DamageKind::Fatigue } => {
+                self.append(format!("{} suffers {} fatigue.", name(target), amount))
+            }
+            GameEvent::BleedingStarted { target } => self.append(format!("{} is bleeding.", name(target))),
+            GameEvent::BleedingStopped { target } => self.append(format!("{} stopped bleeding.", name(target))),
+            GameEvent::Healed { target, amount } => self.append(format!("{} gained {} HP.", name(target), amount)),
+            GameEvent::Died { entity } => {
+                if names.get(*entity).is_some() {
+                    self.append(format!("{} has died.", name(entity)));
+                }
+            }
+            GameEvent::HungerChanged { entity, from, to } => {
+                if *entity == player {
+                    match (from, to) {
+                        (_, HungerState::Hungry) => self.append("Your belly grumbles."),
+                        (_, HungerState::Famished) => self.append("You are famished!"),
+                        (HungerState::Starving, HungerState::Starving) => self.append("You are starving to death."),
+                        (_, HungerState::Starving) => self.append("You are starving."),
+                        _ => {}
+                    }
+                }
+            }
+            GameEvent::ItemPickedUp { item, by } => {
+                if *by == player {
+                    self.append(format!("{} placed into inventory.", name(item)));
+                }
+            }
+            GameEvent::ItemDropped { item, by } => {
+                if *by == player {
+                    self.append(format!("{} dropped.", name(item)));
+                }
+            }
+            GameEvent::ItemUsed { item, user, target } => {
+                if *user == player {
+                    self.append(format!("Used {} on {}.", name(item), name(target)));
+                }
+            }
+            GameEvent::Confused { item, user, target } => {
+                if *user == player {
+                    self.append(format!("Used {} on {}, they are confused!", name(item), name(target)));
+                }
+            }
+            GameEvent::MagicMapped { .. } => self.append("The Witness is within you."),
+            GameEvent::Equipped { item, owner } => self.append(format!("{} equipped {}.", name(owner), name(item))),
+            GameEvent::Unequipped { item, owner } => {
+                self.append(format!("{} unequipped {}.", name(owner), name(item)))
+            }
+            GameEvent::Thrown { item, thrower, target: Some(target) } => {
+                self.append(format!("{} threw a {} at {}.", name(thrower), name(item), name(target)))
+            }
+            GameEvent::Thrown { item, thrower, target: None } => {
+                self.append(format!("{} threw a {}.", name(thrower), name(item)))
+            }
+            GameEvent::TrapTriggered { trap, .. } => {
+                if names.get(*trap).is_some() {
+                    self.append(format!("Triggered a {}!", name(trap)));
+                }
+            }
+            GameEvent::DoorOpened { door, by } => {
+                if *by == player || seen(door) {
+                    self.append(format!("{} opened the {}.", name(by), name(door)));
+                }
+            }
+            GameEvent::DoorClosed { door, by } => {
+                if *by == player || seen(door) {
+                    self.append(format!("{} closed the {}.", name(by), name(door)));
+                }
+            }
+            GameEvent::DoorUnlocked { door, by } => {
+                self.append(format!("{} unlocked the {} with a key.", name(by), name(door)))
+            }
+            GameEvent::DoorLocked { door, by } => {
+                if *by == player {
+                    self.append(format!("The {} is locked.", name(door)));
+                }
+            }
+            GameEvent::Spotted { entity } => {
+                if names.get(*entity).is_some() {
+                    self.append(format!("{} spotted!", name(entity)));
+                }
+            }
+            GameEvent::SecretFound { by, secret } => {
+                if *by == player {
+                    match secret {
+                        TileType::SecretDoor => self.append("You find a secret door!".to_string()),
+                        _ => self.append("You find a hidden passage!".to_string()),
+                    }
+                }
+            }
+            GameEvent::AchievementUnlocked { achievement } => {
+                self.lines.push((format!("Achievement: {}!", achievement.title()), KHAKI));
+            }
+        }
+    }
+
+    fn flush(&mut self, _ecs: &World) {
+        if self.lines.is_empty() {
+            return;
+        }
+        let mut logger = gamelog::Logger::new();
+        for (text, color) in self.lines.drain(..) {
+            logger.color(color);
+            logger.append(text);
+        }
+        logger.log();
+    }
+}
+
+//-----------------------------------------------------------------
+//---------------------------- Particles --------------------------
+//-----------------------------------------------------------------
+pub struct ParticleSubscriber {}
+
+impl Subscriber for ParticleSubscriber {
+    fn notify(&mut self, ecs: &World, event: &GameEvent) {
+        if let GameEvent::Damaged { target, kind: DamageKind::Health, .. } = event {
+            if let Some(pos) = ecs.read_storage::<Position>().get(*target) {
+                ecs.fetch_mut::<ParticleBuilder>().request(pos.x, pos.y, RGB::named(ORANGE),
+                    RGB::named(BLACK), to_cp437('‼'), 200.0);
+            }
+        }
+    }
+}
+
+//-----------------------------------------------------------------
+//---------------------------- Statistics -------------------------
+//-----------------------------------------------------------------
+//Running totals for the current run, from the player's point of view.
+#[derive(Default, PartialEq, Clone, Debug)]
+pub struct Statistics {
+    pub kills: u32, //monsters that died, by whatever means
+    pub damage_dealt: i32, //health damage taken by anything but the player
+    pub damage_taken: i32,
+    pub items_picked_up: u32,
+    pub items_used: u32,
+    pub traps_triggered: u32,
+}
+
+pub struct StatisticsSubscriber {}
+
+impl Subscriber for StatisticsSubscriber {
+    fn notify(&mut self, ecs: &World, event: &GameEvent) {
+        let player = *ecs.fetch::<Entity>();
+        let is_player = |e: &Entity| *e == player || ecs.read_storage::<Player>().get(*e).is_some();
+        let mut stats = ecs.fetch_mut::<Statistics>();
+
+        match event {
+            GameEvent::Damaged { target, amount, kind: DamageKind::Health } => {
+                if is_player(target) {
+                    stats.damage_taken += amount;
+                } else {
+                    stats.damage_dealt += amount;
+                }
+            }
+            GameEvent::Died { entity } if !is_player(entity) => stats.kills += 1,
+            GameEvent::ItemPickedUp { by, .. } if is_player(by) => stats.items_picked_up += 1,
+            GameEvent::ItemUsed { user, .. } | GameEvent::Confused { user, .. } | GameEvent::MagicMapped { user }
+                if is_player(user) => stats.items_used += 1,
+            GameEvent::TrapTriggered { victim, .. } if is_player(victim) => stats.traps_triggered += 1,
+            _ => {}
+        }
+    }
+}
+
+//-----------------------------------------------------------------
+//--------------------------- Achievements ------------------------
+//-----------------------------------------------------------------
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum Achievement {
+    FirstBlood,
+    Exterminator,
+    PackRat,
+    WatchYourStep,
+}
+
+impl Achievement {
+    pub fn title(&self) -> &'static str {
+        match self {
+            Achievement::FirstBlood => "First Blood",
+            Achievement::Exterminator => "Exterminator",
+            Achievement::PackRat => "Pack Rat",
+            Achievement::WatchYourStep => "Watch Your Step",
+        }
+    }
+
+    fn is_earned(&self, stats: &Statistics) -> bool {
+        match self {
+            Achievement::FirstBlood => stats.kills >= 1,
+            Achievement::Exterminator => stats.kills >= 25,
+            Achievement::PackRat => stats.items_picked_up >= 10,
+            Achievement::WatchYourStep => stats.traps_triggered >= 3,
+        }
+    }
+}
+
+const ALL_ACHIEVEMENTS: [Achievement; 4] = [
+    Achievement::FirstBlood,
+    Achievement::Exterminator,
+    Achievement::PackRat,
+    Achievement::WatchYourStep,
+];
+
+#[derive(Default, PartialEq, Clone, Debug)]
+pub struct Achievements {
+    pub unlocked: Vec<Achievement>,
+}
+
+pub struct AchievementSubscriber {}
+
+impl Subscriber for AchievementSubscriber {
+    fn notify(&mut self, _ecs: &World, _event: &GameEvent) {}
+
+    //Checked once per publish, after StatisticsSubscriber has counted everything.
+    fn flush(&mut self, ecs: &World) {
+        let stats = ecs.fetch::<Statistics>();
+        let mut achievements = ecs.fetch_mut::<Achievements>();
+        let mut queue = ecs.fetch_mut::<EventQueue>();
+
+        for achievement in ALL_ACHIEVEMENTS.iter() {
+            if !achievements.unlocked.contains(achievement) && achievement.is_earned(&stats) {
+                achievements.unlocked.push(*achievement);
+                queue.push(GameEvent::AchievementUnlocked { achievement: *achievement });
+            }
+        }
+    }
+}
@@ -5,28 +5,24 @@
 //Many Observers per Subject/Observable
 
 use std::any::Any;
-use std::collections::HashSet;
 use std::sync::{Arc, Mutex};
 
 pub struct IdGenerator {
-    used_ids: Mutex<HashSet<usize>>,
+    next_id: Mutex<usize>,
 }
 impl IdGenerator {
     pub fn new() -> Self {
         IdGenerator {
-            used_ids: Mutex::new(HashSet::new()),
+            next_id: Mutex::new(0),
         }
     }
 
     //Guaranteed to return a unique usize for this session.
+    //Sequential rather than random, so a seeded run hands out the same ids every time.
     pub fn generate_observer_id(&self) -> usize {
-        let mut new_id: usize = rand::random();
-        if let Ok(mut used_ids) = self.used_ids.lock() {
-            while used_ids.contains(&new_id) {
-                new_id = rand::random();
-            }
-            used_ids.insert(new_id);
-        }
+        let mut next_id = self.next_id.lock().unwrap();
+        let new_id = *next_id;
+        *next_id += 1;
         new_id
     }
 }
@@ -0,0 +1,58 @@
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bracket_lib::prelude::{BTerm, Point, RGB, GRAY, RED};
+
+use super::super::Widget;
+use super::super::super::UserInput;
+use super::super::super::super::saveload_system::SaveSlot;
+
+//Lists the given save slots, in order, then "Back", then `error` if there is
+//one; selection i for i < slots.len() is slots[i], anything after is "Back".
+//returns the observer_id of the widget, to allow for needed mutations
+//to UserInput, such as making this newly constructed widget the Focus observer.
+pub fn construct(ctx: &BTerm, user_input: &Arc<UserInput>, slots: &[SaveSlot], error: Option<&str>) -> usize {
+    let (x_chars, y_chars) = ctx.get_char_size();
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+
+    let lines: Vec<(String, bool)> = slots.iter()
+        .map(|slot| match &slot.meta {
+            Ok(meta) => (format!("{}, depth {}, turn {} ({})", meta.character, meta.depth, meta.turn,
+                                 age(now.saturating_sub(meta.timestamp))), true),
+            Err(_) => (format!("{} (unreadable)", slot.name), false),
+        })
+        .collect();
+
+    let longest = lines.iter().map(|(line, _)| line.chars().count())
+        .chain(error.map(|e| e.chars().count()))
+        .max().unwrap_or(0).max(4);
+    let width = i32::min(longest as i32 + 2, x_chars as i32);
+    let height = lines.len() as i32 + if error.is_some() { 4 } else { 3 };
+
+    let mut load_menu = Widget::new("LoadMenu",
+                Point { x: (x_chars as i32 - width) / 2, y: (y_chars as i32 - height) / 2 },
+                Point { x: width, y: height },
+                user_input,
+    );
+    for (line, readable) in lines {
+        if readable {
+            load_menu.with(line);
+        } else {
+            load_menu.with_color(line, RGB::named(GRAY));
+        }
+    }
+    load_menu.with("Back");
+    if let Some(error) = error {
+        load_menu.with_color(error, RGB::named(RED));
+    }
+    load_menu.build()
+}
+
+fn age(seconds: u64) -> String {
+    match seconds {
+        0..=59 => "just now".to_string(),
+        60..=3599 => format!("{}m ago", seconds / 60),
+        3600..=86399 => format!("{}h ago", seconds / 3600),
+        _ => format!("{}d ago", seconds / 86400),
+    }
+}
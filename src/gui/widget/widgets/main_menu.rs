@@ -1,13 +1,14 @@
 use std::sync::Arc;
 
-use bracket_lib::prelude::{BTerm, Point};
+use bracket_lib::prelude::{BTerm, Point, RGB, GRAY};
 
 use super::super::Widget;
 use super::super::super::UserInput;
 
 //returns the observer_id of the widget, to allow for needed mutations
 //to UserInput, such as making this newly constructed widget the Focus observer.
-pub fn construct(ctx: &BTerm, user_input: &Arc<UserInput>) -> usize {
+//"Load Game" is greyed out when there's nothing to load.
+pub fn construct(ctx: &BTerm, user_input: &Arc<UserInput>, can_load: bool) -> usize {
 
     let (x_chars, y_chars) = ctx.get_char_size();
     let center_x = (x_chars / 2) as i32;
@@ -19,7 +20,11 @@ pub fn construct(ctx: &BTerm, user_input: &Arc<UserInput>) -> usize {
                 &user_input,
     );
     main_menu.with("New Game");
-    main_menu.with("Load Game");
+    if can_load {
+        main_menu.with("Load Game");
+    } else {
+        main_menu.with_color("Load Game", RGB::named(GRAY));
+    }
     main_menu.with("Quit Game");
     let id: usize = main_menu.build();
 
@@ -1,4 +1,5 @@
 
 pub mod game_over;
+pub mod load_menu;
 pub mod main_menu;
 pub mod player_stats;
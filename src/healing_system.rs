@@ -1,6 +1,6 @@
 use specs::prelude::*;
 use std::cmp::{min};
-use super::{Name, gui::gamelog, Stats, Healing, Bleeding};
+use super::{Stats, Healing, Bleeding, initiative_system::GameClock, events::{EventQueue, GameEvent}};
 //use bracket_lib::prelude::{console};
 
 pub struct HealingSystem {}
@@ -8,30 +8,33 @@ pub struct HealingSystem {}
 impl<'a> System<'a> for HealingSystem {
     #[allow(clippy::type_complexity)]
     type SystemData = ( Entities<'a>,
+                        ReadExpect<'a, GameClock>,
+                        WriteExpect<'a, EventQueue>,
                         WriteStorage<'a, Healing>,
                         WriteStorage<'a, Stats>,
                         WriteStorage<'a, Bleeding>,
-                        ReadStorage<'a, Name>,
                       );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (entities, mut healing_storage, mut stats, mut bleeding_storage, names) = data;
+        let (entities, clock, mut events, mut healing_storage, mut stats, mut bleeding_storage) = data;
+
+        //Healing over time, like bleeding, is per turn of game time.
+        if !clock.is_new_turn() { return; }
 
         let mut remove_bleed = Vec::<Entity>::new();
-        let mut logger = gamelog::Logger::new();
 
-        for (ent, mut healing, mut stats, name, bleeding) in
-            (&entities, &mut healing_storage, &mut stats, &names, (&bleeding_storage).maybe()).join() {
+        for (ent, mut healing, mut stats, bleeding) in
+            (&entities, &mut healing_storage, &mut stats, (&bleeding_storage).maybe()).join() {
             
             if healing.duration > 0 {
                 healing.duration -= 1;
                 
                 if let Some(_) = bleeding {
                     remove_bleed.push(ent);
-                    logger.append(format!("{} stopped bleeding.", &name.name));
+                    events.push(GameEvent::BleedingStopped { target: ent });
                 } else {
                     stats.hp = min(stats.max_hp, stats.hp + healing.amount);
-                    logger.append(format!("{} gained {} HP.", &name.name, healing.amount));
+                    events.push(GameEvent::Healed { target: ent, amount: healing.amount });
                     if healing.duration <= 0 { remove_bleed.push(ent); }
                 }
             }
@@ -41,7 +44,5 @@ impl<'a> System<'a> for HealingSystem {
         for e in remove_bleed.iter() {
             bleeding_storage.remove(*e);
         }
-
-        logger.log();
     }
 }
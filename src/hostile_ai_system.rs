@@ -1,5 +1,6 @@
 use specs::prelude::*;
-use super::{Viewshed, Hostile, Map, Position, MeleeIntent, RunState, Confusion, JustMoved};
+use super::{Viewshed, Hostile, Map, Position, MeleeIntent, Confusion, JustMoved, Energy, Door, DoorIntent, Stats,
+            dijkstra::{Flow, FlowFields}, initiative_system::{move_cost, GameClock, ACTION_COST}};
 use bracket_lib::prelude::Point;
 
 pub struct HostileAI {}
@@ -9,7 +10,8 @@ impl<'a> System<'a> for HostileAI { // 'a syntax is var name for a "lifetime"
     type SystemData = ( WriteExpect<'a, Map>,
                         ReadExpect<'a, Point>,
                         ReadExpect<'a, Entity>,
-                        ReadExpect<'a, RunState>,
+                        ReadExpect<'a, GameClock>,
+                        WriteExpect<'a, FlowFields>,
                         Entities<'a>,
                         WriteStorage<'a, Viewshed>,
                         ReadStorage<'a, Hostile>,
@@ -17,16 +19,31 @@ impl<'a> System<'a> for HostileAI { // 'a syntax is var name for a "lifetime"
                         WriteStorage<'a, MeleeIntent>,
                         WriteStorage<'a, Confusion>,
                         WriteStorage<'a, JustMoved>,
+                        WriteStorage<'a, Energy>,
+                        ReadStorage<'a, Door>,
+                        WriteStorage<'a, DoorIntent>,
+                        ReadStorage<'a, Stats>,
                       );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (mut map, player_pos, player_entity, runstate, entities,
-             mut viewshed, hostile, mut position, mut melee_intent, mut confusion, mut moved_storage) = data;
-       
-        if *runstate != RunState::GameworldTurn { return; }
+        let (mut map, player_pos, player_entity, clock, mut flow_fields, entities,
+             mut viewshed, hostile, mut position, mut melee_intent, mut confusion, mut moved_storage, mut energies,
+             doors, mut door_intents, stats) = data;
 
+        //Only scheduled in the GameworldTurn; see schedule.rs.
         for (entity, mut viewshed, _hostile, mut pos) in (&entities, &mut viewshed, &hostile, &mut position).join() { 
+            //Not enough energy banked yet; wait for a later tick.
+            let ready = match energies.get(entity) {
+                Some(energy) => energy.current >= ACTION_COST,
+                None => clock.is_new_turn(),
+            };
+            if !ready { continue; }
+
+            //Attacking is paid for by MeleeCombatSystem and opening doors by
+            //DoorSystem; moving, idling and being confused are paid for here.
+            let mut paid_elsewhere = false;
             let mut can_act = true;
+            let mut cost = ACTION_COST;
 
             let is_confused = confusion.get_mut(entity);
             if let Some(confused_creature) = is_confused {
@@ -38,30 +55,54 @@ impl<'a> System<'a> for HostileAI { // 'a syntax is var name for a "lifetime"
             }
 
             if can_act {
+                //Close in on the player, or, badly hurt, get away from them; either
+                //way by the flow fields, which all hostiles share (see dijkstra.rs).
                 let distance = bracket_lib::prelude::DistanceAlg::Pythagoras.distance2d(Point::new(pos.x, pos.y), *player_pos);
-                if distance <= 1.5 {
-                    melee_intent.insert(entity, MeleeIntent{ target: *player_entity })
-                        .expect("Uname to insert attack.");
-                } else if viewshed.visible_tiles.contains(&*player_pos) {
-                    let path = bracket_lib::prelude::a_star_search( //path to player
-                        map.xy_idx(pos.x, pos.y),
-                        map.xy_idx(player_pos.x, player_pos.y),
-                        &*map
-                    );
+                let fleeing = stats.get(entity).is_some_and(|stats| stats.hp * 4 <= stats.max_hp);
+                let flow = if fleeing { Some(Flow::FromPlayer) } else if distance > 1.5 { Some(Flow::ToPlayer) } else { None };
+                let here = map.xy_idx(pos.x, pos.y);
+                let step = match flow {
+                    Some(flow) if viewshed.visible_tiles.contains(&*player_pos) => {
+                        let player_idx = map.xy_idx(player_pos.x, player_pos.y);
+                        flow_fields.get(flow, &map, &[player_idx]).downhill(&map, here)
+                    }
+                    _ => None,
+                };
 
-                    if path.success && path.steps.len() > 1 {
-                        let mut idx = map.xy_idx(pos.x, pos.y);
-                        map.blocked[idx] = false;
-                        pos.x = path.steps[1] as i32 % map.width;
-                        pos.y = path.steps[1] as i32 / map.width;
-                        idx = map.xy_idx(pos.x, pos.y);
-                        map.blocked[idx] = true;
+                match step {
+                    Some(next) if map.closed_doors.contains(&next) => {
+                        let door = map.tile_content[next].iter().copied().find(|e| doors.get(*e).is_some());
+                        if let Some(door) = door {
+                            door_intents.insert(entity, DoorIntent{ door, open: true })
+                                .expect("Unable to insert DoorIntent.");
+                            paid_elsewhere = true;
+                        }
+                    }
+                    Some(next) => {
+                        map.blocked[here] = false;
+                        map.occupied.remove(&here);
+                        pos.x = next as i32 % map.width;
+                        pos.y = next as i32 / map.width;
+                        map.blocked[next] = true;
+                        map.occupied.insert(next);
+                        cost = move_cost(&map, next);
                         viewshed.dirty = true;
                         moved_storage.insert(entity, JustMoved{})
                             .expect("Unable to insert JustMoved component.");
                     }
+                    //Cornered, or already there
+                    None if distance <= 1.5 => {
+                        melee_intent.insert(entity, MeleeIntent{ target: *player_entity })
+                            .expect("Uname to insert attack.");
+                        paid_elsewhere = true;
+                    }
+                    None => {}
                 }
             }
+
+            if !paid_elsewhere {
+                Energy::spend(&mut energies, entity, cost);
+            }
         }
     }
 }
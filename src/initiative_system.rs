@@ -0,0 +1,83 @@
+use specs::prelude::*;
+use super::{Energy, Map, Player, Position, Stats};
+
+/* Initiative
+ *
+ * Game time moves in ticks. Every tick, each creature with an Energy component
+ * gains `speed` energy, and any creature holding at least ACTION_COST may act.
+ * Acting spends the cost of the action, so a creature with twice the speed acts
+ * twice as often, and a heavy action makes its actor wait longer for the next one.
+ *
+ * The GameworldTurn runs the gameworld schedule once per tick until the player
+ * has enough energy to act again (see tick_simulation() in lib.rs).
+ *
+ * A "turn", for things that happen over time (hunger, bleeding), is TICKS_PER_TURN
+ * ticks: the time a NORMAL_SPEED creature needs to earn one ACTION_COST.
+ */
+
+pub const ACTION_COST: i32 = 100; //a step, an attack, waiting a turn
+pub const PICK_UP_COST: i32 = 100;
+pub const USE_ITEM_COST: i32 = 100;
+pub const DROP_COST: i32 = 50;
+pub const THROW_COST: i32 = 150;
+pub const EQUIP_COST: i32 = 100; //weapons, rings, necklaces
+pub const EQUIP_ARMOR_COST: i32 = 300; //anything you have to strap on
+
+pub const NORMAL_SPEED: i32 = 10;
+pub const TICKS_PER_TURN: u64 = (ACTION_COST / NORMAL_SPEED) as u64;
+
+//Safety valve for a player who can never act again (e.g. speed 0).
+pub const MAX_TICKS_PER_TURN: u64 = TICKS_PER_TURN * 100;
+
+//A step onto the tile at idx: ACTION_COST, scaled by how hard its terrain is to cross.
+pub fn move_cost(map: &Map, idx: usize) -> i32 {
+    (ACTION_COST as f32 * map.tiles[idx].movement_cost()) as i32
+}
+
+#[derive(PartialEq, Clone, Copy, Debug, Default)]
+pub struct GameClock {
+    pub tick: u64,
+}
+
+impl GameClock {
+    //True on the tick that completes a turn; systems that act "once per turn" gate on this.
+    pub fn is_new_turn(&self) -> bool {
+        self.tick > 0 && self.tick % TICKS_PER_TURN == 0
+    }
+}
+
+pub struct InitiativeSystem {}
+
+impl<'a> System<'a> for InitiativeSystem {
+    type SystemData = ( WriteExpect<'a, GameClock>,
+                        WriteStorage<'a, Energy>,
+                        ReadStorage<'a, Stats>,
+                        ReadStorage<'a, Position>,
+                      );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (mut clock, mut energies, stats, positions) = data;
+
+        clock.tick += 1;
+
+        //The dead don't get faster, and neither does anything on a level the player has left.
+        for (energy, stats, _pos) in (&mut energies, (&stats).maybe(), &positions).join() {
+            if stats.map_or(true, |s| s.hp > 0) {
+                energy.current += energy.speed;
+            }
+        }
+    }
+}
+
+//True once the player has the energy to take their next action, or can't act at all anymore.
+pub fn player_can_act(ecs: &World) -> bool {
+    let player = ecs.fetch::<Entity>();
+    let players = ecs.read_storage::<Player>();
+    let stats = ecs.read_storage::<Stats>();
+    let energies = ecs.read_storage::<Energy>();
+
+    if players.get(*player).is_none() || stats.get(*player).is_some_and(|s| s.hp < 1) {
+        return true; //dead; stop the clock and let delete_the_dead() end the game.
+    }
+    energies.get(*player).map_or(true, |e| e.current >= ACTION_COST)
+}
@@ -0,0 +1,331 @@
+#[macro_use]
+extern crate lazy_static;
+extern crate serde;
+
+use specs::prelude::*;
+use specs::saveload::SimpleMarkerAllocator;
+
+use bracket_lib::prelude::{BTerm, Point};
+
+mod bleed_system;
+mod c_menu_system;
+mod components;
+mod door_system;
+mod equip_system;
+mod healing_system;
+mod hostile_ai_system;
+mod hunger_system;
+mod initiative_system;
+mod inventory_system;
+mod light_system;
+mod map;
+mod map_indexing_system;
+mod melee_combat_system;
+mod rect;
+mod throw_system;
+mod trigger_system;
+mod visibility_system;
+
+pub mod camera;
+pub mod command;
+pub mod component_registry;
+pub mod damage_system;
+pub mod dijkstra;
+pub mod dungeon;
+pub mod effects;
+pub mod events;
+pub mod gui;
+pub mod map_builders;
+pub mod mapgen_visualizer;
+pub mod particle_system;
+pub mod player;
+pub mod random_table;
+pub mod raws;
+pub mod replay;
+pub mod rng;
+pub mod saveload_system;
+pub mod schedule;
+pub mod spawner;
+pub mod user_input;
+
+pub use component_registry::register_components;
+pub use components::*;
+pub use map::*;
+pub use rect::Rect;
+
+pub const MAP_WIDTH: i32 = 64;
+pub const MAP_HEIGHT: i32 = 64;
+
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum RunState {
+    AwaitingInput,
+    GameOver,
+    GameworldTurn,
+    LoadMenu,
+    MagicMapReveal { row: i32 },
+    //MainMenu { menu_selection: gui::MainMenuSelection }, //OLD
+    MainMenu,
+    MapGeneration,
+    NextLevel,
+    PreRun,
+    PreviousLevel,
+    PlayerTurn,
+    //ShowPlayerMenu { menu_state: gui::PlayerMenuState }, OLD
+    ShowPlayerMenu,
+    ShowContextMenu { selection: i8, focus: i8 },
+    ShowTargeting { range: i32, item: Entity },
+    SaveGame,
+}
+
+//Number of completed game turns (GameworldTurns) this run.
+#[derive(PartialEq, Clone, Copy, Debug, Default)]
+pub struct TurnCounter(pub u64);
+
+struct Inventory {
+    pub hands: (Option<Entity>, Option<Entity>),
+    pub quickbar: Vec<Option<Entity>>,
+    pub backpack: Vec<Option<Entity>>,
+    pub equipment: Vec<Option<Entity>>,
+}
+
+/* Headless World Bootstrap
+ *
+ * Everything below this point needs only a specs World, never a BTerm, so
+ * tools and integration tests can drive the game logic without a window:
+ *
+ *     let mut ecs = my_rl::build_world(seed, 1);
+ *     my_rl::step(&mut ecs);
+ *
+ * The binary (main.rs) wraps these in its GameState::tick().
+ */
+
+//Registers every component and inserts every resource, including the player
+//entity, but does not build a map. See build_world() for a ready-to-play World.
+pub fn new_world(seed: u64) -> World {
+    let mut ecs = World::new();
+    register_components(&mut ecs);
+
+    ecs.insert(SimpleMarkerAllocator::<SerializeMe>::new());
+    ecs.insert(Map::new(1, MAP_WIDTH, MAP_HEIGHT));
+    ecs.insert(dungeon::LevelStore::default());
+    ecs.insert(mapgen_visualizer::MapgenRecorder::default());
+    ecs.insert(Point::new(0, 0)); //<-what is this, Player Entity location? idk
+    rng::insert_rng_resources(&mut ecs, seed);
+    ecs.insert(RunState::MapGeneration {});
+    ecs.insert(TurnCounter::default());
+    ecs.insert(initiative_system::GameClock::default());
+    ecs.insert(particle_system::ParticleBuilder::new());
+    ecs.insert(effects::EffectQueue::default());
+    ecs.insert(dijkstra::FlowFields::default());
+    events::insert_event_resources(&mut ecs);
+    let player_entity = spawner::player(&mut ecs, 0, 0);
+    ecs.insert(player_entity);
+
+    ecs.insert(Inventory {
+        hands: (None, None),
+        quickbar: Vec::new(),
+        backpack: Vec::new(),
+        equipment: Vec::new(),
+    });
+
+    ecs
+}
+
+//A fully initialized World with a generated map at the given depth, with the
+//RunState set to PreRun so the first step() runs the systems once.
+pub fn build_world(seed: u64, depth: i32) -> World {
+    let mut ecs = new_world(seed);
+    generate_world_map(&mut ecs, depth);
+    *ecs.write_resource::<RunState>() = RunState::PreRun;
+    ecs
+}
+
+//Runs one turn's worth of systems; which ones, and in what order, lives in schedule.rs.
+pub fn run_systems(ecs: &mut World, turn: schedule::Turn) {
+    schedule::run(ecs, turn);
+}
+
+//Builds a new map at new_depth, spawns its entities, and places the player on it.
+//With the mapgen visualizer on, the build's snapshots are left in the MapgenRecorder.
+pub fn generate_world_map(ecs: &mut World, new_depth: i32) {
+    let seed = ecs.fetch::<rng::GameSeed>().0;
+    let mut rng = rng::mapgen_rng(seed, new_depth);
+    let mut builder = map_builders::random_builder(new_depth, &mut rng, MAP_WIDTH, MAP_HEIGHT);
+    builder.build_data.take_snapshots = ecs.fetch::<mapgen_visualizer::MapgenRecorder>().enabled;
+    builder.build_map(&mut rng);
+    let snapshots = std::mem::take(&mut builder.build_data.snapshot_history);
+    ecs.write_resource::<mapgen_visualizer::MapgenRecorder>().record(seed, new_depth, snapshots);
+    let player_start;
+    {
+        let mut worldmap_resource = ecs.write_resource::<Map>();
+        *worldmap_resource = builder.build_data.map.clone();
+        player_start = *builder.build_data.starting_position.as_ref().unwrap();
+    }
+
+    // Spawn bad guys
+    builder.spawn_entities(ecs);
+
+    place_player(ecs, player_start.x, player_start.y);
+}
+
+//Moves the player to (x, y) on the current map and updates the resources that follow them.
+pub(crate) fn place_player(ecs: &mut World, x: i32, y: i32) {
+    let mut player_position = ecs.write_resource::<Point>();
+    *player_position = Point::new(x, y);
+    let mut position_components = ecs.write_storage::<Position>();
+    let player_entity = ecs.fetch::<Entity>();
+    let player_pos_comp = position_components.get_mut(*player_entity);
+    if let Some(player_pos_comp) = player_pos_comp {
+        player_pos_comp.x = x;
+        player_pos_comp.y = y;
+    }
+
+    // Mark the player's visibility as dirty
+    let mut viewshed_components = ecs.write_storage::<Viewshed>();
+    let vs = viewshed_components.get_mut(*player_entity);
+    if let Some(vs) = vs {
+        vs.dirty = true;
+    }
+}
+
+pub fn goto_next_level(ecs: &mut World) {
+    // Leave this level as it is and go down to the next one
+    let curr_depth = ecs.fetch::<Map>().depth;
+    dungeon::change_level(ecs, curr_depth + 1);
+
+    // Notify the player and give them some health
+    {
+        let player_entity = ecs.fetch::<Entity>();
+        let mut logger = gui::gamelog::Logger::new();
+        logger.append("You descend to the next level and take a moment to rest.");
+        logger.log();
+        let mut stats_storage = ecs.write_storage::<Stats>();
+        let player_stats = stats_storage.get_mut(*player_entity);
+        if let Some(stats) = player_stats {
+            let new_hp = f32::floor((stats.max_hp - stats.hp) as f32 / 2.0) as i32 + stats.hp;
+            stats.hp = new_hp;
+        }
+    }
+
+    autosave(ecs);
+}
+
+//No rest on the way up; it's usually taken in a hurry.
+pub fn goto_previous_level(ecs: &mut World) {
+    let curr_depth = ecs.fetch::<Map>().depth;
+    if curr_depth <= 1 { return; }
+    dungeon::change_level(ecs, curr_depth - 1);
+
+    let mut logger = gui::gamelog::Logger::new();
+    logger.append(format!("You climb back up to depth {}.", curr_depth - 1));
+    logger.log();
+
+    autosave(ecs);
+}
+
+//Saves the run on every level change, when the binary asked for it.
+fn autosave(ecs: &mut World) {
+    if ecs.has_value::<saveload_system::Autosave>() {
+        let slot = saveload_system::run_slot(ecs);
+        if let Err(e) = saveload_system::save_game(ecs, &slot) {
+            let mut logger = gui::gamelog::Logger::new();
+            logger.append(format!("Autosave failed: {}", e));
+            logger.log();
+        }
+    }
+}
+
+//Wipes the World for a fresh run. The new run gets its own seed so that it
+//does not replay the dungeon that just killed the player.
+pub fn game_over_cleanup(ecs: &mut World, new_seed: u64) {
+    //Delete All Entities
+    let mut to_delete = Vec::new();
+    for e in ecs.entities().join() {
+        to_delete.push(e);
+    }
+    for del in to_delete.iter() {
+        ecs.delete_entity(*del).expect("Deletion failed");
+    }
+
+    {
+        let player_entity = spawner::player(ecs, 0, 0);
+        let mut player_entity_writer = ecs.write_resource::<Entity>();
+        *player_entity_writer = player_entity;
+    }
+
+    rng::insert_rng_resources(ecs, new_seed);
+    ecs.insert(TurnCounter::default());
+    ecs.insert(initiative_system::GameClock::default());
+    ecs.insert(events::Statistics::default());
+    ecs.insert(events::Achievements::default());
+    ecs.insert(dungeon::LevelStore::default());
+    generate_world_map(ecs, 1);
+}
+
+//Advances the simulation-only RunStates (the ones that need no input and no
+//window) by one frame and returns the next RunState. Any other RunState is
+//returned unchanged.
+pub fn tick_simulation(ecs: &mut World, runstate: RunState) -> RunState {
+    match runstate {
+        RunState::PreRun => {
+            run_systems(ecs, schedule::Turn::PreRun);
+            RunState::AwaitingInput
+        }
+        RunState::PlayerTurn => {
+            particle_system::delete_particles(ecs);
+            run_systems(ecs, schedule::Turn::PlayerTurn);
+            match *ecs.fetch::<RunState>() {
+                RunState::MagicMapReveal { .. } => RunState::MagicMapReveal { row: 0 },
+                _ => RunState::GameworldTurn,
+            }
+        }
+        RunState::GameworldTurn => {
+            //Run game time forward, one tick per pass, until the player has the
+            //energy to act again. Everyone else acts whenever their own energy allows.
+            for _ in 0..initiative_system::MAX_TICKS_PER_TURN {
+                run_systems(ecs, schedule::Turn::GameworldTurn);
+                damage_system::delete_the_dead(ecs);
+                if initiative_system::player_can_act(ecs) {
+                    break;
+                }
+            }
+            ecs.write_resource::<TurnCounter>().0 += 1;
+            RunState::AwaitingInput
+        }
+        RunState::MagicMapReveal { row } => {
+            let mut map = ecs.fetch_mut::<Map>();
+            for x in 0..map.width {
+                let idx = map.xy_idx(x as i32, row);
+                map.revealed_tiles[idx] = true;
+            }
+            if row == map.height - 1 {
+                RunState::GameworldTurn
+            } else {
+                RunState::MagicMapReveal { row: row + 1 }
+            }
+        }
+        RunState::NextLevel => {
+            goto_next_level(ecs);
+            RunState::PreRun
+        }
+        RunState::PreviousLevel => {
+            goto_previous_level(ecs);
+            RunState::PreRun
+        }
+        _ => runstate,
+    }
+}
+
+//One headless frame: tick_simulation() on the current RunState resource,
+//store the result, then reap the dead, as GameState::tick() does each frame.
+pub fn step(ecs: &mut World) -> RunState {
+    let runstate = *ecs.fetch::<RunState>();
+    let newrunstate = tick_simulation(ecs, runstate);
+    {
+        let mut runwriter = ecs.write_resource::<RunState>();
+        *runwriter = newrunstate;
+    }
+
+    damage_system::delete_the_dead(ecs);
+    *ecs.fetch::<RunState>()
+}
@@ -1,91 +1,16 @@
-#[macro_use]
-extern crate lazy_static;
-extern crate serde;
-
 use std::sync::Arc;
 
 use specs::prelude::*;
-use specs::saveload::{SimpleMarker, SimpleMarkerAllocator};
 
 use bracket_lib::prelude::{
-    embedded_resource, link_resource, BError, BTerm, BTermBuilder, GameState, Point,
-    RandomNumberGenerator, EMBED, KHAKI, WHITE,
+    embedded_resource, link_resource, BError, BTerm, BTermBuilder, GameState, VirtualKeyCode, EMBED, KHAKI, WHITE,
 };
 
-use command::Commandable;
-
-mod bleed_system;
-mod c_menu_system;
-mod components;
-mod damage_system;
-mod equip_system;
-mod gui;
-mod healing_system;
-mod hostile_ai_system;
-mod hunger_system;
-mod inventory_system;
-mod light_system;
-mod map;
-mod map_indexing_system;
-mod melee_combat_system;
-mod player;
-mod rect;
-mod spawner;
-mod throw_system;
-mod trigger_system;
-mod visibility_system;
-
-pub mod camera;
-pub mod command;
-pub mod map_builders;
-pub mod particle_system;
-pub mod random_table;
-pub mod saveload_system;
-pub mod user_input;
-
-//use player::*;
-use bleed_system::BleedSystem;
-use c_menu_system::ContextMenuSystem;
-use damage_system::DamageSystem;
-use equip_system::EquipSystem;
-use healing_system::HealingSystem;
-use hostile_ai_system::HostileAI;
-use hunger_system::HungerSystem;
-use inventory_system::ItemCollectionSystem;
-use inventory_system::ItemDropSystem;
-use inventory_system::ItemUseSystem;
-use light_system::LightSystem;
-use map_indexing_system::MapIndexingSystem;
-use melee_combat_system::MeleeCombatSystem;
-use throw_system::ThrowSystem;
-use trigger_system::TriggerSystem;
-use visibility_system::VisibilitySystem;
-//use gui::drawable::Drawable;
-
-pub use components::*;
-pub use map::*;
-pub use rect::Rect;
-
-const SHOW_MAPGEN_VISUALIZER: bool = false;
-
-#[derive(PartialEq, Clone, Copy)]
-pub enum RunState {
-    AwaitingInput,
-    GameOver,
-    GameworldTurn,
-    MagicMapReveal { row: i32 },
-    //MainMenu { menu_selection: gui::MainMenuSelection }, //OLD
-    MainMenu,
-    MapGeneration,
-    NextLevel,
-    PreRun,
-    PlayerTurn,
-    //ShowPlayerMenu { menu_state: gui::PlayerMenuState }, OLD
-    ShowPlayerMenu,
-    ShowContextMenu { selection: i8, focus: i8 },
-    ShowTargeting { range: i32, item: Entity },
-    SaveGame,
-}
+use my_rl::command::Commandable;
+use my_rl::mapgen_visualizer::{MapgenRecorder, MapgenVisualizer};
+use my_rl::replay::{Replay, ReplayPlayer, ReplayRecorder};
+use my_rl::{camera, damage_system, gui, map_builders, particle_system, player, raws, replay, saveload_system, user_input};
+use my_rl::{RunState, Stats};
 
 pub struct State {
     pub ecs: World, //specs World
@@ -98,158 +23,54 @@ pub struct State {
     //static_gui_objs: HashMap<String, Arc<dyn Drawable>>, //keeps Rc<things> alive that would otherwise only have Weak<> refs.
     pub tooltips_on: bool, //<-delete after UI integration
 
-    //rltk-based map procgen state - to-be-removed
+    //Some while playing back a --replay file; PlayerController is fed from it instead of UserInput.
+    replay: Option<ReplayPlayer>,
+    //Where new runs are recorded; None when the run came from a replay.
+    record_path: Option<String>,
+    //Slot names in the order the load screen lists them.
+    load_slots: Vec<String>,
+    //Why the last load failed, shown on the load screen.
+    load_error: Option<String>,
+
+    //Where play goes on once the mapgen visualizer is done.
     mapgen_next_state: Option<RunState>,
-    mapgen_history: Vec<Map>,
-    mapgen_index: usize,
-    mapgen_timer: f32,
+    mapgen: MapgenVisualizer,
 }
 
 impl State {
-    fn run_systems(&mut self) {
-        let mut context_menu = ContextMenuSystem {};
-        context_menu.run_now(&self.ecs);
-        let mut mob = HostileAI {};
-        mob.run_now(&self.ecs);
-        let mut triggers = TriggerSystem {};
-        triggers.run_now(&self.ecs);
-        let mut items = ItemUseSystem {};
-        items.run_now(&self.ecs);
-        let mut drop = ItemDropSystem {};
-        drop.run_now(&self.ecs);
-        let mut melee = MeleeCombatSystem {};
-        melee.run_now(&self.ecs);
-        let mut light = LightSystem {};
-        light.run_now(&self.ecs);
-        let mut vis = VisibilitySystem {};
-        vis.run_now(&self.ecs);
-        let mut mapindex = MapIndexingSystem {};
-        mapindex.run_now(&self.ecs);
-        let mut healing = HealingSystem {};
-        healing.run_now(&self.ecs);
-        let mut bleed = BleedSystem {};
-        bleed.run_now(&self.ecs);
-        let mut hunger = HungerSystem {};
-        hunger.run_now(&self.ecs);
-        let mut throw = ThrowSystem {};
-        throw.run_now(&self.ecs);
-        let mut damage = DamageSystem {};
-        damage.run_now(&self.ecs);
-        let mut pick_up = ItemCollectionSystem {};
-        pick_up.run_now(&self.ecs);
-        let mut equips = EquipSystem {};
-        equips.run_now(&self.ecs);
-        let mut particles = particle_system::ParticleSpawnSystem {};
-        particles.run_now(&self.ecs);
-
-        self.ecs.maintain();
-    }
-
-    fn generate_world_map(&mut self, new_depth: i32) {
-        self.mapgen_index = 0;
-        self.mapgen_timer = 0.0;
-        self.mapgen_history.clear();
-        let mut rng = self.ecs.write_resource::<RandomNumberGenerator>();
-        let mut builder = map_builders::random_builder(new_depth, &mut rng, 64, 64);
-        builder.build_map(&mut rng);
-        std::mem::drop(rng); //drops the borrow on rng & self
-        self.mapgen_history = builder.build_data.snapshot_history.clone();
-        let player_start;
-        {
-            let mut worldmap_resource = self.ecs.write_resource::<Map>();
-            *worldmap_resource = builder.build_data.map.clone();
-            player_start = builder
-                .build_data
-                .starting_position
-                .as_mut()
-                .unwrap()
-                .clone();
-        }
-
-        // Spawn bad guys
-        builder.spawn_entities(&mut self.ecs);
-
-        // Place the player and update resources
-        let (player_x, player_y) = (player_start.x, player_start.y);
-        let mut player_position = self.ecs.write_resource::<Point>();
-        *player_position = Point::new(player_x, player_y);
-        let mut position_components = self.ecs.write_storage::<Position>();
-        let player_entity = self.ecs.fetch::<Entity>();
-        let player_pos_comp = position_components.get_mut(*player_entity);
-        if let Some(player_pos_comp) = player_pos_comp {
-            player_pos_comp.x = player_x;
-            player_pos_comp.y = player_y;
-        }
-
-        // Mark the player's visibility as dirty
-        let mut viewshed_components = self.ecs.write_storage::<Viewshed>();
-        let vs = viewshed_components.get_mut(*player_entity);
-        if let Some(vs) = vs {
-            vs.dirty = true;
-        }
+    fn game_over_cleanup(&mut self) {
+        let seed: u64 = rand::random();
+        my_rl::game_over_cleanup(&mut self.ecs, seed);
+        self.start_recording(seed);
     }
 
-    fn entities_to_remove_on_level_change(&mut self) -> Vec<Entity> {
-        let entities = self.ecs.entities();
-        let player = self.ecs.read_storage::<Player>();
-        let backpack = self.ecs.read_storage::<InBackpack>();
-        let equipped = self.ecs.read_storage::<Equipped>();
-
-        let mut to_delete: Vec<Entity> = Vec::new();
-        for (ent, (), (), ()) in (&entities, !&player, !&backpack, !&equipped).join() {
-            to_delete.push(ent);
+    fn start_recording(&mut self, seed: u64) {
+        if let Some(path) = &self.record_path {
+            match ReplayRecorder::create(path, seed) {
+                Ok(recorder) => self.ecs.insert(recorder),
+                Err(e) => eprintln!("Unable to create replay file {}: {}", path, e),
+            }
         }
-
-        to_delete
     }
 
-    fn goto_next_level(&mut self) {
-        // Delete entities that aren't the player or his/her equipment
-        let to_delete = self.entities_to_remove_on_level_change();
-        for target in to_delete {
-            self.ecs
-                .delete_entity(target)
-                .expect("Unable to delete entity");
-        }
-
-        // Build a new map and place the player
-        let curr_depth;
-        {
-            let worldmap_resource = self.ecs.fetch::<Map>();
-            curr_depth = worldmap_resource.depth;
-        }
-        self.generate_world_map(curr_depth + 1);
+    //Queues the next recorded batch on the PlayerController. Once the replay runs
+    //out (or desyncs) control is handed back to the keyboard.
+    fn feed_replay(&mut self) {
+        let result = match &mut self.replay {
+            Some(replay) => replay.feed(&self.ecs, &self.player_controller),
+            None => return,
+        };
 
-        // Notify the player and give them some health
-        let player_entity = self.ecs.fetch::<Entity>();
         let mut logger = gui::gamelog::Logger::new();
-        logger.append("You descend to the next level and take a moment to rest.");
-        logger.log();
-        let mut stats_storage = self.ecs.write_storage::<Stats>();
-        let player_stats = stats_storage.get_mut(*player_entity);
-        if let Some(stats) = player_stats {
-            let new_hp = f32::floor((stats.max_hp - stats.hp) as f32 / 2.0) as i32 + stats.hp;
-            stats.hp = new_hp;
-        }
-    }
-
-    fn game_over_cleanup(&mut self) {
-        //Delete All Entities
-        let mut to_delete = Vec::new();
-        for e in self.ecs.entities().join() {
-            to_delete.push(e);
-        }
-        for del in to_delete.iter() {
-            self.ecs.delete_entity(*del).expect("Deletion failed");
-        }
-
-        {
-            let player_entity = spawner::player(&mut self.ecs, 0, 0);
-            let mut player_entity_writer = self.ecs.write_resource::<Entity>();
-            *player_entity_writer = player_entity;
+        match result {
+            Ok(true) => return,
+            Ok(false) => logger.append("The replay has ended."),
+            Err(e) => logger.append(format!("The replay has stopped: {}", e)),
         }
+        logger.log();
 
-        self.generate_world_map(1);
+        self.replay = None;
+        self.player_controller = player::PlayerController::new(&self.user_input);
     }
 }
 
@@ -271,6 +92,14 @@ impl GameState for State {
             }
         }
 
+        if ctx.key == Some(VirtualKeyCode::F12) {
+            let on = self.ecs.write_resource::<MapgenRecorder>().toggle();
+            let mut logger = gui::gamelog::Logger::new();
+            logger.append(if on { "Map generation visualizer on; it shows the next new level." }
+                          else { "Map generation visualizer off." });
+            logger.log();
+        }
+
         //Clear both main-map and log consoles.
         //ctx.set_active_console(1);
         //ctx.cls();
@@ -284,6 +113,7 @@ impl GameState for State {
         //the map or HUD if we're in the Main Menu or Game Over screens/modes.
         match newrunstate {
             RunState::MainMenu => {}
+            RunState::LoadMenu => {}
             RunState::GameOver => {}
             //RunState::MapGeneration => {}
             _ => {
@@ -314,30 +144,18 @@ impl GameState for State {
         //Now match on the runstate again to handle all other runstate-speicific factors.
         match newrunstate {
             RunState::MapGeneration => {
-                if !SHOW_MAPGEN_VISUALIZER {
+                if let Some(recording) = self.ecs.write_resource::<MapgenRecorder>().take() {
+                    self.mapgen.play(recording);
+                }
+                ctx.set_active_console(0);
+                ctx.cls();
+                if !self.mapgen.tick(ctx) {
                     newrunstate = self.mapgen_next_state.unwrap();
-                } else {
-                    ctx.set_active_console(0);
-                    ctx.cls();
-                    if self.mapgen_index < self.mapgen_history.len() {
-                        camera::render_debug_map(&self.mapgen_history[self.mapgen_index], ctx);
-                    }
-                    self.mapgen_timer += ctx.frame_time_ms;
-                    if self.mapgen_timer > 300.0 {
-                        self.mapgen_timer = 0.0;
-                        self.mapgen_index += 1;
-                        if self.mapgen_index >= self.mapgen_history.len() {
-                            newrunstate = self.mapgen_next_state.unwrap();
-                        }
-                    }
                 }
             }
             RunState::PreRun => {
-                self.run_systems();
-                self.ecs.maintain();
+                newrunstate = my_rl::tick_simulation(&mut self.ecs, newrunstate);
                 self.gui.init_widgets();
-
-                newrunstate = RunState::AwaitingInput;
             }
             RunState::AwaitingInput => {
                 //newrunstate = player_input(&mut self.ecs, ctx); //OLD
@@ -352,24 +170,13 @@ impl GameState for State {
                  * results in gameplay but which is undoable and not committed until they choose
                  * to submit their final turn, which consists of the Commands in the CommandQueue,
                  * in the order they were added to the CommandQueue.*/
+                self.feed_replay();
                 newrunstate = self
                     .player_controller
                     .ecs_process(&mut self.ecs, RunState::AwaitingInput);
             }
-            RunState::PlayerTurn => {
-                self.run_systems();
-                self.ecs.maintain();
-                match *self.ecs.fetch::<RunState>() {
-                    RunState::MagicMapReveal { .. } => {
-                        newrunstate = RunState::MagicMapReveal { row: 0 }
-                    }
-                    _ => newrunstate = RunState::GameworldTurn,
-                }
-            }
-            RunState::GameworldTurn => {
-                self.run_systems();
-                self.ecs.maintain();
-                newrunstate = RunState::AwaitingInput;
+            RunState::PlayerTurn | RunState::GameworldTurn => {
+                newrunstate = my_rl::tick_simulation(&mut self.ecs, newrunstate);
             }
             /*RunState::ShowContextMenu { selection, focus } => {
                 let result = gui::open_context_menu(&self.ecs, ctx, selection, focus);
@@ -541,28 +348,30 @@ impl GameState for State {
                     }
                 }
             }*/
-            RunState::MagicMapReveal { row } => {
-                let mut map = self.ecs.fetch_mut::<Map>();
-                for x in 0..map.width {
-                    let idx = map.xy_idx(x as i32, row);
-                    map.revealed_tiles[idx] = true;
-                }
-                if row == map.height - 1 {
-                    newrunstate = RunState::GameworldTurn;
-                } else {
-                    newrunstate = RunState::MagicMapReveal { row: row + 1 };
-                }
+            RunState::MagicMapReveal { .. } => {
+                newrunstate = my_rl::tick_simulation(&mut self.ecs, newrunstate);
             }
 
-            RunState::NextLevel => {
-                self.goto_next_level();
-                newrunstate = RunState::PreRun;
+            RunState::NextLevel | RunState::PreviousLevel => {
+                newrunstate = my_rl::tick_simulation(&mut self.ecs, newrunstate);
             }
 
-            /*RunState::SaveGame => {
-                saveload_system::save_game(&mut self.ecs);
-                newrunstate = RunState::MainMenu {menu_selection: gui::MainMenuSelection::LoadGame};
-            }*/
+            RunState::SaveGame => {
+                let slot = saveload_system::run_slot(&self.ecs);
+                match saveload_system::save_game(&mut self.ecs, &slot) {
+                    Ok(()) => {
+                        //Leave a fresh world behind, for "New Game".
+                        self.game_over_cleanup();
+                        newrunstate = RunState::MainMenu;
+                    }
+                    Err(e) => {
+                        let mut logger = gui::gamelog::Logger::new();
+                        logger.append(format!("Unable to save: {}", e));
+                        logger.log();
+                        newrunstate = RunState::AwaitingInput;
+                    }
+                }
+            }
             RunState::MainMenu => {
                 use gui::widget::{main_menu, widget_storage};
 
@@ -576,17 +385,60 @@ impl GameState for State {
                         }
                         Some(1) => {
                             //Load Game
-                            saveload_system::load_game(&mut self.ecs);
-                            newrunstate = RunState::AwaitingInput;
-                            saveload_system::delete_save(); //death is permanent
-                            widget_storage::rm("MainMenu")
-                                .expect("widget_storage::rm(main_menu) failed.");
+                            if saveload_system::does_save_exist() {
+                                newrunstate = RunState::LoadMenu;
+                                widget_storage::rm("MainMenu")
+                                    .expect("widget_storage::rm(main_menu) failed.");
+                            }
                         }
                         Some(2) => ::std::process::exit(0), //Quit Game
                         _ => {}
                     }
                 } else {
-                    let id: usize = main_menu::construct(ctx, &self.gui.user_input);
+                    let can_load = saveload_system::does_save_exist();
+                    let id: usize = main_menu::construct(ctx, &self.gui.user_input, can_load);
+                    self.user_input.set_focus(id);
+                }
+            }
+            RunState::LoadMenu => {
+                use gui::widget::{load_menu, widget_storage};
+
+                if widget_storage::contains("LoadMenu") {
+                    match self.user_input.get_focus_selection() {
+                        Some(i) if (i as usize) < self.load_slots.len() => {
+                            let slot = self.load_slots[i as usize].clone();
+                            match saveload_system::load_game(&mut self.ecs, &slot) {
+                                Ok(()) => {
+                                    //A loaded game can't be replayed from its seed alone.
+                                    self.ecs.remove::<ReplayRecorder>();
+                                    newrunstate = RunState::AwaitingInput;
+                                    self.load_error = None;
+                                    widget_storage::rm("LoadMenu")
+                                        .expect("widget_storage::rm(load_menu) failed.");
+                                }
+                                Err(e) => {
+                                    //The world may be half-loaded; start over from a fresh one.
+                                    self.game_over_cleanup();
+                                    self.load_error = Some(format!("Unable to load {}: {}", slot, e));
+                                    widget_storage::rm("LoadMenu")
+                                        .expect("widget_storage::rm(load_menu) failed.");
+                                }
+                            }
+                        }
+                        Some(_) => {
+                            //Back
+                            self.load_error = None;
+                            newrunstate = RunState::MainMenu;
+                            widget_storage::rm("LoadMenu")
+                                .expect("widget_storage::rm(load_menu) failed.");
+                        }
+                        None => {}
+                    }
+                } else {
+                    let slots = saveload_system::list_slots();
+                    self.load_slots = slots.iter().map(|slot| slot.name.clone()).collect();
+                    let id: usize = load_menu::construct(ctx, &self.gui.user_input, &slots,
+                                                         self.load_error.as_deref());
                     self.user_input.set_focus(id);
                 }
             }
@@ -607,6 +459,11 @@ impl GameState for State {
                         _ => {}
                     }
                 } else {
+                    //Death is permanent; the run's autosave goes with it.
+                    let slot = saveload_system::run_slot(&self.ecs);
+                    if let Err(e) = saveload_system::delete_save(&slot) {
+                        eprintln!("Unable to delete save {}: {}", slot, e);
+                    }
                     let id: usize = game_over::construct(ctx, &self.gui.user_input);
                     self.user_input.set_focus(id);
                 }
@@ -614,6 +471,15 @@ impl GameState for State {
             _ => {}
         }
 
+        //A level was just generated with the visualizer on: show how, then carry on.
+        if newrunstate != RunState::MapGeneration {
+            if let Some(recording) = self.ecs.write_resource::<MapgenRecorder>().take() {
+                self.mapgen.play(recording);
+                self.mapgen_next_state = Some(newrunstate);
+                newrunstate = RunState::MapGeneration;
+            }
+        }
+
         {
             let mut runwriter = self.ecs.write_resource::<RunState>();
             *runwriter = newrunstate;
@@ -623,24 +489,53 @@ impl GameState for State {
     }
 }
 
-/*Needs to be more obviously differentiated from GUI::Cursor. -------------------------- !!!
-struct Cursor {
-    pub x: i32,
-    pub y: i32,
-    pub active: bool,
-}*/
-
-struct Inventory {
-    pub hands: (Option<Entity>, Option<Entity>),
-    pub quickbar: Vec<Option<Entity>>,
-    pub backpack: Vec<Option<Entity>>,
-    pub equipment: Vec<Option<Entity>>,
-}
-
 //embedded_resource!(TILE_FONT, "../resources/unicode_16x16.png"); //bracket-lib should doc this
 embedded_resource!(TILE_FONT, "../resources/terminal8x8.jpg");
 
 fn main() -> BError {
+    //Entity definitions. The copy built into the binary stands in if the file can't be used.
+    if let Err(e) = raws::load_raws(raws::RAWS_PATH) {
+        eprintln!("Using built-in raws; {}", e);
+    }
+    //Vaults, sections and levels; before the recipes, which name them.
+    if let Err(e) = map_builders::load_prefabs(map_builders::PREFABS_PATH) {
+        eprintln!("Using built-in prefabs; {}", e);
+    }
+    //Which builders make each level; likewise falls back to the built-in copy.
+    if let Err(e) = map_builders::load_recipes(map_builders::RECIPES_PATH) {
+        eprintln!("Using built-in map recipes; {}", e);
+    }
+    //Last, as it checks what the prefabs and biomes name against the raws.
+    for problem in raws::RAWS.lock().unwrap().validate() {
+        eprintln!("Raws: {}", problem);
+    }
+    if let Err(e) = saveload_system::import_legacy_save() {
+        eprintln!("Unable to move {} into the save directory: {}", saveload_system::LEGACY_SAVE_PATH, e);
+    }
+
+    //`--replay <file>` plays a recorded run back; add `--headless` to run it
+    //to the end without a window and print the final state.
+    let replay = arg_value("--replay").map(|path| match Replay::load(&path) {
+        Ok(replay) => replay,
+        Err(e) => {
+            eprintln!("Unable to load replay {}: {}", path, e);
+            std::process::exit(1);
+        }
+    });
+
+    if let Some(replay) = &replay {
+        if has_flag("--headless") {
+            match replay::run_headless(replay) {
+                Ok(final_state) => println!("{}", final_state),
+                Err(e) => {
+                    eprintln!("{}", e);
+                    std::process::exit(1);
+                }
+            }
+            return Ok(());
+        }
+    }
+
     //link_resource!(TILE_FONT, "resources/unicode_16x16.png");
     link_resource!(TILE_FONT, "resources/terminal8x8.jpg");
 
@@ -659,97 +554,44 @@ fn main() -> BError {
     //----------- initialization of State fields ------------
     let user_input = Arc::new(user_input::UserInput::new());
 
-    let player_controller = player::PlayerController::new(&user_input);
+    //During a replay the PlayerController listens to an input that is never fed,
+    //so keypresses can't leak into the recorded run.
+    let player_controller = match replay {
+        Some(_) => player::PlayerController::new(&Arc::new(user_input::UserInput::new())),
+        None => player::PlayerController::new(&user_input),
+    };
 
     let gui = gui::GUI::new(&user_input);
     //-------------------------------------------------------
 
+    let seed = match &replay {
+        Some(replay) => replay.header.seed,
+        None => arg_value("--seed").and_then(|s| s.parse().ok()).unwrap_or_else(rand::random),
+    };
+    let record_path = match replay {
+        Some(_) => None,
+        None => Some(arg_value("--record").unwrap_or_else(|| replay::DEFAULT_REPLAY_PATH.to_string())),
+    };
+
     let mut gs = State {
-        ecs: World::new(),
+        ecs: my_rl::new_world(seed),
         user_input,
         player_controller,
         gui,
         tooltips_on: false,
 
+        replay: replay.as_ref().map(ReplayPlayer::new),
+        record_path,
+        load_slots: Vec::new(),
+        load_error: None,
+
         //mapgen_next_state : Some(RunState::MainMenu{ menu_selection: gui::MainMenuSelection::NewGame }), OLD
         mapgen_next_state: Some(RunState::MainMenu),
-        mapgen_index: 0,
-        mapgen_history: Vec::new(),
-        mapgen_timer: 0.0,
+        mapgen: MapgenVisualizer::default(),
     };
-
-    gs.ecs.register::<Position>();
-    gs.ecs.register::<Renderable>();
-    gs.ecs.register::<Player>();
-    gs.ecs.register::<Viewshed>();
-    gs.ecs.register::<Hostile>();
-    gs.ecs.register::<Name>();
-    gs.ecs.register::<BlocksTile>();
-    gs.ecs.register::<Stats>();
-    gs.ecs.register::<MeleeIntent>();
-    gs.ecs.register::<DamageOnUse>();
-    gs.ecs.register::<DamageQueue>();
-    gs.ecs.register::<Item>();
-    gs.ecs.register::<PickUpIntent>();
-    gs.ecs.register::<InBackpack>();
-    gs.ecs.register::<UseItemIntent>();
-    gs.ecs.register::<DropItemIntent>();
-    gs.ecs.register::<Consumable>();
-    gs.ecs.register::<Ranged>();
-    gs.ecs.register::<AoE>();
-    gs.ecs.register::<Confusion>();
-    gs.ecs.register::<Equippable>();
-    gs.ecs.register::<Equipped>();
-    gs.ecs.register::<Resistances>();
-    gs.ecs.register::<Weapon>();
-    gs.ecs.register::<EquipIntent>();
-    gs.ecs.register::<UnequipIntent>();
-    gs.ecs.register::<BasicAttack>();
-    gs.ecs.register::<BlocksAttacks>();
-    gs.ecs.register::<Menuable>();
-    gs.ecs.register::<Creature>();
-    gs.ecs.register::<Bleeding>();
-    gs.ecs.register::<Healing>();
-    gs.ecs.register::<Heals>();
-    gs.ecs.register::<Immunities>();
-    gs.ecs.register::<Particle>();
-    gs.ecs.register::<Hunger>();
-    gs.ecs.register::<MagicMapper>();
-    gs.ecs.register::<Useable>();
-    gs.ecs.register::<ThrowIntent>();
-    gs.ecs.register::<Throwable>();
-    gs.ecs.register::<Flammable>();
-    gs.ecs.register::<Aflame>();
-    gs.ecs.register::<Lightsource>();
-    gs.ecs.register::<Hidden>();
-    gs.ecs.register::<EntryTrigger>();
-    gs.ecs.register::<JustMoved>();
-    gs.ecs.register::<Door>();
-    gs.ecs.register::<BlocksVisibility>();
-    gs.ecs.register::<Info>();
-    gs.ecs.register::<SimpleMarker<SerializeMe>>();
-    gs.ecs.register::<SerializationHelper>();
-
-    gs.ecs.insert(SimpleMarkerAllocator::<SerializeMe>::new());
-    gs.ecs.insert(Map::new(1, 64, 64));
-    gs.ecs.insert(Point::new(0, 0)); //<-what is this, Player Entity location? idk
-    gs.ecs.insert(RandomNumberGenerator::new());
-    gs.ecs.insert(RunState::MapGeneration {});
-    gs.ecs.insert(particle_system::ParticleBuilder::new());
-    let player_entity = spawner::player(&mut gs.ecs, 0, 0);
-    gs.ecs.insert(player_entity);
-
-    /*gs.ecs.insert(Cursor {
-        x: 0,
-        y: 0,
-        active: false,
-    });*/
-    gs.ecs.insert(Inventory {
-        hands: (None, None),
-        quickbar: Vec::new(),
-        backpack: Vec::new(),
-        equipment: Vec::new(),
-    });
+    if gs.replay.is_none() {
+        gs.ecs.insert(saveload_system::Autosave);
+    }
 
     let mut logger = gui::gamelog::Logger::new();
     logger.append("A most stifling damp chokes the air, the");
@@ -759,6 +601,25 @@ fn main() -> BError {
     logger.append("have become close.");
     logger.log();
 
-    gs.generate_world_map(1);
+    //Players include this in bug reports; `--seed <n>` replays the same dungeon.
+    let mut logger = gui::gamelog::Logger::new();
+    logger.append(format!("Seed: {}", seed));
+    logger.log();
+
+    //`--mapgen-visualizer` shows how the first level was built; F12 toggles it in game.
+    gs.ecs.write_resource::<MapgenRecorder>().enabled = has_flag("--mapgen-visualizer");
+    gs.start_recording(seed);
+    my_rl::generate_world_map(&mut gs.ecs, 1);
     bracket_lib::prelude::main_loop(context, gs)
 }
+
+//Command line: `--seed <n>`, `--record <file>`, `--replay <file> [--headless]`, `--mapgen-visualizer`.
+fn arg_value(flag: &str) -> Option<String> {
+    let args: Vec<String> = std::env::args().collect();
+    let pos = args.iter().position(|arg| arg == flag)?;
+    args.get(pos + 1).cloned()
+}
+
+fn has_flag(flag: &str) -> bool {
+    std::env::args().any(|arg| arg == flag)
+}
@@ -1,19 +1,137 @@
 //use bracket_lib::prelude::{BaseMap, Algorithm2D, Point};
-use bracket_lib::prelude::Point;
+use bracket_lib::prelude::{Point, RGB};
 use bracket_lib::prelude::{Algorithm2D, BaseMap, DistanceAlg, SmallVec};
 
 use std::collections::HashSet;
+use std::sync::atomic::{AtomicU64, Ordering};
 use specs::prelude::*;
 use serde::{Serialize, Deserialize};
 
-#[derive(PartialEq, Eq, Copy, Clone, Hash, Serialize, Deserialize)]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Copy, Clone, Hash, Debug, Serialize, Deserialize)]
 pub enum TileType {
     Wall,
     Floor,
-    StairsDown
+    StairsDown,
+    StairsUp,
+    ShallowWater,
+    DeepWater,
+    Mud,
+    Rubble,
+    Grass,
+    Chasm,
+    Bridge,
+    Lava,
+    SecretDoor,
+    HiddenPassage,
 }
 
-#[derive(Default, Serialize, Deserialize, Clone)]
+/* Terrain
+ *
+ * Everything the game needs to know about a tile type lives in the methods
+ * below; BaseMap, the camera, the map builders and movement all ask these
+ * rather than matching on tile types themselves. A new terrain is a new
+ * variant plus an arm in each method.
+ *
+ * Movement cost is a multiplier on the cost of stepping onto the tile: it
+ * weighs A* paths, and moving onto the tile costs that much more energy.
+ *
+ * Secret doors and hidden passages are walls until they're found (see
+ * Map::reveal_secret()), and are drawn as walls; only `mapgen` tells them apart.
+ */
+impl TileType {
+    pub fn is_walkable(self) -> bool {
+        match self {
+            TileType::Floor | TileType::StairsDown | TileType::StairsUp | TileType::ShallowWater
+            | TileType::Mud | TileType::Rubble | TileType::Grass | TileType::Bridge => true,
+            TileType::Wall | TileType::DeepWater | TileType::Chasm | TileType::Lava
+            | TileType::SecretDoor | TileType::HiddenPassage => false,
+        }
+    }
+
+    pub fn is_opaque(self) -> bool {
+        self.looks_like() == TileType::Wall
+    }
+
+    //What the player takes the tile for.
+    pub fn looks_like(self) -> TileType {
+        match self {
+            TileType::SecretDoor | TileType::HiddenPassage => TileType::Wall,
+            _ => self,
+        }
+    }
+
+    pub fn movement_cost(self) -> f32 {
+        match self {
+            TileType::ShallowWater | TileType::Rubble => 1.5,
+            TileType::Mud => 2.0,
+            _ => 1.0,
+        }
+    }
+
+    //The glyph and foreground colour the camera draws; walls are drawn to join up instead (see camera.rs).
+    pub fn glyph(self) -> (char, RGB) {
+        match self {
+            TileType::Wall => ('#', RGB::from_f32(0., 1.0, 0.)),
+            TileType::Floor => ('.', RGB::from_f32(0.0, 0.5, 0.5)),
+            TileType::StairsDown => ('>', RGB::from_f32(0., 1.0, 1.0)),
+            TileType::StairsUp => ('<', RGB::from_f32(0., 1.0, 1.0)),
+            TileType::ShallowWater => ('~', RGB::from_f32(0.3, 0.6, 1.0)),
+            TileType::DeepWater => ('≈', RGB::from_f32(0.1, 0.2, 0.9)),
+            TileType::Mud => (',', RGB::from_f32(0.55, 0.35, 0.15)),
+            TileType::Rubble => (';', RGB::from_f32(0.6, 0.6, 0.6)),
+            TileType::Grass => ('"', RGB::from_f32(0.2, 0.8, 0.2)),
+            TileType::Chasm => (' ', RGB::from_f32(0., 0., 0.)),
+            TileType::Bridge => ('=', RGB::from_f32(0.6, 0.4, 0.2)),
+            TileType::Lava => ('≈', RGB::from_f32(1.0, 0.35, 0.0)),
+            TileType::SecretDoor => ('+', RGB::from_f32(0., 1.0, 0.)),
+            TileType::HiddenPassage => ('#', RGB::from_f32(0., 1.0, 0.)),
+        }
+    }
+}
+
+/* Biome
+ *
+ * What a level is besides its layout. Every level is built in one of the
+ * biomes in raws/map_recipes.yaml, which picks its recipe, and the level keeps
+ * the rest of it: the spawn table its rooms are filled from, the colours its
+ * tiles are drawn in, and how far anyone can see by its ambient light.
+ * Torchlit tiles are seen from as far away as anywhere else.
+ *
+ * The default is what a level built outside any biome gets: the room table,
+ * the tiles' own colours, and full light.
+ */
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Biome {
+    pub name: String,
+    pub spawn_table: String,
+    pub ambient_light: f32,
+    pub palette: Vec<(TileType, RGB)>,
+}
+
+impl Default for Biome {
+    fn default() -> Biome {
+        Biome {
+            name: String::new(),
+            spawn_table: crate::spawner::ROOM_TABLE.to_string(),
+            ambient_light: 1.0,
+            palette: Vec::new(),
+        }
+    }
+}
+
+impl Biome {
+    //The tile's colour here, or its own if the palette leaves it out.
+    pub fn colour(&self, tile: TileType) -> RGB {
+        self.palette.iter().find(|(t, _)| *t == tile).map_or_else(|| tile.glyph().1, |(_, colour)| *colour)
+    }
+
+    //How far a viewshed of `range` reaches by this biome's light; never blind.
+    pub fn sight_range(&self, range: i32) -> i32 {
+        i32::max(1, (range as f32 * self.ambient_light).round() as i32)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
 pub struct Map {
     pub tiles: Vec<TileType>,
     pub width: i32,
@@ -25,10 +143,37 @@ pub struct Map {
     pub bloodstains: HashSet<usize>,
     pub illuminated_tiles: HashSet<usize>,
     pub view_blocked: HashSet<usize>,
+    #[serde(default)]
+    pub biome: Biome,
+    //Closed doors that aren't locked: blocked, but a path may go through them.
+    #[serde(default)]
+    pub closed_doors: HashSet<usize>,
 
     #[serde(skip_serializing)]
     #[serde(skip_deserializing)]
-    pub tile_content: Vec<Vec<Entity>>
+    pub tile_content: Vec<Vec<Entity>>,
+    //Tiles blocked by a creature standing there; they move, the terrain doesn't.
+    #[serde(skip_serializing)]
+    #[serde(skip_deserializing)]
+    pub occupied: HashSet<usize>,
+    //Changed by terrain_changed(); no two maps share one, so flow fields (see
+    //dijkstra.rs) can tell whether they were built for this layout.
+    #[serde(skip_serializing)]
+    #[serde(skip_deserializing, default = "next_terrain_version")]
+    pub terrain_version: u64,
+}
+
+static TERRAIN_VERSIONS: AtomicU64 = AtomicU64::new(1);
+
+fn next_terrain_version() -> u64 {
+    TERRAIN_VERSIONS.fetch_add(1, Ordering::Relaxed)
+}
+
+//An empty map, with a terrain version of its own like any other.
+impl Default for Map {
+    fn default() -> Map {
+        Map::new(0, 0, 0)
+    }
 }
 
 impl Map {
@@ -47,26 +192,78 @@ impl Map {
             bloodstains: HashSet::new(),
             illuminated_tiles: HashSet::new(),
             view_blocked: HashSet::new(),
+            biome: Biome::default(),
+            closed_doors: HashSet::new(),
+            occupied: HashSet::new(),
+            terrain_version: next_terrain_version(),
         }
     }
 
+    //Call when what blocks the way, creatures aside, changes: terrain, or a door.
+    pub fn terrain_changed(&mut self) {
+        self.terrain_version = next_terrain_version();
+    }
+
     pub fn xy_idx(&self, x: i32, y: i32) -> usize {
         (y as usize * self.width as usize) + x as usize
     }
 
-    //a valid "exit" is a walkable tile, not a zone transition.
-    fn is_exit_valid(&self, x:i32, y:i32) -> bool {
-        if x < 1 || x > self.width-1 || y < 1 || y > self.height-1 {return false;}
-        let idx = self.xy_idx(x, y);
-        !self.blocked[idx]
+    //Turns a secret door or hidden passage into plain floor, and says which it was.
+    //The door that belongs in a secret doorway is the finder's to spawn.
+    pub fn reveal_secret(&mut self, idx: usize) -> Option<TileType> {
+        let secret = self.tiles[idx];
+        if secret.looks_like() == secret {
+            return None;
+        }
+        self.tiles[idx] = TileType::Floor;
+        self.blocked[idx] = false;
+        self.terrain_changed();
+        Some(secret)
+    }
+
+    //Whether a walker may step onto idx: it isn't blocked, or it's a door they can open.
+    pub fn is_open(&self, idx: usize) -> bool {
+        !self.blocked[idx] || self.closed_doors.contains(&idx)
+    }
+
+    //Whether idx is open but for whoever is standing there.
+    pub fn is_open_terrain(&self, idx: usize) -> bool {
+        self.is_open(idx) || self.occupied.contains(&idx)
+    }
+
+    //The tiles one step from idx for which `passable` holds, each with what the step costs.
+    pub fn exits_where(&self, idx: usize, passable: impl Fn(usize) -> bool) -> SmallVec<[(usize, f32); 10]> {
+        let mut exits = SmallVec::new();
+        let x = idx as i32 % self.width;
+        let y = idx as i32 / self.width;
+        let w = self.width as usize;
+        let valid = |x: i32, y: i32| x >= 1 && x < self.width && y >= 1 && y < self.height
+                                     && passable(self.xy_idx(x, y));
+
+        if valid(x-1, y) {exits.push((idx-1, self.step_cost(idx-1, 1.0)))};
+        if valid(x+1, y) {exits.push((idx+1, self.step_cost(idx+1, 1.0)))};
+        if valid(x, y-1) {exits.push((idx-w, self.step_cost(idx-w, 1.0)))};
+        if valid(x, y+1) {exits.push((idx+w, self.step_cost(idx+w, 1.0)))};
+
+        if valid(x-1, y-1) {exits.push(((idx-w) - 1, self.step_cost((idx-w) - 1, 1.45)));}
+        if valid(x+1, y-1) {exits.push(((idx-w) + 1, self.step_cost((idx-w) + 1, 1.45)));}
+        if valid(x-1, y+1) {exits.push(((idx+w) - 1, self.step_cost((idx+w) - 1, 1.45)));}
+        if valid(x+1, y+1) {exits.push(((idx+w) + 1, self.step_cost((idx+w) + 1, 1.45)));}
+
+        exits
     }
 
     pub fn populate_blocked(&mut self) {
         for (i, tile) in self.tiles.iter_mut().enumerate() {
-            self.blocked[i] = *tile == TileType::Wall;
+            self.blocked[i] = !tile.is_walkable();
         }
     }
 
+    //The cost of stepping onto idx: the step's distance weighted by its terrain.
+    pub fn step_cost(&self, idx: usize, distance: f32) -> f32 {
+        distance * self.tiles[idx].movement_cost()
+    }
+
     pub fn clear_content_index(&mut self) {
         for content in self.tile_content.iter_mut() {
             content.clear();
@@ -77,7 +274,7 @@ impl Map {
 impl BaseMap for Map {
     fn is_opaque(&self, idx:usize) -> bool {
         let idx_u = idx as usize;
-        self.tiles[idx_u] == TileType::Wall || self.view_blocked.contains(&idx_u)
+        self.tiles[idx_u].is_opaque() || self.view_blocked.contains(&idx_u)
     }
 
     fn get_pathing_distance(&self, idx1:usize, idx2:usize) -> f32 {
@@ -89,22 +286,7 @@ impl BaseMap for Map {
 
     //an exit is an adjacent walkable tile, not a zone transition.
     fn get_available_exits(&self, idx:usize) -> SmallVec<[(usize, f32); 10]> {
-        let mut exits = SmallVec::new();
-        let x = idx as i32 % self.width;
-        let y = idx as i32 / self.width;
-        let w = self.width as usize;
-
-        if self.is_exit_valid(x-1, y) {exits.push((idx-1, 1.0))};
-        if self.is_exit_valid(x+1, y) {exits.push((idx+1, 1.0))};
-        if self.is_exit_valid(x, y-1) {exits.push((idx-w, 1.0))};
-        if self.is_exit_valid(x, y+1) {exits.push((idx+w, 1.0))};
-
-        if self.is_exit_valid(x-1, y-1) {exits.push(((idx-w) - 1, 1.45));}
-        if self.is_exit_valid(x+1, y-1) {exits.push(((idx-w) + 1, 1.45));}
-        if self.is_exit_valid(x-1, y+1) {exits.push(((idx+w) - 1, 1.45));}
-        if self.is_exit_valid(x+1, y+1) {exits.push(((idx+w) + 1, 1.45));}
-
-        exits
+        self.exits_where(idx, |idx| self.is_open(idx))
     }
 }
 
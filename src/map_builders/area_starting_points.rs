@@ -1,11 +1,17 @@
 use super::{MetaMapBuilder, BuilderMap, Position, TileType};
 use bracket_lib::prelude::RandomNumberGenerator;
+use serde::Deserialize;
 
-//takes in a preferred starting point and finds the closes legal starting position to that point.
+//takes in a preferred starting point and finds the closes legal starting position to that point:
+//a floor tile in the largest open area, so the start isn't walled into some scrap of the map.
 
+#[derive(Deserialize)]
+#[serde(rename_all = "lowercase")]
 #[allow(dead_code)]
 pub enum XStart { LEFT, CENTER, RIGHT }
 
+#[derive(Deserialize)]
+#[serde(rename_all = "lowercase")]
 #[allow(dead_code)]
 pub enum YStart { TOP, CENTER, BOTTOM }
 
@@ -44,9 +50,10 @@ impl AreaStartingPosition {
             YStart::BOTTOM => seed_y = build_data.map.height - 2
         }
 
+        let largest = largest_area(build_data);
         let mut available_floors : Vec<(usize, f32)> = Vec::new();
         for (idx, tiletype) in build_data.map.tiles.iter().enumerate() {
-            if *tiletype == TileType::Floor {
+            if *tiletype == TileType::Floor && largest[idx] {
                 available_floors.push(
                     (
                         idx,
@@ -70,3 +77,33 @@ impl AreaStartingPosition {
         build_data.starting_position = Some(Position{x : start_x, y: start_y});
     }
 }
+
+//The tiles of the biggest area that can be walked around in, diagonals included.
+fn largest_area(build_data : &BuilderMap) -> Vec<bool> {
+    let map = &build_data.map;
+    let mut area_of = vec![usize::MAX; map.tiles.len()];
+    let mut sizes : Vec<usize> = Vec::new();
+    for first in 0..map.tiles.len() {
+        if area_of[first] != usize::MAX || !map.tiles[first].is_walkable() { continue; }
+        let area = sizes.len();
+        let mut size = 0;
+        let mut open = vec![first];
+        area_of[first] = area;
+        while let Some(idx) = open.pop() {
+            size += 1;
+            let (x, y) = (idx as i32 % map.width, idx as i32 / map.width);
+            for (dx, dy) in [(-1, -1), (0, -1), (1, -1), (-1, 0), (1, 0), (-1, 1), (0, 1), (1, 1)].iter() {
+                let (nx, ny) = (x + dx, y + dy);
+                if nx < 0 || ny < 0 || nx >= map.width || ny >= map.height { continue; }
+                let next = map.xy_idx(nx, ny);
+                if area_of[next] == usize::MAX && map.tiles[next].is_walkable() {
+                    area_of[next] = area;
+                    open.push(next);
+                }
+            }
+        }
+        sizes.push(size);
+    }
+    let biggest = (0..sizes.len()).max_by_key(|area| sizes[*area]);
+    area_of.iter().map(|area| Some(*area) == biggest).collect()
+}
@@ -1,4 +1,4 @@
-use super::{MetaMapBuilder, BuilderMap, Rect, draw_corridor };
+use super::{MetaMapBuilder, BuilderMap, Rect, TileType, draw_corridor };
 use bracket_lib::prelude::RandomNumberGenerator;
 
 pub struct BspCorridors {}
@@ -28,10 +28,8 @@ impl BspCorridors {
         for i in 0..rooms.len()-1 {
             let room = rooms[i];
             let next_room = rooms[i+1];
-            let start_x = room.x1 + (rng.roll_dice(1, i32::abs(room.x1 - room.x2))-1);
-            let start_y = room.y1 + (rng.roll_dice(1, i32::abs(room.y1 - room.y2))-1);
-            let end_x = next_room.x1 + (rng.roll_dice(1, i32::abs(next_room.x1 - next_room.x2))-1);
-            let end_y = next_room.y1 + (rng.roll_dice(1, i32::abs(next_room.y1 - next_room.y2))-1);
+            let (start_x, start_y) = self.point_in(room, rng, build_data);
+            let (end_x, end_y) = self.point_in(next_room, rng, build_data);
             let corridor = draw_corridor(&mut build_data.map, start_x, start_y, end_x, end_y);
             corridors.push(corridor);
             build_data.take_snapshot();
@@ -39,4 +37,16 @@ impl BspCorridors {
 
         build_data.corridors = Some(corridors);
     }
+
+    //A random spot on the room's floor. Rooms are dug from x1 + 1 to x2 (and likewise
+    //down), and a round one doesn't fill its corners, so a miss falls back on the middle.
+    fn point_in(&self, room : Rect, rng : &mut RandomNumberGenerator, build_data : &BuilderMap) -> (i32, i32) {
+        let x = room.x1 + rng.roll_dice(1, i32::abs(room.x1 - room.x2));
+        let y = room.y1 + rng.roll_dice(1, i32::abs(room.y1 - room.y2));
+        if build_data.map.tiles[build_data.map.xy_idx(x, y)] == TileType::Floor {
+            (x, y)
+        } else {
+            room.center()
+        }
+    }
 }
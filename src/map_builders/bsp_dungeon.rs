@@ -35,7 +35,7 @@ impl BspDungeonBuilder {
             let rect = self.get_random_rect(rng);
             let candidate = self.get_random_sub_rect(rect, rng);
 
-            if self.is_possible(rect, build_data, &rooms) {
+            if self.is_possible(candidate, build_data, &rooms) {
                 rooms.push(candidate);
                 self.add_subrects(rect);
             }
@@ -78,6 +78,7 @@ impl BspDungeonBuilder {
         result
     }
 
+    //True if `rect`, and a two-tile margin around it, is solid wall and clear of every room.
     fn is_possible(&self, rect: Rect, build_data: &BuilderMap, rooms: &Vec<Rect>) -> bool {
         let mut expanded = rect;
         expanded.x1 -= 2;
@@ -1,7 +1,9 @@
 use super::{Map, TileType};
 use std::cmp::{max, min};
+use serde::Deserialize;
 
-#[derive(PartialEq, Copy, Clone)]
+#[derive(PartialEq, Copy, Clone, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum Symmetry { None, Horizontal, Vertical, Both }
 
 pub fn apply_horizontal_tunnel(map : &mut Map, x1:i32, x2:i32, y:i32) -> Vec<usize> {
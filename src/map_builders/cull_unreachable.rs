@@ -1,5 +1,6 @@
 use super::{MetaMapBuilder, BuilderMap, TileType};
 use bracket_lib::prelude::RandomNumberGenerator;
+use crate::dijkstra::DijkstraMap;
 
 pub struct CullUnreachable {}
 
@@ -22,17 +23,15 @@ impl CullUnreachable {
             starting_pos.y
         );
         build_data.map.populate_blocked();
-        let map_starts : Vec<usize> = vec![start_idx];
-        let dijkstra_map = bracket_lib::prelude::DijkstraMap::new(build_data.map.width as usize,
-            build_data.map.height as usize, &map_starts , &build_data.map, 1000.0);
+        let dijkstra_map = DijkstraMap::new(&build_data.map, &[start_idx], 1000.0);
         for (i, tile) in build_data.map.tiles.iter_mut().enumerate() {
-            if *tile == TileType::Floor {
-                let distance_to_start = dijkstra_map.map[i];
-                // We can't get to this tile - so we'll make it a wall
-                if distance_to_start == std::f32::MAX {
-                    *tile = TileType::Wall;
-                }
+            // We can't get to this tile - so we'll make it a wall
+            if tile.is_walkable() && !dijkstra_map.reachable(i) {
+                *tile = TileType::Wall;
             }
         }
+        // Anything already placed out there is walled up with it
+        let tiles = &build_data.map.tiles;
+        build_data.spawn_list.retain(|(idx, _)| tiles[*idx].is_walkable());
     }
 }
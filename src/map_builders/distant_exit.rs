@@ -1,5 +1,6 @@
 use super::{MetaMapBuilder, BuilderMap, TileType};
 use bracket_lib::prelude::RandomNumberGenerator;
+use crate::dijkstra::DijkstraMap;
 
 pub struct DistantExit {}
 
@@ -22,24 +23,11 @@ impl DistantExit {
             starting_pos.y
         );
         build_data.map.populate_blocked();
-        let map_starts : Vec<usize> = vec![start_idx];
-        let dijkstra_map = bracket_lib::prelude::DijkstraMap::new(build_data.map.width as usize, build_data.map.height as usize, &map_starts , &build_data.map, 1000.0);
-        let mut exit_tile = (0, 0.0f32);
-        for (i, tile) in build_data.map.tiles.iter_mut().enumerate() {
-            if *tile == TileType::Floor {
-                let distance_to_start = dijkstra_map.map[i];
-                if distance_to_start != std::f32::MAX {
-                    // If it is further away than our current exit candidate, move the exit
-                    if distance_to_start > exit_tile.1 {
-                        exit_tile.0 = i;
-                        exit_tile.1 = distance_to_start;
-                    }
-                }
-            }
-        }
+        let dijkstra_map = DijkstraMap::new(&build_data.map, &[start_idx], 1000.0);
+        let tiles = &build_data.map.tiles;
+        let stairs_idx = dijkstra_map.farthest(|i| tiles[i] == TileType::Floor).unwrap_or(0);
 
         // Place a staircase
-        let stairs_idx = exit_tile.0;
         build_data.map.tiles[stairs_idx] = TileType::StairsDown;
         build_data.take_snapshot();
     }
@@ -1,10 +1,14 @@
 use super::{InitialMapBuilder, BuilderMap, MetaMapBuilder, TileType, Position, paint, Symmetry};
 use bracket_lib::prelude::RandomNumberGenerator;
+use serde::Deserialize;
 
-#[derive(PartialEq, Copy, Clone)]
+#[derive(PartialEq, Copy, Clone, Deserialize)]
+#[serde(rename_all = "snake_case")]
 #[allow(dead_code)]
 pub enum DrunkSpawnMode { StartingPoint, Random }
 
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct DrunkardSettings {
     pub spawn_mode : DrunkSpawnMode,
     pub drunken_lifetime : i32,
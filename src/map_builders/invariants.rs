@@ -0,0 +1,107 @@
+use super::{BuilderMap, TileType};
+use crate::dijkstra::{DijkstraMap, UNREACHABLE};
+
+/* Map invariants
+ *
+ * What every finished level must get right, whatever built it: the player
+ * starts on solid ground inside the map's wall, the way down (and, below the
+ * first level, the way back up) can be walked to from there, nothing spawns
+ * inside rock, and every door stands in a gap between two walls.
+ *
+ * violations() lists what a build got wrong, and stats() describes it; the
+ * mapgen tests check the first across many seeds, and `mapgen --stats` reports
+ * both per recipe and starter.
+ */
+
+#[derive(PartialEq, Clone, Copy, Debug, Default)]
+pub struct MapStats {
+    pub open_ratio: f32, //walkable tiles, as a share of the map
+    pub rooms: Option<usize>,
+    pub spawns: usize,
+    pub doors: usize,
+}
+
+pub fn violations(build_data: &BuilderMap) -> Vec<String> {
+    let map = &build_data.map;
+    let mut problems = Vec::new();
+    if map.tiles.len() != (map.width * map.height) as usize {
+        problems.push(format!("{} tiles for a {}x{} map", map.tiles.len(), map.width, map.height));
+        return problems;
+    }
+    let at = |idx: usize| (idx as i32 % map.width, idx as i32 / map.width);
+    let in_bounds = |x: i32, y: i32| x >= 0 && y >= 0 && x < map.width && y < map.height;
+    let walkable = |x: i32, y: i32| in_bounds(x, y) && map.tiles[map.xy_idx(x, y)].is_walkable();
+
+    for (idx, tile) in map.tiles.iter().enumerate() {
+        let (x, y) = at(idx);
+        if tile.is_walkable() && (x == 0 || y == 0 || x == map.width - 1 || y == map.height - 1) {
+            problems.push(format!("{:?} on the edge of the map at {}, {}", tile, x, y));
+        }
+    }
+
+    let start = match &build_data.starting_position {
+        Some(start) if walkable(start.x, start.y) => map.xy_idx(start.x, start.y),
+        Some(start) => {
+            problems.push(format!("start at {}, {} isn't walkable", start.x, start.y));
+            return problems;
+        }
+        None => {
+            problems.push("no start position".to_string());
+            return problems;
+        }
+    };
+    let reachable = reachable_from(build_data, start);
+
+    let mut stairs = vec![TileType::StairsDown];
+    if map.depth > 1 {
+        stairs.push(TileType::StairsUp);
+    }
+    for kind in stairs.iter() {
+        let found: Vec<usize> = (0..map.tiles.len()).filter(|idx| map.tiles[*idx] == *kind).collect();
+        if found.is_empty() {
+            problems.push(format!("no {:?}", kind));
+        }
+        for idx in found.into_iter().filter(|idx| !reachable[*idx]) {
+            let (x, y) = at(idx);
+            problems.push(format!("{:?} at {}, {} can't be reached from the start", kind, x, y));
+        }
+    }
+
+    for (idx, name) in build_data.spawn_list.iter() {
+        if *idx >= map.tiles.len() {
+            problems.push(format!("{} spawns off the map", name));
+            continue;
+        }
+        let (x, y) = at(*idx);
+        if !map.tiles[*idx].is_walkable() {
+            problems.push(format!("{} spawns in a {:?} at {}, {}", name, map.tiles[*idx], x, y));
+        } else if name == "Door" || name == "Locked Door" {
+            let solid = |x: i32, y: i32| in_bounds(x, y) && map.tiles[map.xy_idx(x, y)] == TileType::Wall;
+            let across = solid(x, y - 1) && solid(x, y + 1) && walkable(x - 1, y) && walkable(x + 1, y);
+            let down = solid(x - 1, y) && solid(x + 1, y) && walkable(x, y - 1) && walkable(x, y + 1);
+            if !across && !down {
+                problems.push(format!("door at {}, {} isn't between two walls", x, y));
+            }
+        }
+    }
+
+    problems
+}
+
+pub fn stats(build_data: &BuilderMap) -> MapStats {
+    let map = &build_data.map;
+    let open = map.tiles.iter().filter(|tile| tile.is_walkable()).count();
+    MapStats {
+        open_ratio: open as f32 / map.tiles.len().max(1) as f32,
+        rooms: build_data.rooms.as_ref().map(|rooms| rooms.len()),
+        spawns: build_data.spawn_list.len(),
+        doors: build_data.spawn_list.iter().filter(|(_, name)| name == "Door" || name == "Locked Door").count(),
+    }
+}
+
+//Every tile a walk from `start` can get to, diagonals included.
+pub(super) fn reachable_from(build_data: &BuilderMap, start: usize) -> Vec<bool> {
+    let map = &build_data.map;
+    let dijkstra_map = DijkstraMap::over(map, &[start], UNREACHABLE, |idx| map.tiles[idx].is_walkable());
+    (0..map.tiles.len()).map(|idx| dijkstra_map.reachable(idx)).collect()
+}
@@ -1,5 +1,5 @@
 use specs::prelude::*;
-use super::{Map, Rect, TileType, Position, spawner, SHOW_MAPGEN_VISUALIZER};
+use super::{Map, Rect, TileType, Position, spawner};
 mod simple_map;
 use simple_map::SimpleMapBuilder;
 mod room_based_spawner;
@@ -19,12 +19,18 @@ mod room_corridors_nearest;
 mod room_corridors_lines;
 mod room_corridor_spawner;
 mod door_placement;
+mod secrets;
+mod terrain_features;
+mod up_stairs;
 use door_placement::DoorPlacement;
+use secrets::SecretPlacement;
+use terrain_features::TerrainFeatures;
+use up_stairs::UpStairs;
 use room_corridor_spawner::CorridorSpawner;
 use room_corridors_lines::StraightLineCorridors;
 use room_corridors_nearest::NearestCorridors;
 use room_draw::RoomDrawer;
-use room_sorter::{RoomSorter, RoomSort};
+use room_sorter::RoomSorter;
 use room_exploder::RoomExploder;
 use room_corner_rounding::RoomCornerRounder;
 use bsp_corridors::BspCorridors;
@@ -52,8 +58,15 @@ mod voronoi;
 use voronoi::VoronoiCellBuilder;
 mod prefab_builder;
 use prefab_builder::PrefabBuilder;
+pub use prefab_builder::{load_prefabs, Prefab, PrefabKind, PrefabLibrary, PREFABS, PREFABS_PATH, PREFAB_SPAWNS, PREFAB_TERRAIN};
+mod waveform_collapse;
+pub use waveform_collapse::WaveformCollapseBuilder;
 mod common;
 use common::*;
+mod registry;
+mod recipes;
+pub use recipes::{load_recipes, RecipeBook, RECIPES, RECIPES_PATH};
+pub mod invariants;
 
 pub trait InitialMapBuilder {
     fn build_map(&mut self, rng: &mut bracket_lib::prelude::RandomNumberGenerator, build_data: &mut BuilderMap);
@@ -69,19 +82,30 @@ pub struct BuilderMap {
     pub starting_position: Option<Position>,
     pub rooms: Option<Vec<Rect>>,
     pub corridors: Option<Vec<Vec<usize>>>,
-    pub snapshot_history: Vec<Map>,
+    pub snapshot_history: Vec<Snapshot>,
     pub width: i32,
     pub height: i32,
+    //Keep a copy of the map after each step, for the mapgen visualizer and `mapgen --snapshots`.
+    pub take_snapshots: bool,
+    //The step now building, which its snapshots are labelled with.
+    step: String,
+}
+
+//The map as it stood partway through a build, and which step of the chain made it.
+#[derive(Clone)]
+pub struct Snapshot {
+    pub step: String,
+    pub map: Map,
 }
 
 impl BuilderMap {
     fn take_snapshot(&mut self) {
-        if SHOW_MAPGEN_VISUALIZER {
+        if self.take_snapshots {
             let mut snapshot = self.map.clone();
             for v in snapshot.revealed_tiles.iter_mut() {
                 *v = true;
             }
-            self.snapshot_history.push(snapshot);
+            self.snapshot_history.push(Snapshot { step: self.step.clone(), map: snapshot });
         }
     }
 }
@@ -90,6 +114,8 @@ pub struct BuilderChain {
     starter: Option<Box<dyn InitialMapBuilder>>,
     builders: Vec<Box<dyn MetaMapBuilder>>,
     pub build_data: BuilderMap,
+    //What each builder is, starter first.
+    pub steps: Vec<String>,
 }
 
 impl BuilderChain {
@@ -106,7 +132,10 @@ impl BuilderChain {
                 snapshot_history: Vec::new(),
                 width,
                 height,
-            }
+                take_snapshots: false,
+                step: String::new(),
+            },
+            steps: Vec::new(),
         }
     }
 
@@ -125,11 +154,13 @@ impl BuilderChain {
         match &mut self.starter {
             None => panic!("Cannot run map builder chain without starter build."),
             Some(starter) => {
+                self.build_data.step = step_name(&self.steps, 0);
                 starter.build_map(rng, &mut self.build_data);
             }
         }
 
-        for metabuilder in self.builders.iter_mut() {
+        for (i, metabuilder) in self.builders.iter_mut().enumerate() {
+            self.build_data.step = step_name(&self.steps, i + 1);
             metabuilder.build_map(rng, &mut self.build_data);
         }
     }
@@ -141,138 +172,68 @@ impl BuilderChain {
     }
 }
 
+//Chains put together in code may not have named every step.
+fn step_name(steps: &[String], i: usize) -> String {
+    steps.get(i).cloned().unwrap_or_else(|| format!("step {}", i + 1))
+}
+
+//A level for `new_depth`, in whichever biome raws/map_recipes.yaml gives that
+//depth and built by one of the biome's recipes.
 pub fn random_builder(new_depth: i32, rng: &mut bracket_lib::prelude::RandomNumberGenerator,
                                     width: i32, height: i32) -> BuilderChain {
-    let mut builder = BuilderChain::new(new_depth, width, height);
-    let type_roll = rng.roll_dice(1, 2);
-    match type_roll {
-        1 => random_room_builder(rng, &mut builder),
-        _ => random_shape_builder(rng, &mut builder)
-    }
-
-    if rng.roll_dice(1, 20)==1 {
-        builder.with(PrefabBuilder::sectional(prefab_builder::prefab_sections::UNDERGROUND_FORT));
-    }
-
-    builder.with(DoorPlacement::new());
-    builder.with(PrefabBuilder::vaults());
-
-    return builder;
+    let biome = RECIPES.lock().unwrap().biome_for(new_depth, rng);
+    biome.and_then(|biome| themed_builder(&biome, None, new_depth, rng, width, height))
+        .unwrap_or_else(|| {
+            bracket_lib::prelude::console::log(format!("No map recipe for depth {}; using simple rooms.", new_depth));
+            let mut builder = fallback_builder(new_depth, width, height);
+            add_up_stairs(&mut builder, new_depth);
+            builder
+        })
 }
 
-fn random_start_position(rng: &mut bracket_lib::prelude::RandomNumberGenerator) -> (XStart, YStart) {
-    let x;
-    let xroll = rng.roll_dice(1, 3);
-    match xroll {
-        1 => x = XStart::LEFT,
-        2 => x = XStart::CENTER,
-        _ => x = XStart::RIGHT
-    }
-
-    let y;
-    let yroll = rng.roll_dice(1, 3);
-    match yroll {
-        1 => y = YStart::BOTTOM,
-        2 => y = YStart::CENTER,
-        _ => y = YStart::TOP
-    }
-
-    (x, y)
+//A level in the named biome, built by `recipe` or else one of the biome's own.
+//None if there's no such biome or recipe.
+pub fn themed_builder(biome: &str, recipe: Option<&str>, new_depth: i32,
+                      rng: &mut bracket_lib::prelude::RandomNumberGenerator,
+                      width: i32, height: i32) -> Option<BuilderChain> {
+    let recipes = RECIPES.lock().unwrap();
+    let theme = recipes.biome(biome)?.clone();
+    let recipe = match recipe {
+        Some(recipe) => recipe.to_string(),
+        None => recipes.recipe_in(biome, new_depth, rng)?,
+    };
+    let mut builder = recipes.build_chain(&recipe, new_depth, rng, width, height)?;
+    //Set before any step runs, so the spawners fill it from the biome's table.
+    builder.build_data.map.biome = theme;
+    add_up_stairs(&mut builder, new_depth);
+    Some(builder)
 }
 
-fn random_room_builder(rng: &mut bracket_lib::prelude::RandomNumberGenerator, builder : &mut BuilderChain) {
-    let build_roll = rng.roll_dice(1, 4);
-    match build_roll {
-        1..=2 => builder.start_with(SimpleMapBuilder::new()),
-        3..=4 => builder.start_with(BspDungeonBuilder::new()),
-        _ => builder.start_with(BspInteriorBuilder::new()) //<--currently unreachable
-    }
-
-    // BSP Interior still makes holes in the walls
-    if build_roll != 3 {
-        // Sort by one of the 5 available algorithms
-        let sort_roll = rng.roll_dice(1, 5);
-        match sort_roll {
-            1 => builder.with(RoomSorter::new(RoomSort::LEFTMOST)),
-            2 => builder.with(RoomSorter::new(RoomSort::RIGHTMOST)),
-            3 => builder.with(RoomSorter::new(RoomSort::TOPMOST)),
-            4 => builder.with(RoomSorter::new(RoomSort::BOTTOMMOST)),
-            _ => builder.with(RoomSorter::new(RoomSort::CENTRAL)),
-        }
-
-        builder.with(RoomDrawer::new());
-
-        let corridor_roll = rng.roll_dice(1, 4);
-        match corridor_roll {
-            1 => builder.with(DoglegCorridors::new()),
-            2 => builder.with(NearestCorridors::new()),
-            3 => builder.with(StraightLineCorridors::new()),
-            _ => builder.with(BspCorridors::new())
-        }
-
-        let cspawn_roll = rng.roll_dice(1, 2);
-        if cspawn_roll == 1 {
-            builder.with(CorridorSpawner::new());
-        }
-
-        let modifier_roll = rng.roll_dice(1, 6);
-        match modifier_roll {
-            1 => builder.with(RoomExploder::new()),
-            2 => builder.with(RoomCornerRounder::new()),
-            _ => {}
-        }
-    }
-
-    let start_roll = rng.roll_dice(1, 2);
-    match start_roll {
-        1 => builder.with(RoomBasedStartingPosition::new()),
-        _ => {
-            let (start_x, start_y) = random_start_position(rng);
-            builder.with(AreaStartingPosition::new(start_x, start_y));
-        }
-    }
-
-    let exit_roll = rng.roll_dice(1, 2);
-    match exit_roll {
-        1 => builder.with(RoomBasedStairs::new()),
-        _ => builder.with(DistantExit::new())
-    }
-
-    let spawn_roll = rng.roll_dice(1, 2);
-    match spawn_roll {
-        1 => builder.with(RoomBasedSpawner::new()),
-        _ => builder.with(VoronoiSpawning::new())
-    }
+//The named recipe, outside any biome. None if there's no such recipe.
+pub fn named_builder(name: &str, new_depth: i32, rng: &mut bracket_lib::prelude::RandomNumberGenerator,
+                     width: i32, height: i32) -> Option<BuilderChain> {
+    let mut builder = RECIPES.lock().unwrap().build_chain(name, new_depth, rng, width, height)?;
+    add_up_stairs(&mut builder, new_depth);
+    Some(builder)
 }
 
-fn random_shape_builder(rng: &mut bracket_lib::prelude::RandomNumberGenerator, builder : &mut BuilderChain) {
-    let builder_roll = rng.roll_dice(1, 16);
-    match builder_roll {
-        1 => builder.start_with(CellularAutomataBuilder::new()),
-        2 => builder.start_with(DrunkardsWalkBuilder::open_area()),
-        3 => builder.start_with(DrunkardsWalkBuilder::open_halls()),
-        4 => builder.start_with(DrunkardsWalkBuilder::winding_passages()),
-        5 => builder.start_with(DrunkardsWalkBuilder::fat_passages()),
-        6 => builder.start_with(DrunkardsWalkBuilder::fearful_symmetry()),
-        7 => builder.start_with(MazeBuilder::new()),
-        8 => builder.start_with(DLABuilder::walk_inwards()),
-        9 => builder.start_with(DLABuilder::walk_outwards()),
-        10 => builder.start_with(DLABuilder::central_attractor()),
-        11 => builder.start_with(DLABuilder::insectoid()),
-        12 => builder.start_with(VoronoiCellBuilder::pythagoras()),
-        13 => builder.start_with(VoronoiCellBuilder::manhattan()),
-        _ => builder.start_with(PrefabBuilder::constant(prefab_builder::prefab_levels::WFC_POPULATED)),
+//Every level below the first is entered from above, where the player starts.
+fn add_up_stairs(builder: &mut BuilderChain, new_depth: i32) {
+    if new_depth > 1 {
+        builder.with(UpStairs::new());
+        builder.steps.push("up_stairs".to_string());
     }
+}
 
-    // Set the start to the center and cull
-    builder.with(AreaStartingPosition::new(XStart::CENTER, YStart::CENTER));
-    builder.with(CullUnreachable::new());
-
-    // Now set the start to a random starting area
-    let (start_x, start_y) = random_start_position(rng);
-    builder.with(AreaStartingPosition::new(start_x, start_y));
-
-    // Setup an exit and spawn mobs
-    builder.with(VoronoiSpawning::new());
-    builder.with(DistantExit::new());
+fn fallback_builder(new_depth: i32, width: i32, height: i32) -> BuilderChain {
+    let mut builder = BuilderChain::new(new_depth, width, height);
+    builder.start_with(SimpleMapBuilder::new());
+    builder.with(RoomDrawer::new());
+    builder.with(DoglegCorridors::new());
+    builder.with(RoomBasedStartingPosition::new());
+    builder.with(RoomBasedStairs::new());
+    builder.with(RoomBasedSpawner::new());
+    builder.steps = ["simple_map", "room_drawer", "dogleg_corridors", "room_based_starting_position",
+                     "room_based_stairs", "room_based_spawner"].iter().map(|s| s.to_string()).collect();
+    builder
 }
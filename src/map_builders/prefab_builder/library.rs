@@ -0,0 +1,368 @@
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+use std::sync::Mutex;
+
+use bracket_lib::prelude::{rex::XpFile, RandomNumberGenerator};
+use serde::Deserialize;
+
+use super::{PREFAB_SPAWNS, PREFAB_TERRAIN};
+use crate::map_builders::recipes::fit_recipes_to;
+use crate::raws::{RawWeight, RawsError};
+use crate::TileType;
+
+/* Prefab library
+ *
+ * Hand-made vaults (rooms), sections and whole levels live in raws/prefabs/,
+ * one YAML file each, named for the file. Like the raws, the directory is
+ * read once at startup by load_prefabs(), on top of the prefabs compiled into
+ * the binary: a file replaces the built-in prefab of its name, and the rest
+ * stay, so the built-in recipes always have what they name. The built-ins
+ * alone stand in if the directory can't be read, and are what tests see.
+ *
+ *   kind: room | section | level
+ *   weight: 2                  # how often a room comes up next to the others;
+ *   min_depth: 3               # weight and depths work as in spawn tables
+ *   max_depth: 8
+ *   placement: { x: left | center | right, y: top | center | bottom }   # sections
+ *   rotate: true               # may be placed turned by any quarter turn
+ *   mirror: true               # may be placed flipped left to right
+ *   legend: { b: Bear Trap }   # this prefab's own glyphs for what it spawns
+ *   key: Key                   # rooms: spawned somewhere outside the room, and
+ *                              # reachable, when the room is placed
+ *   map: |
+ *     ..#..
+ *     .b@b.
+ *
+ * `.` (or a space) is floor, `#` wall, `@` the start, `>` the way down, and
+ * the glyphs in PREFAB_TERRAIN other terrain. Anything else spawns what the
+ * legend says, or failing that PREFAB_SPAWNS, on floor.
+ *
+ * Instead of `map`, `rex: <file>.xp` reads a REXPaint drawing next to the
+ * YAML file. Its `terrain` layer (the first, unless `layers` says otherwise)
+ * is read like a map, and its `entities` layer (the second, if there is one)
+ * only spawns: blank cells there leave the terrain's cell as it is.
+ *
+ * Everything is checked when loaded, down to every glyph, so a prefab never
+ * half-builds.
+ */
+
+pub const PREFABS_PATH: &str = "./raws/prefabs";
+const BUILTIN_PREFABS: &[(&str, &str)] = &[
+    ("checkerboard", include_str!("../../../raws/prefabs/checkerboard.yaml")),
+    ("locked_cache", include_str!("../../../raws/prefabs/locked_cache.yaml")),
+    ("silly_smile", include_str!("../../../raws/prefabs/silly_smile.yaml")),
+    ("totally_not_a_trap", include_str!("../../../raws/prefabs/totally_not_a_trap.yaml")),
+    ("underground_fort", include_str!("../../../raws/prefabs/underground_fort.yaml")),
+    ("wfc_populated", include_str!("../../../raws/prefabs/wfc_populated.yaml")),
+];
+
+lazy_static! {
+    pub static ref PREFABS: Mutex<PrefabLibrary> = Mutex::new(PrefabLibrary::builtin());
+}
+
+#[derive(Deserialize, PartialEq, Eq, Clone, Copy, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum PrefabKind { Room, Section, Level }
+
+#[derive(Deserialize, PartialEq, Eq, Clone, Copy, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum HorizontalPlacement { Left, Center, Right }
+
+#[derive(Deserialize, PartialEq, Eq, Clone, Copy, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum VerticalPlacement { Top, Center, Bottom }
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawPrefab {
+    kind: PrefabKind,
+    #[serde(default = "one")]
+    weight: RawWeight,
+    min_depth: Option<i32>,
+    max_depth: Option<i32>,
+    placement: Option<RawPlacement>,
+    #[serde(default)]
+    rotate: bool,
+    #[serde(default)]
+    mirror: bool,
+    #[serde(default)]
+    legend: BTreeMap<char, String>,
+    key: Option<String>,
+    map: Option<String>,
+    rex: Option<String>,
+    #[serde(default)]
+    layers: RexLayers,
+}
+
+fn one() -> RawWeight {
+    RawWeight::Fixed(1)
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawPlacement {
+    x: HorizontalPlacement,
+    y: VerticalPlacement,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+struct RexLayers {
+    terrain: Option<usize>,
+    entities: Option<usize>,
+}
+
+//What one cell of a prefab puts on the map.
+#[derive(PartialEq, Clone, Debug)]
+pub struct PrefabCell {
+    pub tile: TileType,
+    pub spawn: Option<String>,
+    pub start: bool,
+}
+
+#[derive(Clone, Debug)]
+pub struct Prefab {
+    pub name: String,
+    pub kind: PrefabKind,
+    pub width: usize,
+    pub height: usize,
+    cells: Vec<PrefabCell>,
+    pub weight: RawWeight,
+    pub min_depth: Option<i32>,
+    pub max_depth: Option<i32>,
+    pub placement: (HorizontalPlacement, VerticalPlacement),
+    pub rotate: bool,
+    pub mirror: bool,
+    pub key: Option<String>,
+}
+
+impl Prefab {
+    pub fn cell(&self, x: usize, y: usize) -> &PrefabCell {
+        &self.cells[y * self.width + x]
+    }
+
+    pub fn in_depth(&self, depth: i32) -> bool {
+        self.min_depth.map_or(true, |min| depth >= min) && self.max_depth.map_or(true, |max| depth <= max)
+    }
+
+    //Turned clockwise by `turns` quarter turns, then flipped left to right if `flip`.
+    pub fn transformed(&self, turns: u32, flip: bool) -> Prefab {
+        let mut prefab = self.clone();
+        for _ in 0..turns % 4 {
+            let (width, height) = (prefab.height, prefab.width);
+            let cells = (0..width * height)
+                .map(|i| prefab.cell(i / width, prefab.height - 1 - i % width).clone())
+                .collect();
+            prefab = Prefab { width, height, cells, ..prefab };
+        }
+        if flip {
+            let cells = (0..prefab.cells.len())
+                .map(|i| prefab.cell(prefab.width - 1 - i % prefab.width, i / prefab.width).clone())
+                .collect();
+            prefab.cells = cells;
+        }
+        prefab
+    }
+
+    //As it's to be placed this time: turned and flipped at random, as far as it allows.
+    pub fn roll_transform(&self, rng: &mut RandomNumberGenerator) -> Prefab {
+        let turns = if self.rotate { rng.roll_dice(1, 4) as u32 - 1 } else { 0 };
+        let flip = self.mirror && rng.roll_dice(1, 2) == 2;
+        self.transformed(turns, flip)
+    }
+
+    //Every entity the prefab can spawn, for raws validation.
+    pub fn spawn_names(&self) -> Vec<&String> {
+        let mut names: Vec<&String> = self.cells.iter().filter_map(|cell| cell.spawn.as_ref()).chain(self.key.iter()).collect();
+        names.sort();
+        names.dedup();
+        names
+    }
+}
+
+pub struct PrefabLibrary {
+    prefabs: BTreeMap<String, Prefab>,
+}
+
+impl PrefabLibrary {
+    //Prefabs from (name, yaml) pairs; `dir` is where any REXPaint files they name are.
+    pub fn parse(files: &[(String, String)], dir: Option<&Path>) -> Result<PrefabLibrary, RawsError> {
+        let mut prefabs = BTreeMap::new();
+        for (name, yaml) in files.iter() {
+            let raw: RawPrefab = serde_yaml::from_str(yaml)?;
+            let prefab = build_prefab(name, raw, dir)
+                .map_err(|reason| RawsError::Invalid { name: format!("prefab {}", name), reason })?;
+            if prefabs.insert(name.clone(), prefab).is_some() {
+                return Err(RawsError::DuplicateName(name.clone()));
+            }
+        }
+        Ok(PrefabLibrary { prefabs })
+    }
+
+    pub fn builtin() -> PrefabLibrary {
+        let files: Vec<(String, String)> = BUILTIN_PREFABS.iter()
+            .map(|(name, yaml)| (name.to_string(), yaml.to_string()))
+            .collect();
+        PrefabLibrary::parse(&files, None).expect("Built-in prefabs are invalid.")
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Prefab> {
+        self.prefabs.get(name)
+    }
+
+    pub fn all(&self) -> impl Iterator<Item = &Prefab> {
+        self.prefabs.values()
+    }
+
+    pub fn of_kind(&self, kind: PrefabKind) -> impl Iterator<Item = &Prefab> {
+        self.prefabs.values().filter(move |prefab| prefab.kind == kind)
+    }
+
+    //Adds `other`'s prefabs, in place of any here of the same name.
+    pub fn merge(&mut self, other: PrefabLibrary) {
+        self.prefabs.extend(other.prefabs);
+    }
+}
+
+//Replaces the loaded prefabs with the built-in ones and, over them, the
+//directory's `*.yaml` files. On error, the old ones stay.
+pub fn load_prefabs(dir: &str) -> Result<(), RawsError> {
+    let mut files = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|ext| ext == "yaml") {
+            let name = path.file_stem().unwrap_or_default().to_string_lossy().to_string();
+            files.push((name, std::fs::read_to_string(&path)?));
+        }
+    }
+    files.sort();
+    let mut library = PrefabLibrary::builtin();
+    library.merge(PrefabLibrary::parse(&files, Some(Path::new(dir)))?);
+    fit_recipes_to(&library)?;
+    *PREFABS.lock().unwrap() = library;
+    Ok(())
+}
+
+fn build_prefab(name: &str, raw: RawPrefab, dir: Option<&Path>) -> Result<Prefab, String> {
+    let (width, height, cells) = match (&raw.map, &raw.rex) {
+        (Some(map), None) => text_cells(map, &raw.legend)?,
+        (None, Some(file)) => {
+            let dir = dir.ok_or_else(|| format!("can't read {} here", file))?;
+            rex_cells(&dir.join(file), &raw.layers, &raw.legend)?
+        }
+        _ => return Err("needs one of `map` and `rex`".to_string()),
+    };
+    if width == 0 || height == 0 {
+        return Err("the map is empty".to_string());
+    }
+    if let (Some(min), Some(max)) = (raw.min_depth, raw.max_depth) {
+        if min > max {
+            return Err(format!("min_depth {} is deeper than max_depth {}", min, max));
+        }
+    }
+    let placement = match (raw.kind, raw.placement) {
+        (PrefabKind::Section, Some(placement)) => (placement.x, placement.y),
+        (PrefabKind::Section, None) => return Err("a section needs a `placement`".to_string()),
+        (_, Some(_)) => return Err("only sections have a `placement`".to_string()),
+        (_, None) => (HorizontalPlacement::Left, VerticalPlacement::Top),
+    };
+    if raw.key.is_some() && raw.kind != PrefabKind::Room {
+        return Err("only rooms have a `key`".to_string());
+    }
+
+    Ok(Prefab {
+        name: name.to_string(),
+        kind: raw.kind,
+        width,
+        height,
+        cells,
+        weight: raw.weight,
+        min_depth: raw.min_depth,
+        max_depth: raw.max_depth,
+        placement,
+        rotate: raw.rotate,
+        mirror: raw.mirror,
+        key: raw.key,
+    })
+}
+
+fn text_cells(map: &str, legend: &BTreeMap<char, String>) -> Result<(usize, usize, Vec<PrefabCell>), String> {
+    let rows: Vec<Vec<char>> = map.lines().map(|row| row.chars().collect()).collect();
+    let width = rows.first().map_or(0, |row| row.len());
+    let mut cells = Vec::new();
+    for (y, row) in rows.iter().enumerate() {
+        if row.len() != width {
+            return Err(format!("row {} is {} wide, not {}", y + 1, row.len(), width));
+        }
+        for (x, ch) in row.iter().enumerate() {
+            cells.push(glyph_cell(*ch, legend).map_err(|e| format!("{} at {}, {}", e, x, y))?);
+        }
+    }
+    Ok((width, rows.len(), cells))
+}
+
+fn rex_cells(path: &Path, layers: &RexLayers, legend: &BTreeMap<char, String>)
+             -> Result<(usize, usize, Vec<PrefabCell>), String> {
+    let file = File::open(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let xp = XpFile::read(&mut BufReader::new(file)).map_err(|e| format!("{}: {}", path.display(), e))?;
+
+    let terrain_layer = layers.terrain.unwrap_or(0);
+    let terrain = xp.layers.get(terrain_layer).ok_or_else(|| format!("no layer {} in {}", terrain_layer, path.display()))?;
+    let entities = match layers.entities {
+        Some(layer) => Some(xp.layers.get(layer).ok_or_else(|| format!("no layer {} in {}", layer, path.display()))?),
+        None => xp.layers.get(1).filter(|_| layers.terrain.is_none()),
+    };
+
+    let mut cells = Vec::new();
+    for y in 0..terrain.height {
+        for x in 0..terrain.width {
+            let at = |e: String| format!("{} at {}, {}", e, x, y);
+            let mut cell = glyph_cell(rex_char(terrain.get(x, y).map_or(0, |c| c.ch)), legend).map_err(at)?;
+            let ch = entities.and_then(|layer| layer.get(x, y)).map_or(0, |c| c.ch);
+            if ch != 0 && rex_char(ch) != ' ' {
+                let name = spawn_name(rex_char(ch), legend).ok_or_else(|| at(format!("unknown glyph '{}'", rex_char(ch))))?;
+                if !cell.tile.is_walkable() {
+                    return Err(at(format!("{} spawns in a {:?}", name, cell.tile)));
+                }
+                cell.spawn = Some(name);
+            }
+            cells.push(cell);
+        }
+    }
+    Ok((terrain.width, terrain.height, cells))
+}
+
+//REXPaint stores code page 437; prefabs only use its ASCII half.
+fn rex_char(ch: u32) -> char {
+    if ch == 0 { ' ' } else { ch as u8 as char }
+}
+
+fn glyph_cell(ch: char, legend: &BTreeMap<char, String>) -> Result<PrefabCell, String> {
+    let cell = |tile: TileType| PrefabCell { tile, spawn: None, start: false };
+    if let Some(name) = legend.get(&ch) {
+        return Ok(PrefabCell { spawn: Some(name.clone()), ..cell(TileType::Floor) });
+    }
+    match ch {
+        '.' | ' ' | '\u{a0}' => Ok(cell(TileType::Floor)),
+        '#' => Ok(cell(TileType::Wall)),
+        '@' => Ok(PrefabCell { start: true, ..cell(TileType::Floor) }),
+        '>' => Ok(cell(TileType::StairsDown)),
+        _ => {
+            if let Some((_, tile)) = PREFAB_TERRAIN.iter().find(|(glyph, _)| *glyph == ch) {
+                Ok(cell(*tile))
+            } else if let Some(name) = spawn_name(ch, legend) {
+                Ok(PrefabCell { spawn: Some(name), ..cell(TileType::Floor) })
+            } else {
+                Err(format!("unknown glyph '{}'", ch))
+            }
+        }
+    }
+}
+
+//The prefab's own legend first, then the shared one.
+fn spawn_name(ch: char, legend: &BTreeMap<char, String>) -> Option<String> {
+    legend.get(&ch).cloned()
+        .or_else(|| PREFAB_SPAWNS.iter().find(|(glyph, _)| *glyph == ch).map(|(_, name)| name.to_string()))
+}
@@ -1,16 +1,40 @@
 use super::{InitialMapBuilder, MetaMapBuilder, BuilderMap, TileType, Position};
 use bracket_lib::prelude::RandomNumberGenerator;
-pub mod prefab_levels;
-pub mod prefab_sections;
-pub mod prefab_rooms;
+use crate::random_table::RandomTable;
+mod library;
+pub use library::*;
 use std::collections::HashSet;
-
-#[derive(PartialEq, Copy, Clone)]
+use crate::dijkstra::{DijkstraMap, UNREACHABLE};
+
+//What each prefab glyph spawns, unless the prefab's legend says otherwise;
+//raws::RawMaster::validate() checks these exist.
+pub const PREFAB_SPAWNS: &[(char, &str)] = &[
+    ('g', "Goblin"),
+    ('o', "Orc"),
+    ('^', "Bear Trap"),
+    ('%', "Rations"),
+    ('!', "Health Potion"),
+];
+
+//Terrain other than plain floor and wall, by the glyph that stands for it in a prefab.
+pub const PREFAB_TERRAIN: &[(char, TileType)] = &[
+    ('~', TileType::ShallowWater),
+    ('W', TileType::DeepWater),
+    (',', TileType::Mud),
+    (';', TileType::Rubble),
+    ('"', TileType::Grass),
+    (':', TileType::Chasm),
+    ('=', TileType::Bridge),
+    ('L', TileType::Lava),
+    ('S', TileType::SecretDoor),
+    ('H', TileType::HiddenPassage),
+];
+
+#[derive(Clone)]
 #[allow(dead_code)]
 pub enum PrefabMode { 
-    RexLevel{ template : &'static str },
-    Constant{ level : prefab_levels::PrefabLevel },
-    Sectional{ section : prefab_sections::PrefabSection },
+    Constant{ level : Prefab },
+    Sectional{ section : Prefab },
     RoomVaults
 }
 
@@ -41,21 +65,14 @@ impl PrefabBuilder {
     }
 
     #[allow(dead_code)]
-    pub fn rex_level(template : &'static str) -> Box<PrefabBuilder> {
-        Box::new(PrefabBuilder{
-            mode : PrefabMode::RexLevel{ template },
-        })
-    }
-
-    #[allow(dead_code)]
-    pub fn constant(level : prefab_levels::PrefabLevel) -> Box<PrefabBuilder> {
+    pub fn constant(level : Prefab) -> Box<PrefabBuilder> {
         Box::new(PrefabBuilder{
             mode : PrefabMode::Constant{ level },
         })
     }
 
     #[allow(dead_code)]
-    pub fn sectional(section : prefab_sections::PrefabSection) -> Box<PrefabBuilder> {
+    pub fn sectional(section : Prefab) -> Box<PrefabBuilder> {
         Box::new(PrefabBuilder{
             mode : PrefabMode::Sectional{ section },
         })
@@ -69,92 +86,37 @@ impl PrefabBuilder {
     }
 
     fn build(&mut self, rng : &mut RandomNumberGenerator, build_data : &mut BuilderMap) {
-        match self.mode {
-            PrefabMode::RexLevel{template} => self.load_rex_map(&template, build_data),
-            PrefabMode::Constant{level} => self.load_ascii_map(&level, build_data),
-            PrefabMode::Sectional{section} => self.apply_sectional(&section, rng, build_data),
+        match self.mode.clone() {
+            PrefabMode::Constant{level} => self.load_level(&level.roll_transform(rng), build_data),
+            PrefabMode::Sectional{section} => self.apply_sectional(&section.roll_transform(rng), rng, build_data),
             PrefabMode::RoomVaults => self.apply_room_vaults(rng, build_data)
         }
         build_data.take_snapshot();    
     }
 
-    fn char_to_map(&mut self, ch : char, idx: usize, build_data : &mut BuilderMap) {
-        match ch {
-            ' ' => build_data.map.tiles[idx] = TileType::Floor,
-            '#' => build_data.map.tiles[idx] = TileType::Wall,
-            '@' => {
-                let x = idx as i32 % build_data.map.width;
-                let y = idx as i32 / build_data.map.width;
-                build_data.map.tiles[idx] = TileType::Floor;
-                build_data.starting_position = Some(Position{ x:x as i32, y:y as i32 });
-            }
-            '>' => build_data.map.tiles[idx] = TileType::StairsDown,
-            'g' => {
-                build_data.map.tiles[idx] = TileType::Floor;
-                build_data.spawn_list.push((idx, "Goblin".to_string()));
-            }
-            'o' => {
-                build_data.map.tiles[idx] = TileType::Floor;
-                build_data.spawn_list.push((idx, "Orc".to_string()));
-            }
-            '^' => {
-                build_data.map.tiles[idx] = TileType::Floor;
-                build_data.spawn_list.push((idx, "Bear Trap".to_string()));
-            }
-            '%' => {
-                build_data.map.tiles[idx] = TileType::Floor;
-                build_data.spawn_list.push((idx, "Rations".to_string()));
-            }
-            '!' => {
-                build_data.map.tiles[idx] = TileType::Floor;
-                build_data.spawn_list.push((idx, "Health Potion".to_string()));
-            }
-            _ => {
-                bracket_lib::prelude::console::log(format!("Unknown glyph loading map: {}", (ch as u8) as char));
-            }
+    fn place_cell(&mut self, cell : &PrefabCell, idx: usize, build_data : &mut BuilderMap) {
+        build_data.map.tiles[idx] = cell.tile;
+        if let Some(name) = &cell.spawn {
+            build_data.spawn_list.push((idx, name.clone()));
         }
-    }
-
-    #[allow(dead_code)]
-    fn load_rex_map(&mut self, path: &str, build_data : &mut BuilderMap) {
-        let xp_file = bracket_lib::prelude::rex::XpFile::from_resource(path).unwrap();
-
-        for layer in &xp_file.layers {
-            for y in 0..layer.height {
-                for x in 0..layer.width {
-                    let cell = layer.get(x, y).unwrap();
-                    if x < build_data.map.width as usize && y < build_data.map.height as usize {
-                        let idx = build_data.map.xy_idx(x as i32, y as i32);
-                        // We're doing some nasty casting to make it easier to type things like '#' in the match
-                        self.char_to_map(cell.ch as u8 as char, idx, build_data);
-                    }
-                }
-            }
+        if cell.start {
+            let x = idx as i32 % build_data.map.width;
+            let y = idx as i32 / build_data.map.width;
+            build_data.starting_position = Some(Position{ x, y });
         }
     }
 
-    fn read_ascii_to_vec(template : &str) -> Vec<char> {
-        let mut string_vec : Vec<char> = template.chars().filter(|a| *a != '\r' && *a !='\n').collect();
-        for c in string_vec.iter_mut() { if *c as u8 == 160u8 { *c = ' '; } }
-        string_vec
-    }
-
     #[allow(dead_code)]
-    fn load_ascii_map(&mut self, level: &prefab_levels::PrefabLevel, build_data : &mut BuilderMap) {
-        let string_vec = PrefabBuilder::read_ascii_to_vec(level.template);
-
-        let mut i = 0;
+    fn load_level(&mut self, level: &Prefab, build_data : &mut BuilderMap) {
         for ty in 0..level.height {
             for tx in 0..level.width {
                 if tx < build_data.map.width as usize && ty < build_data.map.height as usize {
                     let idx = build_data.map.xy_idx(tx as i32, ty as i32);
-                    if i < string_vec.len() { self.char_to_map(string_vec[i], idx, build_data); }
+                    self.place_cell(level.cell(tx, ty), idx, build_data);
                 }
-                i += 1;
             }
         }
     }
-
     fn apply_previous_iteration<F>(&mut self, mut filter: F, _rng: &mut RandomNumberGenerator, build_data : &mut BuilderMap)
         where F : FnMut(i32, i32) -> bool
     {
@@ -168,11 +130,7 @@ impl PrefabBuilder {
     }
 
     #[allow(dead_code)]
-    fn apply_sectional(&mut self, section : &prefab_sections::PrefabSection, rng: &mut RandomNumberGenerator, build_data : &mut BuilderMap) {
-        use prefab_sections::*;
-
-        let string_vec = PrefabBuilder::read_ascii_to_vec(section.template);
-        
+    fn apply_sectional(&mut self, section : &Prefab, rng: &mut RandomNumberGenerator, build_data : &mut BuilderMap) {
         // Place the new section
         let chunk_x;
         match section.placement.0 {
@@ -193,22 +151,25 @@ impl PrefabBuilder {
             x < chunk_x || x > (chunk_x + section.width as i32) || y < chunk_y || y > (chunk_y + section.height as i32)
         }, rng, build_data);       
 
-        let mut i = 0;
+        // Rooms under the section are gone, so later builders shouldn't start, spawn or put stairs in them
+        if let Some(rooms) = build_data.rooms.as_mut() {
+            rooms.retain(|room| {
+                room.x2 < chunk_x || room.x1 > (chunk_x + section.width as i32) || room.y2 < chunk_y || room.y1 > (chunk_y + section.height as i32)
+            });
+        }
+
         for ty in 0..section.height {
             for tx in 0..section.width {
                 if tx > 0 && tx < build_data.map.width as usize -1 && ty < build_data.map.height as usize -1 && ty > 0 {
                     let idx = build_data.map.xy_idx(tx as i32 + chunk_x, ty as i32 + chunk_y);
-                    if i < string_vec.len() { self.char_to_map(string_vec[i], idx, build_data); }
+                    self.place_cell(section.cell(tx, ty), idx, build_data);
                 }
-                i += 1;
             }
         }
         build_data.take_snapshot();
     }
 
     fn apply_room_vaults(&mut self, rng : &mut RandomNumberGenerator, build_data : &mut BuilderMap) {
-        use prefab_rooms::*;
-
         // Apply the previous builder, and keep all entities it spawns (for now)
         self.apply_previous_iteration(|_x,_y| true, rng, build_data);
 
@@ -216,24 +177,33 @@ impl PrefabBuilder {
         let vault_roll = rng.roll_dice(1, 6) + build_data.map.depth;
         if vault_roll < 4 { return; }
 
-        // Note that this is a place-holder and will be moved out of this function
-        let master_vault_list = vec![TOTALLY_NOT_A_TRAP, CHECKERBOARD, SILLY_SMILE];
-
-        // Filter the vault list down to ones that are applicable to the current depth
-        let mut possible_vaults : Vec<&PrefabRoom> = master_vault_list
-            .iter()
-            .filter(|v| { build_data.map.depth >= v.first_depth && build_data.map.depth <= v.last_depth })
+        // Filter the library's rooms down to ones that are applicable to the current depth
+        let depth = build_data.map.depth;
+        let mut possible_vaults : Vec<Prefab> = PREFABS.lock().unwrap()
+            .of_kind(PrefabKind::Room)
+            .filter(|v| v.in_depth(depth) && v.weight.at_depth(depth) > 0)
+            .cloned()
             .collect();
 
         if possible_vaults.is_empty() { return; } // Bail out if there's nothing to build
 
         let n_vaults = i32::min(rng.roll_dice(1, 3), possible_vaults.len() as i32);
         let mut used_tiles : HashSet<usize> = HashSet::new();
+        let start = build_data.starting_position.as_ref().map(|pos| build_data.map.xy_idx(pos.x, pos.y));
+        let mut keys : Vec<String> = Vec::new();
 
         for _i in 0..n_vaults {
 
-            let vault_index = if possible_vaults.len() == 1 { 0 } else { (rng.roll_dice(1, possible_vaults.len() as i32)-1) as usize };
-            let vault = possible_vaults[vault_index];
+            // Rarer vaults have lower weights; each is placed at most once, turned as it allows
+            let mut table = RandomTable::new();
+            for v in possible_vaults.iter() {
+                table = table.add(&v.name, v.weight.at_depth(depth));
+            }
+            let vault_index = match table.roll(rng) {
+                Some(name) => possible_vaults.iter().position(|v| v.name == name).unwrap(),
+                None => break,
+            };
+            let vault = possible_vaults[vault_index].roll_transform(rng);
 
             // We'll make a list of places in which the vault could fit
             let mut vault_positions : Vec<Position> = Vec::new();
@@ -250,9 +220,11 @@ impl PrefabBuilder {
                     && (y+vault.height as i32) < build_data.map.height-2
                 {
 
+                    // The vault and a tile all round it must be open floor, so its walls
+                    // can't plug a corridor, and it mustn't bury the start.
                     let mut possible = true;
-                    for ty in 0..vault.height as i32 {
-                        for tx in 0..vault.width as i32 {
+                    for ty in -1..=vault.height as i32 {
+                        for tx in -1..=vault.width as i32 {
 
                             let idx = build_data.map.xy_idx(tx + x, ty + y);
                             if build_data.map.tiles[idx] != TileType::Floor {
@@ -261,6 +233,9 @@ impl PrefabBuilder {
                             if used_tiles.contains(&idx) {
                                 possible = false;
                             }
+                            if start == Some(idx) {
+                                possible = false;
+                            }
                         }
                     }
 
@@ -283,28 +258,44 @@ impl PrefabBuilder {
                 let chunk_y = pos.y;
 
                 let width = build_data.map.width; // The borrow checker really doesn't like it
-                let height = build_data.map.height; // when we access `self` inside the `retain`
+                                                  // when we access `self` inside the `retain`
                 build_data.spawn_list.retain(|e| {
                     let idx = e.0 as i32;
                     let x = idx % width;
-                    let y = idx / height;
+                    let y = idx / width;
                     x < chunk_x || x > chunk_x + vault.width as i32 || y < chunk_y || y > chunk_y + vault.height as i32
                 });
 
-                let string_vec = PrefabBuilder::read_ascii_to_vec(vault.template);
-                let mut i = 0;
                 for ty in 0..vault.height {
                     for tx in 0..vault.width {
                         let idx = build_data.map.xy_idx(tx as i32 + chunk_x, ty as i32 + chunk_y);
-                        if i < string_vec.len() { self.char_to_map(string_vec[i], idx, build_data); }
+                        self.place_cell(vault.cell(tx, ty), idx, build_data);
                         used_tiles.insert(idx);
-                        i += 1;
                     }
                 }
                 build_data.take_snapshot();
 
+                keys.extend(vault.key.clone());
                 possible_vaults.remove(vault_index);
             }
         }
+
+        // Keys go down once every vault is built, outside all of them, where
+        // the player can walk to without one
+        if let (Some(start), false) = (start, keys.is_empty()) {
+            let map = &build_data.map;
+            let reached = DijkstraMap::over(map, &[start], UNREACHABLE,
+                                            |idx| map.tiles[idx].is_walkable() && !used_tiles.contains(&idx));
+            let mut free : Vec<usize> = (0..build_data.map.tiles.len())
+                .filter(|idx| reached.reachable(*idx) && build_data.map.tiles[*idx] == TileType::Floor && *idx != start)
+                .filter(|idx| !build_data.spawn_list.iter().any(|(taken, _)| taken == idx))
+                .collect();
+            for key in keys {
+                if free.is_empty() { break; }
+                let idx = free.remove((rng.roll_dice(1, free.len() as i32) - 1) as usize);
+                build_data.spawn_list.push((idx, key));
+            }
+        }
     }
 }
+
@@ -0,0 +1,388 @@
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+use bracket_lib::prelude::{console, RandomNumberGenerator, RGB};
+use serde::Deserialize;
+use serde_yaml::Value;
+
+use super::{registry, BuilderChain, PrefabLibrary, PREFABS};
+use crate::random_table::RandomTable;
+use crate::raws::{RawWeight, RawsError};
+use crate::spawner::ROOM_TABLE;
+use crate::{Biome, TileType};
+
+/* Map recipes
+ *
+ * Which builders make a level, and in what order, is read from
+ * raws/map_recipes.yaml (the file's header describes the format). Like the
+ * raws, it's loaded once at startup by load_recipes(), with the copy compiled
+ * into the binary as the fallback and what tests see.
+ *
+ * Each depth is given a biome, and the biome picks the recipe; the rest of the
+ * biome (its spawn table, palette and light) goes with the level, as the
+ * map's Biome.
+ *
+ * A recipe is checked in full when loaded: every builder it names must exist
+ * in the registry (registry.rs) and accept its parameters, every recipe a
+ * biome names and every biome a depth names must exist, and palettes must be
+ * colours. Prefabs a recipe names are checked against the prefabs loaded
+ * then (the built-in recipes against the built-in prefabs), and again whenever
+ * the prefabs are reloaded; recipes that no longer fit are swapped for the
+ * built-in ones. Whether a biome's spawn table exists is left to the raws'
+ * validate(), as the raws may be reloaded without the recipes. Choices inside
+ * a recipe (one_of, chance) are made each time it builds a level.
+ */
+
+pub const RECIPES_PATH: &str = "./raws/map_recipes.yaml";
+const BUILTIN_RECIPES: &str = include_str!("../../raws/map_recipes.yaml");
+
+lazy_static! {
+    pub static ref RECIPES: Mutex<RecipeBook> = Mutex::new(RecipeBook::builtin());
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawRecipes {
+    recipes: BTreeMap<String, RawRecipe>,
+    biomes: BTreeMap<String, RawBiome>,
+    depths: RawDepths,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawRecipe {
+    starter: Value,
+    #[serde(default)]
+    builders: Vec<Value>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawBiome {
+    recipes: Vec<RawBiomeRecipe>,
+    spawn_table: Option<String>,
+    #[serde(default)]
+    palette: BTreeMap<TileType, String>,
+    ambient_light: Option<f32>,
+}
+
+//One of the recipes a biome's levels may be built with.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawBiomeRecipe {
+    recipe: String,
+    weight: RawWeight,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawDepths {
+    #[serde(default)]
+    pinned: BTreeMap<i32, String>,
+    random: Vec<RawDepthEntry>,
+}
+
+//One of the biomes a depth may be in; weighted like a spawn table entry.
+#[derive(Deserialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+struct RawDepthEntry {
+    biome: String,
+    weight: RawWeight,
+    min_depth: Option<i32>,
+    max_depth: Option<i32>,
+}
+
+impl RawDepthEntry {
+    fn in_depth(&self, depth: i32) -> bool {
+        self.min_depth.map_or(true, |min| depth >= min) && self.max_depth.map_or(true, |max| depth <= max)
+    }
+}
+
+#[derive(Clone, Debug)]
+enum RecipeStep {
+    Builder { name: String, params: Value },
+    OneOf(Vec<RecipeStep>),
+    Chance { percent: i32, then: Box<RecipeStep> },
+    Nothing,
+}
+
+struct Recipe {
+    starter: RecipeStep,
+    builders: Vec<RecipeStep>,
+}
+
+struct BiomeEntry {
+    recipes: Vec<RawBiomeRecipe>,
+    biome: Biome,
+}
+
+pub struct RecipeBook {
+    recipes: BTreeMap<String, Recipe>,
+    biomes: BTreeMap<String, BiomeEntry>,
+    pinned: BTreeMap<i32, String>,
+    random: Vec<RawDepthEntry>,
+}
+
+impl RecipeBook {
+    //Recipes from `yaml`, checked against `prefabs` for the prefabs they name.
+    pub fn parse(yaml: &str, prefabs: &PrefabLibrary) -> Result<RecipeBook, RawsError> {
+        let raw: RawRecipes = serde_yaml::from_str(yaml)?;
+
+        let mut recipes = BTreeMap::new();
+        for (name, recipe) in raw.recipes.into_iter() {
+            let invalid = |reason: String| RawsError::Invalid { name: name.clone(), reason };
+            let starter = parse_step(&recipe.starter).map_err(|e| invalid(format!("starter: {}", e)))?;
+            let mut builders = Vec::new();
+            for (i, step) in recipe.builders.iter().enumerate() {
+                builders.push(parse_step(step).map_err(|e| invalid(format!("builder {}: {}", i + 1, e)))?);
+            }
+            let recipe = Recipe { starter, builders };
+            recipe.check(prefabs).map_err(invalid)?;
+            recipes.insert(name, recipe);
+        }
+
+        let mut biomes = BTreeMap::new();
+        for (name, biome) in raw.biomes.into_iter() {
+            let biome = parse_biome(&name, biome, &recipes)
+                .map_err(|reason| RawsError::Invalid { name: format!("biome {}", name), reason })?;
+            biomes.insert(name, biome);
+        }
+
+        let unknown = |depth: String, biome: &str| RawsError::Invalid { name: depth, reason: format!("unknown biome \"{}\"", biome) };
+        for (depth, biome) in raw.depths.pinned.iter() {
+            if !biomes.contains_key(biome) {
+                return Err(unknown(format!("depth {}", depth), biome));
+            }
+        }
+        for entry in raw.depths.random.iter() {
+            if !biomes.contains_key(&entry.biome) {
+                return Err(unknown("random depths".to_string(), &entry.biome));
+            }
+        }
+
+        Ok(RecipeBook { recipes, biomes, pinned: raw.depths.pinned, random: raw.depths.random })
+    }
+
+    //The recipes compiled into the binary, which only name built-in prefabs.
+    pub fn builtin() -> RecipeBook {
+        RecipeBook::parse(BUILTIN_RECIPES, &PrefabLibrary::builtin()).expect("Built-in map recipes are invalid.")
+    }
+
+    //Whether every recipe can still be built from `prefabs`.
+    pub fn check(&self, prefabs: &PrefabLibrary) -> Result<(), RawsError> {
+        for (name, recipe) in self.recipes.iter() {
+            recipe.check(prefabs).map_err(|reason| RawsError::Invalid { name: name.clone(), reason })?;
+        }
+        Ok(())
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &String> {
+        self.recipes.keys()
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.recipes.contains_key(name)
+    }
+
+    pub fn biomes(&self) -> impl Iterator<Item = &Biome> {
+        self.biomes.values().map(|entry| &entry.biome)
+    }
+
+    pub fn biome(&self, name: &str) -> Option<&Biome> {
+        self.biomes.get(name).map(|entry| &entry.biome)
+    }
+
+    //The recipes the named biome builds with, e.g. for listing.
+    pub fn biome_recipes(&self, name: &str) -> Vec<&String> {
+        self.biomes.get(name).map_or_else(Vec::new, |entry| entry.recipes.iter().map(|r| &r.recipe).collect())
+    }
+
+    //The biome pinned to `depth`, or else one rolled from those allowed there.
+    pub fn biome_for(&self, depth: i32, rng: &mut RandomNumberGenerator) -> Option<String> {
+        if let Some(biome) = self.pinned.get(&depth) {
+            return Some(biome.clone());
+        }
+        let mut table = RandomTable::new();
+        for entry in self.random.iter().filter(|entry| entry.in_depth(depth)) {
+            table = table.add(&entry.biome, entry.weight.at_depth(depth));
+        }
+        table.roll(rng)
+    }
+
+    //One of the named biome's recipes, rolled by weight. None if there's no such biome.
+    pub fn recipe_in(&self, biome: &str, depth: i32, rng: &mut RandomNumberGenerator) -> Option<String> {
+        let mut table = RandomTable::new();
+        for entry in self.biomes.get(biome)?.recipes.iter() {
+            table = table.add(&entry.recipe, entry.weight.at_depth(depth));
+        }
+        table.roll(rng)
+    }
+
+    //A chain for the named recipe, its choices made with `rng`. None if there's no
+    //such recipe, or it names a prefab that isn't loaded.
+    pub fn build_chain(&self, name: &str, new_depth: i32, rng: &mut RandomNumberGenerator,
+                       width: i32, height: i32) -> Option<BuilderChain> {
+        let recipe = self.recipes.get(name)?;
+        let prefabs = PREFABS.lock().unwrap();
+        let mut chain = BuilderChain::new(new_depth, width, height);
+        let unbuildable = |step: &str, e: String| {
+            console::log(format!("Map recipe {} can't be built; {}: {}", name, step, e));
+        };
+
+        //Builder names and parameters were checked when loaded; prefabs are looked up now.
+        if let Some((name, params)) = resolve(&recipe.starter, rng) {
+            chain.steps.push(describe(name, params));
+            let constructor = registry::starter(name).expect("Recipe names an unknown starter.");
+            match constructor(params, &prefabs, rng) {
+                Ok(starter) => chain.start_with(starter),
+                Err(e) => { unbuildable(name, e); return None; }
+            }
+        }
+        for step in recipe.builders.iter() {
+            if let Some((name, params)) = resolve(step, rng) {
+                chain.steps.push(describe(name, params));
+                let constructor = registry::meta(name).expect("Recipe names an unknown builder.");
+                match constructor(params, &prefabs, rng) {
+                    Ok(builder) => chain.with(builder),
+                    Err(e) => { unbuildable(name, e); return None; }
+                }
+            }
+        }
+        Some(chain)
+    }
+}
+
+impl Recipe {
+    fn check(&self, prefabs: &PrefabLibrary) -> Result<(), String> {
+        check_starter(&self.starter, prefabs).map_err(|e| format!("starter: {}", e))?;
+        for (i, step) in self.builders.iter().enumerate() {
+            check_meta(step, prefabs).map_err(|e| format!("builder {}: {}", i + 1, e))?;
+        }
+        Ok(())
+    }
+}
+
+//Replaces the loaded recipes with the given file's, checked against the loaded
+//prefabs. On error, the old ones stay.
+pub fn load_recipes(path: &str) -> Result<(), RawsError> {
+    let yaml = std::fs::read_to_string(path)?;
+    let book = RecipeBook::parse(&yaml, &PREFABS.lock().unwrap())?;
+    *RECIPES.lock().unwrap() = book;
+    Ok(())
+}
+
+//Keeps the loaded recipes buildable once `prefabs` replace the loaded ones: if
+//they name a prefab that's gone, the built-in recipes take their place. Err,
+//changing nothing, if even those can't be built from `prefabs`.
+pub(super) fn fit_recipes_to(prefabs: &PrefabLibrary) -> Result<(), RawsError> {
+    let mut recipes = RECIPES.lock().unwrap();
+    if let Err(e) = recipes.check(prefabs) {
+        let builtin = RecipeBook::parse(BUILTIN_RECIPES, prefabs)?;
+        console::log(format!("Using built-in map recipes; {}", e));
+        *recipes = builtin;
+    }
+    Ok(())
+}
+
+fn parse_biome(name: &str, raw: RawBiome, recipes: &BTreeMap<String, Recipe>) -> Result<BiomeEntry, String> {
+    if raw.recipes.is_empty() {
+        return Err("no recipes to build with".to_string());
+    }
+    if let Some(entry) = raw.recipes.iter().find(|entry| !recipes.contains_key(&entry.recipe)) {
+        return Err(format!("unknown recipe \"{}\"", entry.recipe));
+    }
+    let ambient_light = raw.ambient_light.unwrap_or(1.0);
+    if !(ambient_light > 0.0 && ambient_light <= 1.0) {
+        return Err(format!("ambient_light {} isn't above 0 and at most 1", ambient_light));
+    }
+    let mut palette = Vec::new();
+    for (tile, hex) in raw.palette.into_iter() {
+        let colour = RGB::from_hex(&hex).map_err(|_| format!("palette: {:?} isn't a colour like #808080", hex))?;
+        palette.push((tile, colour));
+    }
+    let biome = Biome {
+        name: name.to_string(),
+        spawn_table: raw.spawn_table.unwrap_or_else(|| ROOM_TABLE.to_string()),
+        ambient_light,
+        palette,
+    };
+    Ok(BiomeEntry { recipes: raw.recipes, biome })
+}
+
+//`name`, `name: params`, `one_of: [steps]`, `{ chance: N, then: step }` or `none`.
+fn parse_step(value: &Value) -> Result<RecipeStep, String> {
+    match value {
+        Value::String(name) if name == "none" => Ok(RecipeStep::Nothing),
+        Value::String(name) => Ok(RecipeStep::Builder { name: name.clone(), params: Value::Null }),
+        Value::Mapping(map) => {
+            let key = |k: &str| map.get(&Value::String(k.to_string()));
+            if let (Some(chance), Some(then)) = (key("chance"), key("then")) {
+                if map.len() != 2 {
+                    return Err("a chance step has only `chance` and `then`".to_string());
+                }
+                let percent = chance.as_i64().filter(|p| (0..=100).contains(p))
+                    .ok_or_else(|| "chance must be a percentage".to_string())?;
+                return Ok(RecipeStep::Chance { percent: percent as i32, then: Box::new(parse_step(then)?) });
+            }
+            if map.len() != 1 {
+                return Err("a step names one builder".to_string());
+            }
+            let (name, params) = map.iter().next().unwrap();
+            let name = name.as_str().ok_or_else(|| "builder names are strings".to_string())?;
+            if name == "one_of" {
+                let options = params.as_sequence().filter(|options| !options.is_empty())
+                    .ok_or_else(|| "one_of needs a list of steps".to_string())?;
+                return Ok(RecipeStep::OneOf(options.iter().map(parse_step).collect::<Result<_, _>>()?));
+            }
+            Ok(RecipeStep::Builder { name: name.to_string(), params: params.clone() })
+        }
+        _ => Err("expected a builder name or a map".to_string()),
+    }
+}
+
+//A chain needs exactly one starter, so it can't be left to chance.
+fn check_starter(step: &RecipeStep, prefabs: &PrefabLibrary) -> Result<(), String> {
+    let mut scratch = RandomNumberGenerator::seeded(0);
+    match step {
+        RecipeStep::Builder { name, params } => {
+            let constructor = registry::starter(name).ok_or_else(|| format!("unknown starting builder \"{}\"", name))?;
+            constructor(params, prefabs, &mut scratch).map(|_| ()).map_err(|e| format!("{}: {}", name, e))
+        }
+        RecipeStep::OneOf(options) => options.iter().try_for_each(|option| check_starter(option, prefabs)),
+        RecipeStep::Chance { .. } | RecipeStep::Nothing => Err("the starter can't be left out".to_string()),
+    }
+}
+
+fn check_meta(step: &RecipeStep, prefabs: &PrefabLibrary) -> Result<(), String> {
+    let mut scratch = RandomNumberGenerator::seeded(0);
+    match step {
+        RecipeStep::Builder { name, params } => {
+            let constructor = registry::meta(name).ok_or_else(|| format!("unknown builder \"{}\"", name))?;
+            constructor(params, prefabs, &mut scratch).map(|_| ()).map_err(|e| format!("{}: {}", name, e))
+        }
+        RecipeStep::OneOf(options) => options.iter().try_for_each(|option| check_meta(option, prefabs)),
+        RecipeStep::Chance { then, .. } => check_meta(then, prefabs),
+        RecipeStep::Nothing => Ok(()),
+    }
+}
+
+//Makes the step's choices, down to the one builder it comes to (if any).
+fn resolve<'a>(step: &'a RecipeStep, rng: &mut RandomNumberGenerator) -> Option<(&'a str, &'a Value)> {
+    match step {
+        RecipeStep::Builder { name, params } => Some((name, params)),
+        RecipeStep::OneOf(options) => resolve(&options[(rng.roll_dice(1, options.len() as i32) - 1) as usize], rng),
+        RecipeStep::Chance { percent, then } => {
+            if rng.roll_dice(1, 100) <= *percent { resolve(then, rng) } else { None }
+        }
+        RecipeStep::Nothing => None,
+    }
+}
+
+//`name`, `name: preset`, or the parameters written out on one line.
+fn describe(name: &str, params: &Value) -> String {
+    match params {
+        Value::Null => name.to_string(),
+        Value::String(preset) => format!("{}: {}", name, preset),
+        _ => format!("{}: {}", name, serde_json::to_string(params).unwrap_or_default()),
+    }
+}
@@ -0,0 +1,218 @@
+use bracket_lib::prelude::RandomNumberGenerator;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_yaml::Value;
+
+use super::*;
+use drunkard::DrunkardSettings;
+
+/* Builder registry
+ *
+ * Every builder a recipe (raws/map_recipes.yaml) can name, and how to make one
+ * from the step's parameters. A builder that can both start a chain and
+ * rework one (drunkards_walk, dla, wave_function_collapse) is listed in both
+ * tables, and may take different parameters in each.
+ *
+ * Constructors get the level's rng, so a builder whose parameters include a
+ * random choice (area_starting_position: random) makes it afresh each level,
+ * and the prefab library to look named prefabs up in, so a recipe can be
+ * checked against prefabs other than those loaded now.
+ * A bad parameter is an error, not a fallback: recipes are checked by building
+ * every step once when they're loaded.
+ *
+ * Parameters, where a builder takes any:
+ *   room_sorter             leftmost | rightmost | topmost | bottommost | central
+ *   area_starting_position  random, or { x: left | center | right | random,
+ *                                        y: top | center | bottom | random }
+ *   drunkards_walk          open_area | open_halls | winding_passages | fat_passages |
+ *                           fearful_symmetry, or { spawn_mode: starting_point | random,
+ *                           drunken_lifetime, floor_percent, brush_size,
+ *                           symmetry: none | horizontal | vertical | both }
+ *   dla                     walk_inwards | walk_outwards | central_attractor |
+ *                           insectoid | heavy_erosion
+ *   voronoi                 pythagoras | manhattan
+ *   terrain_features        nothing, or flooded
+ *   secrets                 nothing, or { door_chance: <percent of doors>,
+ *                                         passages: <corridors hidden in, at most> }
+ *   prefab_level            a level in raws/prefabs/, e.g. wfc_populated
+ *   wave_function_collapse  a level in raws/prefabs/ when starting a chain; nothing otherwise
+ *   prefab_sectional        a section in raws/prefabs/, e.g. underground_fort
+ *
+ * prefab_vaults takes no parameters: it picks from every room in raws/prefabs/.
+ */
+
+pub type StarterConstructor = fn(&Value, &PrefabLibrary, &mut RandomNumberGenerator) -> Result<Box<dyn InitialMapBuilder>, String>;
+pub type MetaConstructor = fn(&Value, &PrefabLibrary, &mut RandomNumberGenerator) -> Result<Box<dyn MetaMapBuilder>, String>;
+
+const STARTERS: &[(&str, StarterConstructor)] = &[
+    ("simple_map", |p, _, _| { no_params(p)?; Ok(SimpleMapBuilder::new()) }),
+    ("bsp_dungeon", |p, _, _| { no_params(p)?; Ok(BspDungeonBuilder::new()) }),
+    ("bsp_interior", |p, _, _| { no_params(p)?; Ok(BspInteriorBuilder::new()) }),
+    ("cellular_automata", |p, _, _| { no_params(p)?; Ok(CellularAutomataBuilder::new()) }),
+    ("drunkards_walk", |p, _, _| Ok(drunkards_walk(p)?)),
+    ("maze", |p, _, _| { no_params(p)?; Ok(MazeBuilder::new()) }),
+    ("dla", |p, _, _| Ok(dla(p)?)),
+    ("voronoi", |p, _, _| {
+        match preset(p)?.as_str() {
+            "pythagoras" => Ok(VoronoiCellBuilder::pythagoras()),
+            "manhattan" => Ok(VoronoiCellBuilder::manhattan()),
+            other => Err(unknown_preset(other)),
+        }
+    }),
+    ("prefab_level", |p, prefabs, _| Ok(PrefabBuilder::constant(prefab(p, prefabs, PrefabKind::Level)?))),
+    ("wave_function_collapse", |p, prefabs, _| Ok(WaveformCollapseBuilder::from_template(prefab(p, prefabs, PrefabKind::Level)?))),
+];
+
+const METAS: &[(&str, MetaConstructor)] = &[
+    ("room_sorter", |p, _, _| Ok(RoomSorter::new(params(p)?))),
+    ("room_drawer", |p, _, _| { no_params(p)?; Ok(RoomDrawer::new()) }),
+    ("dogleg_corridors", |p, _, _| { no_params(p)?; Ok(DoglegCorridors::new()) }),
+    ("nearest_corridors", |p, _, _| { no_params(p)?; Ok(NearestCorridors::new()) }),
+    ("straight_line_corridors", |p, _, _| { no_params(p)?; Ok(StraightLineCorridors::new()) }),
+    ("bsp_corridors", |p, _, _| { no_params(p)?; Ok(BspCorridors::new()) }),
+    ("corridor_spawner", |p, _, _| { no_params(p)?; Ok(CorridorSpawner::new()) }),
+    ("room_exploder", |p, _, _| { no_params(p)?; Ok(RoomExploder::new()) }),
+    ("room_corner_rounder", |p, _, _| { no_params(p)?; Ok(RoomCornerRounder::new()) }),
+    ("room_based_starting_position", |p, _, _| { no_params(p)?; Ok(RoomBasedStartingPosition::new()) }),
+    ("area_starting_position", |p, _, rng| {
+        let (x, y) = start_position(p, rng)?;
+        Ok(AreaStartingPosition::new(x, y))
+    }),
+    ("room_based_stairs", |p, _, _| { no_params(p)?; Ok(RoomBasedStairs::new()) }),
+    ("distant_exit", |p, _, _| { no_params(p)?; Ok(DistantExit::new()) }),
+    ("room_based_spawner", |p, _, _| { no_params(p)?; Ok(RoomBasedSpawner::new()) }),
+    ("voronoi_spawning", |p, _, _| { no_params(p)?; Ok(VoronoiSpawning::new()) }),
+    ("cull_unreachable", |p, _, _| { no_params(p)?; Ok(CullUnreachable::new()) }),
+    ("terrain_features", |p, _, _| {
+        match p {
+            Value::Null => Ok(TerrainFeatures::new()),
+            _ => match preset(p)?.as_str() {
+                "flooded" => Ok(TerrainFeatures::flooded()),
+                other => Err(unknown_preset(other)),
+            },
+        }
+    }),
+    ("door_placement", |p, _, _| { no_params(p)?; Ok(DoorPlacement::new()) }),
+    ("secrets", |p, _, _| {
+        match p {
+            Value::Null => Ok(SecretPlacement::new(Default::default())),
+            _ => Ok(SecretPlacement::new(params(p)?)),
+        }
+    }),
+    ("prefab_sectional", |p, prefabs, _| Ok(PrefabBuilder::sectional(prefab(p, prefabs, PrefabKind::Section)?))),
+    ("prefab_vaults", |p, _, _| { no_params(p)?; Ok(PrefabBuilder::vaults()) }),
+    ("wave_function_collapse", |p, _, _| { no_params(p)?; Ok(WaveformCollapseBuilder::new()) }),
+    ("drunkards_walk", |p, _, _| Ok(drunkards_walk(p)?)),
+    ("dla", |p, _, _| Ok(dla(p)?)),
+];
+
+pub fn starter(name: &str) -> Option<StarterConstructor> {
+    STARTERS.iter().find(|(n, _)| *n == name).map(|(_, constructor)| *constructor)
+}
+
+pub fn meta(name: &str) -> Option<MetaConstructor> {
+    METAS.iter().find(|(n, _)| *n == name).map(|(_, constructor)| *constructor)
+}
+
+fn params<T: DeserializeOwned>(params: &Value) -> Result<T, String> {
+    serde_yaml::from_value(params.clone()).map_err(|e| e.to_string())
+}
+
+fn no_params(params: &Value) -> Result<(), String> {
+    match params {
+        Value::Null => Ok(()),
+        _ => Err("takes no parameters".to_string()),
+    }
+}
+
+fn preset(params: &Value) -> Result<String, String> {
+    match params {
+        Value::String(name) => Ok(name.clone()),
+        Value::Null => Err("needs a preset".to_string()),
+        _ => Err("expected the name of a preset".to_string()),
+    }
+}
+
+fn unknown_preset(name: &str) -> String {
+    format!("unknown preset \"{}\"", name)
+}
+
+fn drunkards_walk(p: &Value) -> Result<Box<DrunkardsWalkBuilder>, String> {
+    if !p.is_string() {
+        let settings: DrunkardSettings = params(p)?;
+        return Ok(Box::new(DrunkardsWalkBuilder::new(settings)));
+    }
+    match preset(p)?.as_str() {
+        "open_area" => Ok(DrunkardsWalkBuilder::open_area()),
+        "open_halls" => Ok(DrunkardsWalkBuilder::open_halls()),
+        "winding_passages" => Ok(DrunkardsWalkBuilder::winding_passages()),
+        "fat_passages" => Ok(DrunkardsWalkBuilder::fat_passages()),
+        "fearful_symmetry" => Ok(DrunkardsWalkBuilder::fearful_symmetry()),
+        other => Err(unknown_preset(other)),
+    }
+}
+
+fn dla(p: &Value) -> Result<Box<DLABuilder>, String> {
+    match preset(p)?.as_str() {
+        "walk_inwards" => Ok(DLABuilder::walk_inwards()),
+        "walk_outwards" => Ok(DLABuilder::walk_outwards()),
+        "central_attractor" => Ok(DLABuilder::central_attractor()),
+        "insectoid" => Ok(DLABuilder::insectoid()),
+        "heavy_erosion" => Ok(DLABuilder::heavy_erosion()),
+        other => Err(unknown_preset(other)),
+    }
+}
+
+fn prefab(p: &Value, prefabs: &PrefabLibrary, kind: PrefabKind) -> Result<Prefab, String> {
+    let name = preset(p)?;
+    match prefabs.get(&name) {
+        Some(prefab) if prefab.kind == kind => Ok(prefab.clone()),
+        Some(prefab) => Err(format!("prefab \"{}\" is a {:?}, not a {:?}", name, prefab.kind, kind)),
+        None => Err(format!("unknown prefab \"{}\"", name)),
+    }
+}
+
+//Either axis may be `random`: one of its three places, chosen per level.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Axis<T> {
+    Random(RandomAxis),
+    Fixed(T),
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum RandomAxis { Random }
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct StartParams {
+    x: Axis<XStart>,
+    y: Axis<YStart>,
+}
+
+fn start_position(p: &Value, rng: &mut RandomNumberGenerator) -> Result<(XStart, YStart), String> {
+    let start: StartParams = match p {
+        Value::String(s) if s == "random" => StartParams { x: Axis::Random(RandomAxis::Random), y: Axis::Random(RandomAxis::Random) },
+        Value::Null => return Err("needs a position, or `random`".to_string()),
+        _ => params(p)?,
+    };
+
+    let x = match start.x {
+        Axis::Fixed(x) => x,
+        Axis::Random(_) => match rng.roll_dice(1, 3) {
+            1 => XStart::LEFT,
+            2 => XStart::CENTER,
+            _ => XStart::RIGHT,
+        },
+    };
+    let y = match start.y {
+        Axis::Fixed(y) => y,
+        Axis::Random(_) => match rng.roll_dice(1, 3) {
+            1 => YStart::BOTTOM,
+            2 => YStart::CENTER,
+            _ => YStart::TOP,
+        },
+    };
+    Ok((x, y))
+}
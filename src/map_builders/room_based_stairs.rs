@@ -17,7 +17,11 @@ impl RoomBasedStairs {
 
     fn build(&mut self, _rng : &mut RandomNumberGenerator, build_data : &mut BuilderMap) {
         if let Some(rooms) = &build_data.rooms {
-            let stairs_position = rooms[rooms.len()-1].center();
+            // The last room, or the last one still open (and not the start) if culling walled some up
+            let start = build_data.starting_position.as_ref().map(|pos| (pos.x, pos.y));
+            let stairs_position = rooms.iter().rev().map(|room| room.center())
+                .find(|(x, y)| build_data.map.tiles[build_data.map.xy_idx(*x, *y)].is_walkable() && start != Some((*x, *y)))
+                .unwrap_or_else(|| rooms[rooms.len()-1].center());
             let stairs_idx = build_data.map.xy_idx(stairs_position.0, stairs_position.1);
             build_data.map.tiles[stairs_idx] = TileType::StairsDown;
             build_data.take_snapshot();
@@ -25,7 +25,19 @@ impl RoomCornerRounder {
         if x < w-2 && build_data.map.tiles[idx+1] == TileType::Wall { neighbor_walls += 1; }
         if y < h-2 && build_data.map.tiles[idx+w as usize] == TileType::Wall { neighbor_walls += 1; }
 
-        if neighbor_walls == 2 {
+        // A corner only has the room's three tiles beside it; any more and a corridor leaves through it
+        let mut open_neighbors = 0;
+        for dy in -1..=1 {
+            for dx in -1..=1 {
+                let (nx, ny) = (x + dx, y + dy);
+                if (dx, dy) != (0, 0) && nx >= 0 && ny >= 0 && nx < w && ny < h
+                    && build_data.map.tiles[build_data.map.xy_idx(nx, ny)] != TileType::Wall {
+                    open_neighbors += 1;
+                }
+            }
+        }
+
+        if neighbor_walls == 2 && open_neighbors == 3 {
             build_data.map.tiles[idx] = TileType::Wall;
         }
     }
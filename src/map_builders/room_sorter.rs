@@ -1,6 +1,9 @@
 use super::{MetaMapBuilder, BuilderMap, Rect};
 use bracket_lib::prelude::RandomNumberGenerator;
+use serde::Deserialize;
 
+#[derive(Deserialize, Copy, Clone, Debug)]
+#[serde(rename_all = "lowercase")]
 pub enum RoomSort { LEFTMOST, RIGHTMOST, TOPMOST, BOTTOMMOST, CENTRAL }
 
 pub struct RoomSorter {
@@ -0,0 +1,108 @@
+use super::{invariants, BuilderMap, MetaMapBuilder, TileType};
+use bracket_lib::prelude::RandomNumberGenerator;
+use serde::Deserialize;
+
+/* Secrets
+ *
+ * Turns some of the level's doors into secret doors, and hides a short stretch
+ * of some of its corridors, both of which look like wall until searched for.
+ * Nothing is hidden that the way down (or up) depends on, so what's behind a
+ * secret is only ever a short cut, or something extra.
+ *
+ * Run it after door_placement, and after anything that moves the start or the
+ * stairs. Levels without corridors only get secret doors.
+ */
+
+#[derive(Deserialize, Clone, Copy, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct SecretSettings {
+    #[serde(default = "door_chance")]
+    pub door_chance: i32, //percent of doors made secret
+    #[serde(default = "passages")]
+    pub passages: usize,  //corridors with a hidden stretch, at most
+}
+
+fn door_chance() -> i32 { 20 }
+fn passages() -> usize { 2 }
+
+impl Default for SecretSettings {
+    fn default() -> SecretSettings {
+        SecretSettings { door_chance: door_chance(), passages: passages() }
+    }
+}
+
+pub struct SecretPlacement {
+    settings : SecretSettings,
+}
+
+impl MetaMapBuilder for SecretPlacement {
+    fn build_map(&mut self, rng: &mut RandomNumberGenerator, build_data : &mut BuilderMap) {
+        self.secrets(rng, build_data);
+    }
+}
+
+impl SecretPlacement {
+    pub fn new(settings : SecretSettings) -> Box<SecretPlacement> {
+        Box::new(SecretPlacement{ settings })
+    }
+
+    fn secrets(&mut self, rng : &mut RandomNumberGenerator, build_data : &mut BuilderMap) {
+        let start = match &build_data.starting_position {
+            Some(pos) => build_data.map.xy_idx(pos.x, pos.y),
+            None => return,
+        };
+
+        let doors : Vec<usize> = build_data.spawn_list.iter()
+            .filter(|(_, name)| name == "Door")
+            .map(|(idx, _)| *idx)
+            .collect();
+        let width = build_data.map.width as usize;
+        for idx in doors.iter().copied() {
+            // A door next to another would leave that one walled in on a side
+            let paired = doors.iter().any(|other| [idx - 1, idx + 1, idx - width, idx + width].contains(other));
+            if !paired && rng.roll_dice(1, 100) <= self.settings.door_chance
+                && hide(build_data, start, &[idx], TileType::SecretDoor) {
+                build_data.spawn_list.retain(|(spawn, _)| *spawn != idx);
+            }
+        }
+
+        let mut corridors = build_data.corridors.clone().unwrap_or_default();
+        let mut hidden = 0;
+        while hidden < self.settings.passages && !corridors.is_empty() {
+            let corridor = corridors.remove((rng.roll_dice(1, corridors.len() as i32) - 1) as usize);
+            if corridor.len() < 5 { continue; }
+
+            // A stretch of up to three tiles, away from both ends
+            let length = usize::min(rng.roll_dice(1, 3) as usize, corridor.len() - 4);
+            let first = 1 + rng.roll_dice(1, (corridor.len() - 2 - length) as i32) as usize;
+            let stretch = &corridor[first..first + length];
+            let clear = stretch.iter().all(|idx| {
+                build_data.map.tiles[*idx] == TileType::Floor && *idx != start
+                    && !build_data.spawn_list.iter().any(|(spawn, _)| spawn == idx)
+            });
+            if clear && hide(build_data, start, stretch, TileType::HiddenPassage) {
+                hidden += 1;
+            }
+        }
+        build_data.take_snapshot();
+    }
+}
+
+//Makes `tiles` into `secret`, unless that cuts the start off from any stairs.
+fn hide(build_data : &mut BuilderMap, start : usize, tiles : &[usize], secret : TileType) -> bool {
+    let before : Vec<TileType> = tiles.iter().map(|idx| build_data.map.tiles[*idx]).collect();
+    for idx in tiles.iter() {
+        build_data.map.tiles[*idx] = secret;
+    }
+
+    let reached = invariants::reachable_from(build_data, start);
+    let stairs_reached = build_data.map.tiles.iter().enumerate()
+        .filter(|(_, tile)| **tile == TileType::StairsDown || **tile == TileType::StairsUp)
+        .all(|(idx, _)| reached[idx]);
+    if !stairs_reached {
+        for (idx, tile) in tiles.iter().zip(before) {
+            build_data.map.tiles[*idx] = tile;
+        }
+    }
+    stairs_reached
+}
@@ -25,8 +25,9 @@ impl SimpleMapBuilder {
         for _i in 0..MAX_ROOMS {
             let w = rng.range(MIN_SIZE, MAX_SIZE);
             let h = rng.range(MIN_SIZE, MAX_SIZE);
-            let x = rng.roll_dice(1, build_data.map.width - w - 1) - 1;
-            let y = rng.roll_dice(1, build_data.map.height - h - 1) - 1;
+            //Rooms are dug inside their rect, from x1 + 1 to x2, and must stay off the map's edge.
+            let x = rng.roll_dice(1, build_data.map.width - w - 2);
+            let y = rng.roll_dice(1, build_data.map.height - h - 2);
             let new_room = Rect::new(x, y, w, h);
             let mut ok = true;
             for other_room in rooms.iter() {
@@ -0,0 +1,208 @@
+use std::collections::{HashSet, VecDeque};
+
+use super::{MetaMapBuilder, BuilderMap, Map, TileType};
+use bracket_lib::prelude::RandomNumberGenerator;
+use crate::dijkstra::{DijkstraMap, UNREACHABLE};
+
+/* Terrain Features
+ *
+ * Scatters patches of natural terrain over a finished layout: ponds, marshes,
+ * meadows and rubble, and deeper down chasms and lava pools. A patch only
+ * takes over plain Floor, and never the starting position or a tile that
+ * something is going to spawn on.
+ *
+ * Impassable terrain must not cut the level in two. After each patch, every
+ * tile it made unreachable from the start is reached again by bridging the
+ * water or chasm in the way; a lava pool that cuts anything off cools into
+ * rubble instead.
+ *
+ * The flooded preset (a recipe's `terrain_features: flooded`) lays more
+ * patches, and only ponds and marshes.
+ */
+
+#[derive(PartialEq, Copy, Clone)]
+enum Feature { Pond, Marsh, Meadow, RubbleField, Chasm, LavaPool }
+
+impl Feature {
+    //The patch's core, middle and rim, from its centre out. None leaves the Floor be.
+    fn layers(self) -> [Option<TileType>; 3] {
+        match self {
+            Feature::Pond => [Some(TileType::DeepWater), Some(TileType::ShallowWater), Some(TileType::Mud)],
+            Feature::Marsh => [Some(TileType::ShallowWater), Some(TileType::Mud), Some(TileType::Grass)],
+            Feature::Meadow => [Some(TileType::Grass), Some(TileType::Grass), None],
+            Feature::RubbleField => [Some(TileType::Rubble), None, Some(TileType::Rubble)],
+            Feature::Chasm => [Some(TileType::Chasm), Some(TileType::Chasm), Some(TileType::Rubble)],
+            Feature::LavaPool => [Some(TileType::Lava), Some(TileType::Rubble), None],
+        }
+    }
+
+    //Which features may turn up at a depth, and how often.
+    fn weights(depth: i32, flooded: bool) -> Vec<(Feature, i32)> {
+        if flooded {
+            return vec![(Feature::Pond, 3), (Feature::Marsh, 2)];
+        }
+        let mut weights = vec![(Feature::Pond, 3), (Feature::Marsh, 3), (Feature::Meadow, 2), (Feature::RubbleField, 2)];
+        if depth > 1 { weights.push((Feature::Chasm, 2)); }
+        if depth > 3 { weights.push((Feature::LavaPool, 2)); }
+        weights
+    }
+
+    fn roll(rng: &mut RandomNumberGenerator, depth: i32, flooded: bool) -> Feature {
+        let weights = Feature::weights(depth, flooded);
+        let total: i32 = weights.iter().map(|(_, weight)| weight).sum();
+        let mut roll = rng.roll_dice(1, total);
+        for (feature, weight) in weights.iter() {
+            if roll <= *weight { return *feature; }
+            roll -= weight;
+        }
+        weights[0].0
+    }
+}
+
+pub struct TerrainFeatures {
+    flooded: bool,
+}
+
+impl MetaMapBuilder for TerrainFeatures {
+    fn build_map(&mut self, rng: &mut bracket_lib::prelude::RandomNumberGenerator, build_data : &mut BuilderMap)  {
+        self.build(rng, build_data);
+    }
+}
+
+impl TerrainFeatures {
+    pub fn new() -> Box<TerrainFeatures> {
+        Box::new(TerrainFeatures{ flooded: false })
+    }
+
+    pub fn flooded() -> Box<TerrainFeatures> {
+        Box::new(TerrainFeatures{ flooded: true })
+    }
+
+    fn build(&mut self, rng : &mut RandomNumberGenerator, build_data : &mut BuilderMap) {
+        let start = build_data.starting_position.as_ref()
+            .expect("Terrain Features only work after a starting position has been chosen.");
+        let start_idx = build_data.map.xy_idx(start.x, start.y);
+        let mut protected: HashSet<usize> = build_data.spawn_list.iter().map(|(idx, _)| *idx).collect();
+        protected.insert(start_idx);
+
+        let patches = if self.flooded { rng.roll_dice(1, 3) + 2 } else { rng.roll_dice(1, 3) };
+        for _ in 0..patches {
+            let floor: Vec<usize> = build_data.map.tiles.iter().enumerate()
+                .filter(|(idx, tile)| **tile == TileType::Floor && !protected.contains(idx))
+                .map(|(idx, _)| idx)
+                .collect();
+            if floor.is_empty() { return; }
+
+            let feature = Feature::roll(rng, build_data.map.depth, self.flooded);
+            let centre = floor[(rng.roll_dice(1, floor.len() as i32) - 1) as usize];
+            let radius = rng.roll_dice(1, 3) + 2;
+            let reachable_before = reachable(&build_data.map, start_idx);
+            let patch = paint_patch(&mut build_data.map, rng, feature, centre, radius, &protected);
+
+            let mut cut_off = newly_unreachable(&build_data.map, start_idx, &reachable_before);
+            if !cut_off.is_empty() && feature == Feature::LavaPool {
+                for idx in patch.iter() {
+                    if build_data.map.tiles[*idx] == TileType::Lava {
+                        build_data.map.tiles[*idx] = TileType::Rubble;
+                    }
+                }
+                cut_off.clear();
+            }
+            while let Some(target) = cut_off.first().copied() {
+                if !bridge(&mut build_data.map, start_idx, target) {
+                    //Nothing to build a bridge over; the patch goes.
+                    for idx in patch.iter() {
+                        build_data.map.tiles[*idx] = TileType::Floor;
+                    }
+                    break;
+                }
+                cut_off = newly_unreachable(&build_data.map, start_idx, &reachable_before);
+            }
+            build_data.take_snapshot();
+        }
+    }
+}
+
+//Lays one feature's layers around centre, with a ragged edge. Returns the tiles it changed.
+fn paint_patch(map: &mut Map, rng: &mut RandomNumberGenerator, feature: Feature, centre: usize,
+               radius: i32, protected: &HashSet<usize>) -> Vec<usize> {
+    let layers = feature.layers();
+    let (cx, cy) = (centre as i32 % map.width, centre as i32 / map.width);
+    let mut patch = Vec::new();
+    for y in i32::max(1, cy - radius) ..= i32::min(map.height - 2, cy + radius) {
+        for x in i32::max(1, cx - radius) ..= i32::min(map.width - 2, cx + radius) {
+            let idx = map.xy_idx(x, y);
+            if map.tiles[idx] != TileType::Floor || protected.contains(&idx) { continue; }
+
+            let distance = (((x - cx).pow(2) + (y - cy).pow(2)) as f32).sqrt() + (rng.roll_dice(1, 3) - 2) as f32;
+            let layer = match distance / radius as f32 {
+                d if d < 0.5 => layers[0],
+                d if d < 0.8 => layers[1],
+                d if d <= 1.0 => layers[2],
+                _ => None,
+            };
+            if let Some(tile) = layer {
+                map.tiles[idx] = tile;
+                patch.push(idx);
+            }
+        }
+    }
+    patch
+}
+
+//Every tile a walker could reach from start, moving as the player does.
+fn reachable(map: &Map, start: usize) -> Vec<bool> {
+    let dijkstra_map = DijkstraMap::over(map, &[start], UNREACHABLE, |idx| map.tiles[idx].is_walkable());
+    (0..map.tiles.len()).map(|idx| dijkstra_map.reachable(idx)).collect()
+}
+
+//Walkable tiles that start could reach before, but no longer can. The patch's own
+//impassable tiles were reachable Floor before too; they don't count.
+fn newly_unreachable(map: &Map, start: usize, before: &[bool]) -> Vec<usize> {
+    let now = reachable(map, start);
+    (0..now.len()).filter(|idx| before[*idx] && !now[*idx] && map.tiles[*idx].is_walkable()).collect()
+}
+
+//Bridges the shortest crossing of water or chasm from start to target, if there is one.
+fn bridge(map: &mut Map, start: usize, target: usize) -> bool {
+    let bridgeable = |tile: TileType| tile == TileType::DeepWater || tile == TileType::Chasm;
+    let (seen, parents) = flood(map, start, |tile| tile.is_walkable() || bridgeable(tile));
+    if !seen[target] { return false; }
+
+    let mut idx = target;
+    while idx != start {
+        if bridgeable(map.tiles[idx]) {
+            map.tiles[idx] = TileType::Bridge;
+        }
+        idx = parents[idx];
+    }
+    true
+}
+
+//Breadth-first flood fill from start over tiles that pass, without diagonal steps
+//since bridges are only built orthogonally. Returns what was reached, and each
+//reached tile's predecessor.
+fn flood(map: &Map, start: usize, passable: impl Fn(TileType) -> bool) -> (Vec<bool>, Vec<usize>) {
+    let mut seen = vec![false; map.tiles.len()];
+    let mut parents = vec![start; map.tiles.len()];
+    let mut queue = VecDeque::new();
+    seen[start] = true;
+    queue.push_back(start);
+
+    let steps = [(-1, 0), (1, 0), (0, -1), (0, 1)];
+
+    while let Some(idx) = queue.pop_front() {
+        let (x, y) = (idx as i32 % map.width, idx as i32 / map.width);
+        for (dx, dy) in steps.iter() {
+            let (nx, ny) = (x + dx, y + dy);
+            if nx < 1 || nx > map.width - 1 || ny < 1 || ny > map.height - 1 { continue; }
+            let next = map.xy_idx(nx, ny);
+            if !seen[next] && passable(map.tiles[next]) {
+                seen[next] = true;
+                parents[next] = idx;
+                queue.push_back(next);
+            }
+        }
+    }
+    (seen, parents)
+}
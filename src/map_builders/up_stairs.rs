@@ -0,0 +1,31 @@
+use super::{MetaMapBuilder, BuilderMap, TileType};
+use bracket_lib::prelude::RandomNumberGenerator;
+
+//Puts the way back up under the starting position. Runs last, so nothing
+//built after it can cover the stairs.
+pub struct UpStairs {}
+
+impl MetaMapBuilder for UpStairs {
+    fn build_map(&mut self, rng: &mut bracket_lib::prelude::RandomNumberGenerator, build_data : &mut BuilderMap)  {
+        self.build(rng, build_data);
+    }
+}
+
+impl UpStairs {
+    pub fn new() -> Box<UpStairs> {
+        Box::new(UpStairs{})
+    }
+
+    fn build(&mut self, _rng : &mut RandomNumberGenerator, build_data : &mut BuilderMap) {
+        if let Some(start) = &build_data.starting_position {
+            let start_idx = build_data.map.xy_idx(start.x, start.y);
+            //A level small enough to start on its own way down keeps it.
+            if build_data.map.tiles[start_idx] != TileType::StairsDown {
+                build_data.map.tiles[start_idx] = TileType::StairsUp;
+                build_data.take_snapshot();
+            }
+        } else {
+            panic!("Up Stairs only work after a starting position has been chosen.");
+        }
+    }
+}
@@ -0,0 +1,92 @@
+use std::collections::HashSet;
+
+use super::super::{Map, TileType};
+
+//The four sides of a chunk, and the order they are stored in.
+pub const NORTH: usize = 0;
+pub const SOUTH: usize = 1;
+pub const WEST: usize = 2;
+pub const EAST: usize = 3;
+
+//The side of a neighbour that touches this chunk's `side`.
+pub fn opposite(side: usize) -> usize {
+    match side {
+        NORTH => SOUTH,
+        SOUTH => NORTH,
+        WEST => EAST,
+        _ => WEST,
+    }
+}
+
+//One chunk_size x chunk_size pattern, and the patterns that may sit beside it.
+pub struct MapChunk {
+    pub pattern: Vec<TileType>,
+    pub edges: [Vec<TileType>; 4],
+    pub compatible_with: [Vec<bool>; 4],
+}
+
+//Cuts the source map into chunks (and their mirror images), once each. Stairs are
+//read as floor, so the new map doesn't get a staircase in every copy of a chunk.
+pub fn build_patterns(map: &Map, chunk_size: i32) -> Vec<Vec<TileType>> {
+    let chunks_x = map.width / chunk_size;
+    let chunks_y = map.height / chunk_size;
+    let mut seen: HashSet<Vec<TileType>> = HashSet::new();
+    let mut patterns = Vec::new();
+
+    for cy in 0..chunks_y {
+        for cx in 0..chunks_x {
+            for (flip_x, flip_y) in [(false, false), (true, false), (false, true), (true, true)].iter() {
+                let mut pattern = Vec::with_capacity((chunk_size * chunk_size) as usize);
+                for y in 0..chunk_size {
+                    for x in 0..chunk_size {
+                        let sx = cx * chunk_size + if *flip_x { chunk_size - 1 - x } else { x };
+                        let sy = cy * chunk_size + if *flip_y { chunk_size - 1 - y } else { y };
+                        let tile = match map.tiles[map.xy_idx(sx, sy)] {
+                            TileType::StairsDown | TileType::StairsUp => TileType::Floor,
+                            tile => tile,
+                        };
+                        pattern.push(tile);
+                    }
+                }
+                if seen.insert(pattern.clone()) {
+                    patterns.push(pattern);
+                }
+            }
+        }
+    }
+    patterns
+}
+
+//Two chunks may touch only where their touching edges are the same, tile for tile.
+pub fn patterns_to_constraints(patterns: Vec<Vec<TileType>>, chunk_size: i32) -> Vec<MapChunk> {
+    let size = chunk_size as usize;
+    let mut chunks: Vec<MapChunk> = patterns.into_iter().map(|pattern| {
+        let edges = [
+            (0..size).map(|x| pattern[x]).collect(),
+            (0..size).map(|x| pattern[(size - 1) * size + x]).collect(),
+            (0..size).map(|y| pattern[y * size]).collect(),
+            (0..size).map(|y| pattern[y * size + size - 1]).collect(),
+        ];
+        MapChunk { pattern, edges, compatible_with: [Vec::new(), Vec::new(), Vec::new(), Vec::new()] }
+    }).collect();
+
+    for i in 0..chunks.len() {
+        for side in 0..4 {
+            let compatible = (0..chunks.len())
+                .map(|j| chunks[i].edges[side] == chunks[j].edges[opposite(side)])
+                .collect();
+            chunks[i].compatible_with[side] = compatible;
+        }
+    }
+    chunks
+}
+
+//Draws a chunk's pattern into the map at chunk coordinates (cx, cy).
+pub fn render_pattern_to_map(map: &mut Map, chunk: &MapChunk, chunk_size: i32, cx: i32, cy: i32) {
+    for y in 0..chunk_size {
+        for x in 0..chunk_size {
+            let idx = map.xy_idx(cx * chunk_size + x, cy * chunk_size + y);
+            map.tiles[idx] = chunk.pattern[(y * chunk_size + x) as usize];
+        }
+    }
+}
@@ -0,0 +1,119 @@
+use super::{BuilderChain, BuilderMap, InitialMapBuilder, Map, MetaMapBuilder, Prefab, PrefabBuilder, TileType};
+use bracket_lib::prelude::RandomNumberGenerator;
+mod constraints;
+mod solver;
+use constraints::*;
+use solver::{Solver, Step};
+
+/* Wave Function Collapse
+ *
+ * Builds a new map out of the pieces of an old one. The source (the map the
+ * previous builder left, or a prefab template) is cut into CHUNK_SIZE square
+ * patterns; the new map is a grid of them, chosen so that every two chunks
+ * that touch share the tiles along their touching edges (see solver.rs).
+ *
+ * Whatever the source had spawning, starting or stair-placing is gone with
+ * its layout, so the builders that follow have to choose them again. If the
+ * patterns can't be made to fit, the source is kept as it was.
+ */
+
+const CHUNK_SIZE: i32 = 8;
+
+pub struct WaveformCollapseBuilder {
+    template: Option<Prefab>,
+}
+
+impl MetaMapBuilder for WaveformCollapseBuilder {
+    fn build_map(&mut self, rng: &mut bracket_lib::prelude::RandomNumberGenerator, build_data : &mut BuilderMap)  {
+        self.build(rng, build_data);
+    }
+}
+
+impl InitialMapBuilder for WaveformCollapseBuilder {
+    fn build_map(&mut self, rng: &mut bracket_lib::prelude::RandomNumberGenerator, build_data : &mut BuilderMap)  {
+        self.build(rng, build_data);
+    }
+}
+
+impl WaveformCollapseBuilder {
+    //Remixes whatever the previous builder made.
+    pub fn new() -> Box<WaveformCollapseBuilder> {
+        Box::new(WaveformCollapseBuilder{ template: None })
+    }
+
+    //Remixes a prefab level; this one can also start a chain.
+    pub fn from_template(level: Prefab) -> Box<WaveformCollapseBuilder> {
+        Box::new(WaveformCollapseBuilder{ template: Some(level) })
+    }
+
+    fn build(&mut self, rng : &mut RandomNumberGenerator, build_data : &mut BuilderMap) {
+        let source = match self.template.clone() {
+            Some(level) => {
+                let mut scratch = BuilderChain::new(build_data.map.depth, build_data.width, build_data.height);
+                scratch.start_with(PrefabBuilder::constant(level));
+                scratch.build_map(rng);
+                scratch.build_data.map
+            }
+            None => build_data.map.clone(),
+        };
+
+        let patterns = build_patterns(&source, CHUNK_SIZE);
+        let constraints = patterns_to_constraints(patterns, CHUNK_SIZE);
+        let chunks_x = build_data.map.width / CHUNK_SIZE;
+        let chunks_y = build_data.map.height / CHUNK_SIZE;
+        let mut solver = Solver::new(constraints.len(), chunks_x, chunks_y);
+
+        loop {
+            match solver.iteration(&constraints, rng) {
+                Step::Progress => {
+                    render_solution(&mut build_data.map, &solver, &constraints, chunks_x, chunks_y);
+                    build_data.take_snapshot();
+                }
+                Step::Done => {
+                    render_solution(&mut build_data.map, &solver, &constraints, chunks_x, chunks_y);
+                    break;
+                }
+                Step::Failed => {
+                    bracket_lib::prelude::console::log("Wave Function Collapse found no fit; keeping the source map.");
+                    build_data.map.tiles = source.tiles;
+                    build_data.take_snapshot();
+                    return;
+                }
+            }
+        }
+
+        //Nothing may walk off the edge of the world.
+        let (width, height) = (build_data.map.width, build_data.map.height);
+        for (idx, tile) in build_data.map.tiles.iter_mut().enumerate() {
+            let (x, y) = (idx as i32 % width, idx as i32 / width);
+            if x == 0 || y == 0 || x >= width - 1 || y >= height - 1 || x >= chunks_x * CHUNK_SIZE || y >= chunks_y * CHUNK_SIZE {
+                *tile = TileType::Wall;
+            }
+        }
+
+        build_data.spawn_list.clear();
+        build_data.starting_position = None;
+        build_data.rooms = None;
+        build_data.corridors = None;
+        build_data.take_snapshot();
+    }
+}
+
+//Draws every settled chunk; anything still undecided is drawn as solid wall.
+fn render_solution(map: &mut Map, solver: &Solver, constraints: &[MapChunk], chunks_x: i32, chunks_y: i32) {
+    for cy in 0..chunks_y {
+        for cx in 0..chunks_x {
+            match solver.chosen(cx, cy) {
+                Some(pattern) => render_pattern_to_map(map, &constraints[pattern], CHUNK_SIZE, cx, cy),
+                None => {
+                    for y in 0..CHUNK_SIZE {
+                        for x in 0..CHUNK_SIZE {
+                            let idx = map.xy_idx(cx * CHUNK_SIZE + x, cy * CHUNK_SIZE + y);
+                            map.tiles[idx] = TileType::Wall;
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
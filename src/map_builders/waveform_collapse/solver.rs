@@ -0,0 +1,120 @@
+use std::collections::VecDeque;
+
+use bracket_lib::prelude::RandomNumberGenerator;
+
+use super::constraints::{MapChunk, NORTH, SOUTH, WEST, EAST};
+
+//How many wrong guesses to undo before giving up on the source's patterns.
+const MAX_BACKTRACKS: usize = 2000;
+
+pub enum Step {
+    Progress,
+    Done,
+    Failed,
+}
+
+//A guess, and everything as it was before it, to go back to if it leads nowhere.
+struct Decision {
+    cell: usize,
+    choice: usize,
+    before: Vec<Vec<usize>>,
+}
+
+/* The solver keeps, for every chunk-sized cell of the new map, the patterns
+ * still possible there. Each step collapses the cell with the fewest options
+ * left to one of them, at random, and propagates: a neighbour keeps only the
+ * patterns that fit beside something still possible in this cell, and so on
+ * outwards. A cell left with no options is a contradiction; the last guess is
+ * undone and struck off, and the search carries on from there.
+ */
+pub struct Solver {
+    chunks_x: i32,
+    chunks_y: i32,
+    possible: Vec<Vec<usize>>,
+    decisions: Vec<Decision>,
+    backtracks: usize,
+}
+
+impl Solver {
+    pub fn new(pattern_count: usize, chunks_x: i32, chunks_y: i32) -> Solver {
+        Solver {
+            chunks_x,
+            chunks_y,
+            possible: vec![(0..pattern_count).collect(); (chunks_x * chunks_y) as usize],
+            decisions: Vec::new(),
+            backtracks: 0,
+        }
+    }
+
+    //The pattern settled on for a cell, if it has been.
+    pub fn chosen(&self, cx: i32, cy: i32) -> Option<usize> {
+        match self.possible[(cy * self.chunks_x + cx) as usize].as_slice() {
+            [only] => Some(*only),
+            _ => None,
+        }
+    }
+
+    pub fn iteration(&mut self, constraints: &[MapChunk], rng: &mut RandomNumberGenerator) -> Step {
+        let cell = self.possible.iter().enumerate()
+            .filter(|(_, options)| options.len() > 1)
+            .min_by_key(|(_, options)| options.len())
+            .map(|(cell, _)| cell);
+        let cell = match cell {
+            Some(cell) => cell,
+            None => return Step::Done,
+        };
+
+        let options = &self.possible[cell];
+        let choice = options[(rng.roll_dice(1, options.len() as i32) - 1) as usize];
+        self.decisions.push(Decision { cell, choice, before: self.possible.clone() });
+        self.possible[cell] = vec![choice];
+
+        if self.propagate(constraints, cell) {
+            Step::Progress
+        } else {
+            self.backtrack(constraints)
+        }
+    }
+
+    fn backtrack(&mut self, constraints: &[MapChunk]) -> Step {
+        while let Some(decision) = self.decisions.pop() {
+            self.backtracks += 1;
+            if self.backtracks > MAX_BACKTRACKS { return Step::Failed; }
+
+            let Decision { cell, choice, before } = decision;
+            self.possible = before;
+            self.possible[cell].retain(|option| *option != choice);
+            if !self.possible[cell].is_empty() && self.propagate(constraints, cell) {
+                return Step::Progress;
+            }
+        }
+        Step::Failed //every guess, all the way back to the first, was wrong
+    }
+
+    //Narrows the cells around `from`, and theirs in turn. False on a contradiction.
+    fn propagate(&mut self, constraints: &[MapChunk], from: usize) -> bool {
+        let mut queue = VecDeque::new();
+        queue.push_back(from);
+
+        while let Some(cell) = queue.pop_front() {
+            let (cx, cy) = (cell as i32 % self.chunks_x, cell as i32 / self.chunks_x);
+            for (side, dx, dy) in [(NORTH, 0, -1), (SOUTH, 0, 1), (WEST, -1, 0), (EAST, 1, 0)].iter() {
+                let (nx, ny) = (cx + dx, cy + dy);
+                if nx < 0 || nx >= self.chunks_x || ny < 0 || ny >= self.chunks_y { continue; }
+                let neighbour = (ny * self.chunks_x + nx) as usize;
+
+                let allowed = &self.possible[cell];
+                let before = self.possible[neighbour].len();
+                let narrowed: Vec<usize> = self.possible[neighbour].iter().copied()
+                    .filter(|option| allowed.iter().any(|mine| constraints[*mine].compatible_with[*side][*option]))
+                    .collect();
+                if narrowed.is_empty() { return false; }
+                if narrowed.len() < before {
+                    self.possible[neighbour] = narrowed;
+                    queue.push_back(neighbour);
+                }
+            }
+        }
+        true
+    }
+}
@@ -1,5 +1,5 @@
 use specs::prelude::*;
-use super::{Map, Position, BlocksTile};
+use super::{Map, Position, BlocksTile, Creature, Door};
 
 /*April, 2022
  * Writing this note from the future, made a tiny readability edit, otherwise
@@ -26,13 +26,17 @@ impl<'a> System<'a> for MapIndexingSystem {
     type SystemData = ( WriteExpect<'a, Map>,
                         ReadStorage<'a, Position>,
                         ReadStorage<'a, BlocksTile>,
+                        ReadStorage<'a, Door>,
+                        ReadStorage<'a, Creature>,
                         Entities<'a> );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (mut map, position, blockers, entities) = data;
+        let (mut map, position, blockers, doors, creatures, entities) = data;
 
         map.populate_blocked();
         map.clear_content_index();
+        map.closed_doors.clear();
+        map.occupied.clear();
         for (entity, position) in (&entities, &position).join() {
             let idx = map.xy_idx(position.x, position.y);
             
@@ -40,6 +44,14 @@ impl<'a> System<'a> for MapIndexingSystem {
             let is_blocker: Option<&BlocksTile> = blockers.get(entity);
             if let Some(_) = is_blocker {
                 map.blocked[idx] = true;
+                if creatures.get(entity).is_some() {
+                    map.occupied.insert(idx);
+                }
+            }
+            if let Some(door) = doors.get(entity) {
+                if !door.open && !door.locked {
+                    map.closed_doors.insert(idx);
+                }
             }
         
             map.tile_content[idx].push(entity);
@@ -0,0 +1,172 @@
+use bracket_lib::prelude::{BTerm, VirtualKeyCode, BLACK, GREY, RGB, WHITE, YELLOW};
+
+use super::map_builders::{self, Snapshot};
+use super::{camera, rng, MAP_HEIGHT, MAP_WIDTH};
+
+/* Map generation visualizer
+ *
+ * A debug mode for tuning builders. While it's on (F12 in game, or
+ * `--mapgen-visualizer` on the command line), every newly generated level
+ * keeps a snapshot of the map after each builder step, and before play goes
+ * on the game plays them back, labelled with the step that made each one:
+ *
+ *   space         pause / resume
+ *   left, right   step back / forward (pausing)
+ *   home, end     first / last snapshot
+ *   r             reload raws/map_recipes.yaml and raws/prefabs/, and build the
+ *                 same depth with a new seed
+ *   q             carry on with the game
+ *
+ * A level built with r is only a preview; the game goes on with the level it
+ * built. Its seed and depth, given to `mapgen`, print it again. The keys are
+ * ones the game doesn't use, so none of them queue a move.
+ *
+ * MapgenRecorder is the World's half: generate_world_map() reads whether to
+ * take snapshots from it and leaves them there. MapgenVisualizer is the
+ * window's half, which plays them.
+ */
+
+const FRAME_MS: f32 = 300.0;
+
+//The snapshots of one build, and what built it.
+pub struct Recording {
+    pub seed: u64,
+    pub depth: i32,
+    pub snapshots: Vec<Snapshot>,
+    pub preview: bool,
+}
+
+#[derive(Default)]
+pub struct MapgenRecorder {
+    pub enabled: bool,
+    unseen: Option<Recording>,
+}
+
+impl MapgenRecorder {
+    pub fn record(&mut self, seed: u64, depth: i32, snapshots: Vec<Snapshot>) {
+        if self.enabled && !snapshots.is_empty() {
+            self.unseen = Some(Recording { seed, depth, snapshots, preview: false });
+        }
+    }
+
+    pub fn toggle(&mut self) -> bool {
+        self.enabled = !self.enabled;
+        self.enabled
+    }
+
+    //The last build's snapshots, if they haven't been played yet.
+    pub fn take(&mut self) -> Option<Recording> {
+        self.unseen.take()
+    }
+}
+
+#[derive(Default)]
+pub struct MapgenVisualizer {
+    recording: Option<Recording>,
+    index: usize,
+    timer: f32,
+    paused: bool,
+    message: Option<String>,
+}
+
+impl MapgenVisualizer {
+    pub fn play(&mut self, recording: Recording) {
+        self.recording = Some(recording);
+        self.index = 0;
+        self.timer = 0.0;
+        self.paused = false;
+    }
+
+    //Draws the current snapshot and handles the keys. Returns false once the
+    //developer is done (or the last snapshot has played), and play should go on.
+    pub fn tick(&mut self, ctx: &mut BTerm) -> bool {
+        let count = match &self.recording {
+            Some(recording) if !recording.snapshots.is_empty() => recording.snapshots.len(),
+            _ => return false,
+        };
+
+        match ctx.key {
+            Some(VirtualKeyCode::Space) => self.paused = !self.paused,
+            Some(VirtualKeyCode::Right) => self.step_to(self.index + 1),
+            Some(VirtualKeyCode::Left) => self.step_to(self.index.saturating_sub(1)),
+            Some(VirtualKeyCode::Home) => self.step_to(0),
+            Some(VirtualKeyCode::End) => self.step_to(count - 1),
+            Some(VirtualKeyCode::R) => self.regenerate(),
+            Some(VirtualKeyCode::Q) => {
+                self.recording = None;
+                return false;
+            }
+            _ => {}
+        }
+
+        if !self.paused {
+            self.timer += ctx.frame_time_ms;
+            if self.timer > FRAME_MS {
+                self.timer = 0.0;
+                if self.index + 1 >= count {
+                    self.recording = None;
+                    return false;
+                }
+                self.index += 1;
+            }
+        }
+
+        self.draw(ctx);
+        true
+    }
+
+    fn step_to(&mut self, index: usize) {
+        let count = self.recording.as_ref().map_or(0, |recording| recording.snapshots.len());
+        self.index = index.min(count.saturating_sub(1));
+        self.timer = 0.0;
+        self.paused = true;
+    }
+
+    //Builds a preview with a fresh seed, from the recipes and prefabs as they are on disk now.
+    fn regenerate(&mut self) {
+        let depth = match &self.recording {
+            Some(recording) => recording.depth,
+            None => return,
+        };
+        //Prefabs first: the recipes are checked against them.
+        self.message = map_builders::load_prefabs(map_builders::PREFABS_PATH)
+            .and_then(|_| map_builders::load_recipes(map_builders::RECIPES_PATH)).err()
+            .map(|e| format!("Keeping what was loaded; {}", e));
+
+        let seed: u64 = rand::random();
+        let mut rng = rng::mapgen_rng(seed, depth);
+        let mut builder = map_builders::random_builder(depth, &mut rng, MAP_WIDTH, MAP_HEIGHT);
+        builder.build_data.take_snapshots = true;
+        builder.build_map(&mut rng);
+        let snapshots = std::mem::take(&mut builder.build_data.snapshot_history);
+        self.play(Recording { seed, depth, snapshots, preview: true });
+        self.paused = true;
+    }
+
+    fn draw(&self, ctx: &mut BTerm) {
+        let recording = match &self.recording {
+            Some(recording) => recording,
+            None => return,
+        };
+        let snapshot = &recording.snapshots[self.index];
+        camera::render_debug_map(&snapshot.map, ctx);
+
+        let (width, height) = ctx.get_char_size();
+        let blank = " ".repeat(width as usize);
+        let header = format!("seed {}, depth {}, {}{}  snapshot {} of {}: {}{}",
+                             recording.seed, recording.depth, snapshot.map.biome.name,
+                             if recording.preview { " (preview)" } else { "" },
+                             self.index + 1, recording.snapshots.len(), snapshot.step,
+                             if self.paused { "  [paused]" } else { "" });
+        ctx.print_color(0, 0, RGB::named(WHITE), RGB::named(BLACK), &blank);
+        ctx.print_color(0, 0, RGB::named(YELLOW), RGB::named(BLACK), &header);
+
+        let footer = match &self.message {
+            Some(message) => message.clone(),
+            None => "space pause  left/right step  home/end  r new seed  q carry on".to_string(),
+        };
+        let bottom = height as i32 - 1;
+        ctx.print_color(0, bottom, RGB::named(WHITE), RGB::named(BLACK), &blank);
+        ctx.print_color(0, bottom, RGB::named(GREY), RGB::named(BLACK), &footer);
+    }
+}
@@ -1,18 +1,20 @@
 use specs::prelude::*;
-use super::{Stats, MeleeIntent, DamageQueue, BasicAttack};
+use super::{Stats, MeleeIntent, BasicAttack, Energy, initiative_system::ACTION_COST,
+            effects::{EffectQueue, EffectType, Targets}};
 
 pub struct MeleeCombatSystem {}
 
 impl<'a> System<'a> for MeleeCombatSystem {
     type SystemData = ( Entities<'a>,
+                        WriteExpect<'a, EffectQueue>,
                         WriteStorage<'a, MeleeIntent>, 
-                        WriteStorage<'a, DamageQueue>,
                         ReadStorage<'a, BasicAttack>,
                         ReadStorage<'a, Stats>,
+                        WriteStorage<'a, Energy>,
                       );
     fn run(&mut self, data: Self::SystemData) {
-        let (entities, mut melee_intents, mut damage_queues,
-             basic_attacks, stats) = data;
+        let (entities, mut effects, mut melee_intents,
+             basic_attacks, stats, mut energies) = data;
 
         //Queue dmg from all living entities with MeleeIntent.
         for (entity, melee_intent, basic_attack) in
@@ -21,11 +23,14 @@ impl<'a> System<'a> for MeleeCombatSystem {
             //If entity with intent is dead, well... they can't melee.
             if stats.get(entity).unwrap().hp <= 0 {return;}
 
+            Energy::spend(&mut energies, entity, ACTION_COST);
+
             let target = melee_intent.target;
             let t_stats = stats.get(target).unwrap();
 
             if t_stats.hp > 0 {   
-                DamageQueue::queue_damage(&mut damage_queues, target, basic_attack.current);
+                effects.push(Some(entity), EffectType::Damage { atoms: vec![basic_attack.current] },
+                    Targets::Entities { list: vec![target] });
             }
         }
         
@@ -60,6 +60,9 @@ impl ParticleBuilder {
     }
 }
 
+//Hides particles once their lifetime runs out. They are only deleted by
+//delete_particles() at the start of the next turn: frame times differ from run
+//to run, and deleting here would hand out different Entity ids on replay.
 pub fn cull_dead_particles(ecs : &mut World, ctx : &BTerm) {
     let mut dead_particles : Vec<Entity> = Vec::new();
     {
@@ -73,7 +76,19 @@ pub fn cull_dead_particles(ecs : &mut World, ctx : &BTerm) {
             }
         }                    
     }
+    let mut renderables = ecs.write_storage::<Renderable>();
     for dead in dead_particles.iter() {
-        ecs.delete_entity(*dead).expect("Particle will not die");
+        renderables.remove(*dead);
     } 
 }
+
+pub fn delete_particles(ecs : &mut World) {
+    let dead_particles : Vec<Entity> = {
+        let particles = ecs.read_storage::<Particle>();
+        let entities = ecs.entities();
+        (&entities, &particles).join().map(|(entity, _)| entity).collect()
+    };
+    for dead in dead_particles.iter() {
+        ecs.delete_entity(*dead).expect("Particle will not die");
+    }
+}
@@ -1,12 +1,13 @@
 use std::cmp::{max, min};
 use std::sync::Arc;
 
-use bracket_lib::prelude::Point;
+use bracket_lib::prelude::{Point, RandomNumberGenerator};
 use specs::prelude::*;
 
 use super::{
-    gui::gamelog, Hostile, Hunger, HungerState, Item, JustMoved, Map, MeleeIntent, PickUpIntent,
-    Player, Position, RunState, Stats, TileType, Viewshed,
+    door_system::key_carried_by, events::{EventQueue, GameEvent}, gui::gamelog, raws, Hidden, initiative_system::{move_cost, ACTION_COST}, Door, DoorIntent, Energy, Hostile,
+    Hunger, HungerState, InBackpack, Item, JustMoved, Key, Map, MeleeIntent, PickUpIntent, Player, Position, RunState, Stats,
+    TileType, Viewshed,
 };
 use crate::command::*;
 use crate::gui::{look_n_feel::Dir, Observable, Observer};
@@ -55,6 +56,10 @@ impl Observer for PlayerController {
                     InputEvent::WASD(dir) => Some(Command::Move { dir }), //move
                     InputEvent::ENTER => Some(Command::Grab),             //context action
                     InputEvent::SPACE => Some(Command::Wait),             //wait
+                    InputEvent::ESC => Some(Command::Save),               //save & quit to menu
+                    InputEvent::STAIRS => Some(Command::Stairs),          //take the stairs
+                    InputEvent::CLOSE => Some(Command::Close),            //close a door
+                    InputEvent::SEARCH => Some(Command::Search),          //search for secrets
                     _ => None,
                 };
 
@@ -81,10 +86,15 @@ impl Commandable for PlayerController {
     //the series of commands currently stored in the CommandQueue.
     fn ecs_process(&self, ecs: &mut World, runstate: RunState) -> RunState {
         let mut runstate: RunState = runstate;
-        for cmd in &self.cmd_queue.iter() {
+        let commands: Vec<Command> = (&self.cmd_queue.iter()).into_iter().copied().collect();
+        //Saving isn't part of the run; a replay that saved would quit to the menu.
+        let played: Vec<Command> = commands.iter().copied().filter(|cmd| *cmd != Command::Save).collect();
+        crate::replay::record(ecs, &played);
+
+        for cmd in commands.iter() {
             match cmd {
                 Command::Grab => {
-                    get_item(ecs);
+                    runstate = get_item(ecs);
                 }
                 Command::Move { dir } => {
                     match dir {
@@ -95,7 +105,19 @@ impl Commandable for PlayerController {
                     };
                 }
                 Command::Wait => {
-                    skip_turn(ecs);
+                    runstate = skip_turn(ecs);
+                }
+                Command::Save => {
+                    runstate = RunState::SaveGame;
+                }
+                Command::Stairs => {
+                    runstate = try_stairs(ecs);
+                }
+                Command::Close => {
+                    runstate = close_door(ecs);
+                }
+                Command::Search => {
+                    runstate = search(ecs);
                 }
                 _ => {}
             };
@@ -146,7 +168,7 @@ impl Commandable for PlayerController {
 
             //use stairs
             VirtualKeyCode::Period => {
-                try_next_level(ecs)
+                try_stairs(ecs)
             }
 
             //save
@@ -193,6 +215,8 @@ fn try_move_player(delta_x: i32, delta_y: i32, ecs: &mut World) -> RunState {
     let map = ecs.fetch::<Map>();
     let player = ecs.fetch::<Entity>();
     let mut just_moved_storage = ecs.write_storage::<JustMoved>();
+    let doors = ecs.read_storage::<Door>();
+    let mut door_intents = ecs.write_storage::<DoorIntent>();
 
     for (entity, _, pos, viewshed) in
         (&entities, &player_storage, &mut positions, &mut viewsheds).join()
@@ -224,6 +248,27 @@ fn try_move_player(delta_x: i32, delta_y: i32, ecs: &mut World) -> RunState {
             }
         }
 
+        //bumping into a closed door opens it, if it can be opened
+        for potential_door in map.tile_content[destination_idx].iter() {
+            match doors.get(*potential_door) {
+                Some(door) if !door.open => {
+                    let keys = ecs.read_storage::<Key>();
+                    let backpacks = ecs.read_storage::<InBackpack>();
+                    if door.locked && key_carried_by(entity, &entities, &keys, &backpacks).is_none() {
+                        let mut logger = gamelog::Logger::new();
+                        logger.append("The door is locked.");
+                        logger.log();
+                        return RunState::AwaitingInput;
+                    }
+                    door_intents
+                        .insert(entity, DoorIntent { door: *potential_door, open: true })
+                        .expect("Unable to insert DoorIntent.");
+                    return RunState::PlayerTurn;
+                }
+                _ => {}
+            }
+        }
+
         if !map.blocked[destination_idx] {
             pos.x = min(map.width - 1, max(0, pos.x + delta_x));
             pos.y = min(map.height - 1, max(0, pos.y + delta_y));
@@ -236,6 +281,7 @@ fn try_move_player(delta_x: i32, delta_y: i32, ecs: &mut World) -> RunState {
             just_moved_storage
                 .insert(*player, JustMoved {})
                 .expect("Unable to insert JustMoved component.");
+            Energy::spend(&mut ecs.write_storage::<Energy>(), *player, move_cost(&map, destination_idx));
 
             /*Move Cursor with player
             let mut cursor = ecs.fetch_mut::<Cursor>();
@@ -250,18 +296,20 @@ fn try_move_player(delta_x: i32, delta_y: i32, ecs: &mut World) -> RunState {
     return RunState::AwaitingInput;
 }
 
-fn try_next_level(ecs: &mut World) -> RunState {
+fn try_stairs(ecs: &mut World) -> RunState {
     let player_pos = ecs.fetch::<Point>();
     let map = ecs.fetch::<Map>();
     let player_idx = map.xy_idx(player_pos.x, player_pos.y);
 
-    if map.tiles[player_idx] == TileType::StairsDown {
-        return RunState::NextLevel;
-    } else {
-        let mut logger = gamelog::Logger::new();
-        logger.append("There is no way down from here.");
-        logger.log();
-        return RunState::AwaitingInput;
+    match map.tiles[player_idx] {
+        TileType::StairsDown => RunState::NextLevel,
+        TileType::StairsUp => RunState::PreviousLevel,
+        _ => {
+            let mut logger = gamelog::Logger::new();
+            logger.append("There are no stairs here.");
+            logger.log();
+            RunState::AwaitingInput
+        }
     }
 }
 
@@ -284,6 +332,7 @@ fn get_item(ecs: &mut World) -> RunState {
             let mut logger = gamelog::Logger::new();
             logger.append("There is nothing here to pick up.");
             logger.log();
+            return RunState::AwaitingInput;
         }
         Some(item) => {
             let mut pickup = ecs.write_storage::<PickUpIntent>();
@@ -302,6 +351,108 @@ fn get_item(ecs: &mut World) -> RunState {
     return RunState::PlayerTurn;
 }
 
+//Closes an open door next to the player, if there's one with nothing in the way.
+fn close_door(ecs: &mut World) -> RunState {
+    let player_pos = ecs.fetch::<Point>();
+    let player_entity = ecs.fetch::<Entity>();
+    let map = ecs.fetch::<Map>();
+    let doors = ecs.read_storage::<Door>();
+    let stats = ecs.read_storage::<Stats>();
+    let items = ecs.read_storage::<Item>();
+
+    let mut open_doors = Vec::new();
+    for (dx, dy) in [(-1, -1), (0, -1), (1, -1), (-1, 0), (1, 0), (-1, 1), (0, 1), (1, 1)].iter() {
+        let (x, y) = (player_pos.x + dx, player_pos.y + dy);
+        if x < 0 || x >= map.width || y < 0 || y >= map.height {
+            continue;
+        }
+        let content = &map.tile_content[map.xy_idx(x, y)];
+        let door = content.iter().find(|e| doors.get(**e).is_some_and(|door| door.open));
+        if let Some(door) = door {
+            let in_the_way = content.iter().any(|e| stats.get(*e).is_some() || items.get(*e).is_some());
+            open_doors.push((*door, in_the_way));
+        }
+    }
+
+    match open_doors.iter().find(|(_, in_the_way)| !in_the_way) {
+        Some((door, _)) => {
+            ecs.write_storage::<DoorIntent>()
+                .insert(*player_entity, DoorIntent { door: *door, open: false })
+                .expect("Unable to insert DoorIntent.");
+            RunState::PlayerTurn
+        }
+        None => {
+            let mut logger = gamelog::Logger::new();
+            if open_doors.is_empty() {
+                logger.append("There is no open door here to close.");
+            } else {
+                logger.append("Something is in the way of the door.");
+            }
+            logger.log();
+            RunState::AwaitingInput
+        }
+    }
+}
+
+//How far a search reaches, and the d20 + mind it takes to find each thing there.
+const SEARCH_RADIUS: i32 = 2;
+const SEARCH_DIFFICULTY: i32 = 15;
+
+//Spends a turn looking for secret doors, hidden passages and Hidden things (traps)
+//the player can see nearby. Each is rolled for on its own.
+fn search(ecs: &mut World) -> RunState {
+    let player_entity = *ecs.fetch::<Entity>();
+    let player_pos = *ecs.fetch::<Point>();
+    let mind = ecs.read_storage::<Stats>().get(player_entity).map_or(0, |stats| stats.mind);
+    let nearby: Vec<Point> = match ecs.read_storage::<Viewshed>().get(player_entity) {
+        Some(viewshed) => viewshed.visible_tiles.iter().copied()
+            .filter(|pt| (pt.x - player_pos.x).abs() <= SEARCH_RADIUS && (pt.y - player_pos.y).abs() <= SEARCH_RADIUS)
+            .collect(),
+        None => Vec::new(),
+    };
+
+    let mut revealed = false;
+    let mut secret_doors = Vec::new();
+    {
+        let mut map = ecs.fetch_mut::<Map>();
+        let mut rng = ecs.fetch_mut::<RandomNumberGenerator>();
+        let mut events = ecs.fetch_mut::<EventQueue>();
+        let mut hidden = ecs.write_storage::<Hidden>();
+        let mut found = || rng.roll_dice(1, 20) + mind >= SEARCH_DIFFICULTY;
+
+        for pt in nearby.iter() {
+            let idx = map.xy_idx(pt.x, pt.y);
+            if map.tiles[idx].looks_like() != map.tiles[idx] && found() {
+                if let Some(secret) = map.reveal_secret(idx) {
+                    revealed = true;
+                    events.push(GameEvent::SecretFound { by: player_entity, secret });
+                    if secret == TileType::SecretDoor {
+                        secret_doors.push(*pt);
+                    }
+                }
+            }
+            for entity in map.tile_content[idx].iter() {
+                if hidden.get(*entity).is_some() && found() {
+                    hidden.remove(*entity);
+                    events.push(GameEvent::Spotted { entity: *entity });
+                }
+            }
+        }
+    }
+
+    for pt in secret_doors {
+        raws::spawn_named_entity(ecs, "Door", pt.x, pt.y);
+    }
+    if revealed {
+        //The walls changed, for anyone who can see them.
+        for viewshed in (&mut ecs.write_storage::<Viewshed>()).join() {
+            viewshed.dirty = true;
+        }
+    }
+    Energy::spend(&mut ecs.write_storage::<Energy>(), player_entity, ACTION_COST);
+    RunState::PlayerTurn
+}
+
 fn skip_turn(ecs: &mut World) -> RunState {
     let player_entity = ecs.fetch::<Entity>();
     let viewshed_components = ecs.read_storage::<Viewshed>();
@@ -325,6 +476,8 @@ fn skip_turn(ecs: &mut World) -> RunState {
         }
     }
 
+    Energy::spend(&mut ecs.write_storage::<Energy>(), *player_entity, ACTION_COST);
+
     if can_heal {
         let mut stats = ecs.write_storage::<Stats>();
         let p_stats = stats.get_mut(*player_entity).unwrap();
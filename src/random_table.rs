@@ -1,13 +1,18 @@
 use bracket_lib::prelude::RandomNumberGenerator;
 
+enum Roll {
+    Name(String),
+    Table(RandomTable), //rolled on in turn; see add_table()
+}
+
 pub struct RandomEntry {
-    name : String,
+    roll : Roll,
     weight : i32
 }
 
 impl RandomEntry {
     pub fn new<S:ToString>(name: S, weight: i32) -> RandomEntry {
-        RandomEntry {name: name.to_string(), weight}
+        RandomEntry {roll: Roll::Name(name.to_string()), weight}
     }
 }
 
@@ -30,20 +35,33 @@ impl RandomTable {
         self
     }
 
-    pub fn roll(&self, rng : &mut RandomNumberGenerator) -> String {
-        if self.total_weight == 0 { return "None".to_string(); }
+    //Nests a whole table as one entry; landing on it rolls again on `table`.
+    pub fn add_table(mut self, table: RandomTable, weight: i32) -> RandomTable {
+        if weight > 0 && !table.is_empty() {
+            self.total_weight += weight;
+            self.entries.push(RandomEntry { roll: Roll::Table(table), weight });
+        }
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.total_weight == 0
+    }
+
+    pub fn roll(&self, rng : &mut RandomNumberGenerator) -> Option<String> {
+        if self.total_weight == 0 { return None; }
         let mut roll = rng.roll_dice(1, self.total_weight)-1;
-        let mut index : usize = 0;
 
-        while roll > 0 {
-            if roll < self.entries[index].weight {
-                return self.entries[index].name.clone();
+        for entry in self.entries.iter() {
+            if roll < entry.weight {
+                return match &entry.roll {
+                    Roll::Name(name) => Some(name.clone()),
+                    Roll::Table(table) => table.roll(rng),
+                };
             }
-
-            roll -= self.entries[index].weight;
-            index += 1;
+            roll -= entry.weight;
         }
 
-        "None".to_string()
+        None
     }
 }
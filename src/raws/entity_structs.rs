@@ -0,0 +1,138 @@
+use std::collections::BTreeMap;
+
+use serde::Deserialize;
+
+use super::super::{DamageAtom, EquipmentSlot};
+use super::RawSpawnEntry;
+
+//The whole raws file. Which list an entry is in decides the components every
+//entry of that kind gets (see spawn_named_entity()); its fields add the rest.
+#[derive(Deserialize, Clone, Debug, Default)]
+#[serde(deny_unknown_fields)]
+pub struct Raws {
+    #[serde(default)]
+    pub monsters: Vec<RawEntity>,
+    #[serde(default)]
+    pub items: Vec<RawEntity>,
+    #[serde(default)]
+    pub traps: Vec<RawEntity>,
+    #[serde(default)]
+    pub props: Vec<RawEntity>,
+    #[serde(default)]
+    pub spawn_tables: BTreeMap<String, Vec<RawSpawnEntry>>,
+}
+
+#[derive(Deserialize, Clone, Debug, Default)]
+#[serde(deny_unknown_fields)]
+pub struct RawEntity {
+    pub name: String,
+    pub display_name: Option<String>, //in-game Name, if it differs from the spawn name
+    pub renderable: Option<RawRenderable>,
+    pub stats: Option<RawStats>,
+    pub vision: Option<i32>,
+    pub speed: Option<i32>,
+    pub useable: Option<String>,
+    #[serde(default)]
+    pub consumable: bool,
+    pub ranged: Option<i32>,
+    pub heals: Option<RawHeals>,
+    pub damage_on_use: Option<Vec<DamageAtom>>,
+    pub aoe: Option<i32>,
+    pub confusion: Option<i32>,
+    #[serde(default)]
+    pub magic_mapper: bool,
+    pub throwable: Option<DamageAtom>,
+    pub equippable: Option<EquipmentSlot>,
+    pub weapon: Option<RawWeapon>,
+    pub resistances: Option<RawResistances>,
+    pub blocks_attacks: Option<RawBlocksAttacks>,
+    #[serde(default)]
+    pub flammable: bool,
+    #[serde(default)]
+    pub hidden: bool,
+    pub entry_trigger: Option<RawEntryTrigger>,
+    #[serde(default)]
+    pub blocks_tile: bool,
+    pub door: Option<RawDoor>,
+    #[serde(default)]
+    pub key: bool,
+}
+
+//A character from the cp437 set ("o"), or a raw cp437 code (173).
+#[derive(Deserialize, Clone, Copy, Debug)]
+#[serde(untagged)]
+pub enum RawGlyph {
+    Code(u16),
+    Char(char),
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct RawRenderable {
+    pub glyph: RawGlyph,
+    pub fg: String,
+    #[serde(default = "default_bg")]
+    pub bg: String,
+    #[serde(default)]
+    pub order: i32,
+}
+
+fn default_bg() -> String {
+    "#000000".to_string()
+}
+
+#[derive(Deserialize, Clone, Copy, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct RawStats {
+    pub hp: i32,
+    pub fp: i32,
+    pub mp: i32,
+    pub mind: i32,
+    pub body: i32,
+    pub soul: i32,
+}
+
+#[derive(Deserialize, Clone, Copy, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct RawHeals {
+    pub duration: i32,
+    pub amount: i32,
+}
+
+#[derive(Deserialize, Clone, Copy, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct RawWeapon {
+    pub primary: Option<DamageAtom>,
+    pub secondary: Option<DamageAtom>,
+    pub tertiary: Option<DamageAtom>,
+}
+
+#[derive(Deserialize, Clone, Copy, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct RawResistances {
+    pub bludgeon: i32,
+    pub pierce: i32,
+    pub slash: i32,
+    pub thermal: i32,
+}
+
+#[derive(Deserialize, Clone, Copy, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct RawBlocksAttacks {
+    pub chance: f32,
+    pub coverage: u8,
+}
+
+#[derive(Deserialize, Clone, Copy, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct RawEntryTrigger {
+    pub repeatable: bool,
+}
+
+//Doors start closed.
+#[derive(Deserialize, Clone, Copy, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct RawDoor {
+    #[serde(default)]
+    pub locked: bool,
+}
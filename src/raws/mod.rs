@@ -0,0 +1,396 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::Mutex;
+
+use bracket_lib::prelude::{to_cp437, RGB};
+use specs::prelude::*;
+use specs::saveload::{MarkedBuilder, SimpleMarker};
+
+mod entity_structs;
+pub use entity_structs::*;
+mod spawn_table_structs;
+pub use spawn_table_structs::*;
+
+use super::{AoE, BasicAttack, BlocksAttacks, BlocksTile, BlocksVisibility, Confusion, Consumable, Creature,
+            DamageAtom, DamageOnUse, Door, Energy, EntryTrigger, Equippable, Flammable, Heals, Hidden, Hostile,
+            Item, Key, MagicMapper, Menuable, Name, Position, Ranged, Renderable, Resistances, SerializeMe,
+            Stats, Throwable, Useable, Viewshed, Weapon, initiative_system::NORMAL_SPEED,
+            map_builders::{PREFABS, PREFAB_SPAWNS, RECIPES}, random_table::RandomTable, spawner::ROOM_TABLE};
+
+/* Raws
+ *
+ * Monsters, items, traps and props are defined in raws/spawns.yaml rather than
+ * in code. The file is read once at startup by load_raws(); the copy compiled
+ * into the binary is what you get if that fails (and what tests see).
+ *
+ * spawn_named_entity() builds an entity from its definition. Which list a
+ * definition is in (its RawKind) decides the components every entity of that
+ * kind has; the definition's own fields add the rest.
+ *
+ * Spawn tables live in the same file. spawn_table() turns one into a
+ * RandomTable for a given depth: entries outside their min/max depth are left
+ * out, weights are worked out for that depth, and entries naming another
+ * table nest it.
+ *
+ * Definitions are checked when loaded (unique names, valid colors, monsters
+ * have stats, tables don't contain themselves), so a broken file is reported
+ * up front and never half-applied. validate() then lists the softer problems:
+ * spawn tables and prefabs naming things that can't be built.
+ */
+
+pub const RAWS_PATH: &str = "./raws/spawns.yaml";
+const BUILTIN_RAWS: &str = include_str!("../../raws/spawns.yaml");
+
+const DEFAULT_MONSTER_VISION: i32 = 5;
+
+lazy_static! {
+    pub static ref RAWS: Mutex<RawMaster> = Mutex::new(RawMaster::builtin());
+}
+
+#[derive(Debug)]
+pub enum RawsError {
+    Io(std::io::Error),
+    Parse(serde_yaml::Error),
+    DuplicateName(String),
+    Invalid { name: String, reason: String },
+}
+
+impl fmt::Display for RawsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RawsError::Io(e) => write!(f, "raws file i/o failed: {}", e),
+            RawsError::Parse(e) => write!(f, "raws file is malformed: {}", e),
+            RawsError::DuplicateName(name) => write!(f, "raws define \"{}\" more than once", name),
+            RawsError::Invalid { name, reason } => write!(f, "raw \"{}\": {}", name, reason),
+        }
+    }
+}
+
+impl std::error::Error for RawsError {}
+
+impl From<std::io::Error> for RawsError {
+    fn from(e: std::io::Error) -> Self {
+        RawsError::Io(e)
+    }
+}
+
+impl From<serde_yaml::Error> for RawsError {
+    fn from(e: serde_yaml::Error) -> Self {
+        RawsError::Parse(e)
+    }
+}
+
+//Something validate() found that would make a spawn quietly do nothing.
+#[derive(PartialEq, Clone, Debug)]
+pub struct RawsProblem {
+    pub location: String,
+    pub message: String,
+}
+
+impl fmt::Display for RawsProblem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.location, self.message)
+    }
+}
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum RawKind {
+    Monster,
+    Item,
+    Trap,
+    Prop,
+}
+
+pub struct RawMaster {
+    raws: Raws,
+    index: BTreeMap<String, (RawKind, usize)>,
+}
+
+impl RawMaster {
+    pub fn parse(yaml: &str) -> Result<RawMaster, RawsError> {
+        let raws: Raws = serde_yaml::from_str(yaml)?;
+        let mut index = BTreeMap::new();
+
+        let lists = [
+            (RawKind::Monster, &raws.monsters),
+            (RawKind::Item, &raws.items),
+            (RawKind::Trap, &raws.traps),
+            (RawKind::Prop, &raws.props),
+        ];
+        for (kind, list) in lists.iter() {
+            for (i, raw) in list.iter().enumerate() {
+                validate(*kind, raw)?;
+                if index.insert(raw.name.clone(), (*kind, i)).is_some() {
+                    return Err(RawsError::DuplicateName(raw.name.clone()));
+                }
+            }
+        }
+
+        for (table, entries) in raws.spawn_tables.iter() {
+            for (i, entry) in entries.iter().enumerate() {
+                if entry.name.is_some() == entry.table.is_some() {
+                    return Err(RawsError::Invalid {
+                        name: table.clone(),
+                        reason: format!("entry {} needs exactly one of `name` and `table`", i + 1),
+                    });
+                }
+            }
+            if contains_table(&raws, table, table, &mut Vec::new()) {
+                return Err(RawsError::Invalid { name: table.clone(), reason: "spawn table contains itself".to_string() });
+            }
+        }
+
+        Ok(RawMaster { raws, index })
+    }
+
+    pub fn builtin() -> RawMaster {
+        RawMaster::parse(BUILTIN_RAWS).expect("Built-in raws are invalid.")
+    }
+
+    pub fn get(&self, name: &str) -> Option<(RawKind, &RawEntity)> {
+        let (kind, i) = *self.index.get(name)?;
+        let list = match kind {
+            RawKind::Monster => &self.raws.monsters,
+            RawKind::Item => &self.raws.items,
+            RawKind::Trap => &self.raws.traps,
+            RawKind::Prop => &self.raws.props,
+        };
+        Some((kind, &list[i]))
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.index.contains_key(name)
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &String> {
+        self.index.keys()
+    }
+
+    //The named table as it stands at `depth`. Unknown tables are empty.
+    pub fn spawn_table(&self, name: &str, depth: i32) -> RandomTable {
+        let mut table = RandomTable::new();
+        for entry in self.raws.spawn_tables.get(name).into_iter().flatten() {
+            if !entry.in_depth(depth) {
+                continue;
+            }
+            let weight = entry.weight.at_depth(depth);
+            table = match (&entry.name, &entry.table) {
+                (Some(name), _) => table.add(name, weight),
+                (None, Some(sub_table)) => table.add_table(self.spawn_table(sub_table, depth), weight),
+                (None, None) => table,
+            };
+        }
+        table
+    }
+
+    //References the game can't resolve: unknown entities or tables in spawn
+    //tables and prefabs, biomes' unknown spawn tables, and a missing room table.
+    pub fn validate(&self) -> Vec<RawsProblem> {
+        let mut problems = Vec::new();
+        let mut problem = |location: String, message: String| problems.push(RawsProblem { location, message });
+
+        if !self.raws.spawn_tables.contains_key(ROOM_TABLE) {
+            problem("spawn tables".to_string(), format!("no \"{}\" table to fill rooms from", ROOM_TABLE));
+        }
+
+        for (table, entries) in self.raws.spawn_tables.iter() {
+            for (i, entry) in entries.iter().enumerate() {
+                let location = format!("spawn table \"{}\", entry {}", table, i + 1);
+                if let Some(name) = &entry.name {
+                    if !self.contains(name) {
+                        problem(location.clone(), format!("unknown entity \"{}\"", name));
+                    }
+                }
+                if let Some(sub_table) = &entry.table {
+                    if !self.raws.spawn_tables.contains_key(sub_table) {
+                        problem(location.clone(), format!("unknown spawn table \"{}\"", sub_table));
+                    }
+                }
+                if let (Some(min), Some(max)) = (entry.min_depth, entry.max_depth) {
+                    if min > max {
+                        problem(location, format!("min_depth {} is deeper than max_depth {}", min, max));
+                    }
+                }
+            }
+        }
+
+        for (glyph, name) in PREFAB_SPAWNS.iter() {
+            if !self.contains(name) {
+                problem(format!("prefab glyph '{}'", glyph), format!("unknown entity \"{}\"", name));
+            }
+        }
+        for prefab in PREFABS.lock().unwrap().all() {
+            for name in prefab.spawn_names().into_iter().filter(|name| !self.contains(name)) {
+                problem(format!("prefab \"{}\"", prefab.name), format!("unknown entity \"{}\"", name));
+            }
+        }
+        for biome in RECIPES.lock().unwrap().biomes() {
+            if !self.raws.spawn_tables.contains_key(&biome.spawn_table) {
+                problem(format!("biome \"{}\"", biome.name), format!("unknown spawn table \"{}\"", biome.spawn_table));
+            }
+        }
+
+        problems
+    }
+}
+
+//True if `table` nests `target`, at any depth.
+fn contains_table<'a>(raws: &'a Raws, table: &'a str, target: &str, visited: &mut Vec<&'a str>) -> bool {
+    if visited.contains(&table) {
+        return false;
+    }
+    visited.push(table);
+    let entries = match raws.spawn_tables.get(table) {
+        Some(entries) => entries,
+        None => return false,
+    };
+    entries.iter().filter_map(|e| e.table.as_deref())
+        .any(|sub| sub == target || contains_table(raws, sub, target, visited))
+}
+
+//Replaces the loaded raws with the given file's. On error, the old ones stay.
+pub fn load_raws(path: &str) -> Result<(), RawsError> {
+    let yaml = std::fs::read_to_string(path)?;
+    let master = RawMaster::parse(&yaml)?;
+    *RAWS.lock().unwrap() = master;
+    Ok(())
+}
+
+fn validate(kind: RawKind, raw: &RawEntity) -> Result<(), RawsError> {
+    let invalid = |reason: String| RawsError::Invalid { name: raw.name.clone(), reason };
+
+    if let Some(r) = &raw.renderable {
+        renderable(r).map_err(invalid)?;
+    }
+    if kind == RawKind::Monster && raw.stats.is_none() {
+        return Err(invalid("monsters need stats".to_string()));
+    }
+    Ok(())
+}
+
+fn renderable(raw: &RawRenderable) -> Result<Renderable, String> {
+    let color = |hex: &str| RGB::from_hex(hex).map_err(|_| format!("\"{}\" is not a #RRGGBB color", hex));
+    Ok(Renderable {
+        glyph: match raw.glyph {
+            RawGlyph::Code(code) => code,
+            RawGlyph::Char(c) => to_cp437(c),
+        },
+        fg: color(&raw.fg)?,
+        bg: color(&raw.bg)?,
+        render_order: raw.order,
+    })
+}
+
+//Builds the named entity at (x, y); None if there's no such definition.
+pub fn spawn_named_entity(ecs: &mut World, name: &str, x: i32, y: i32) -> Option<Entity> {
+    let raws = RAWS.lock().unwrap();
+    let (kind, raw) = raws.get(name)?;
+
+    let mut eb = ecs.create_entity()
+        .with(Position { x, y })
+        .with(Name { name: raw.display_name.clone().unwrap_or_else(|| raw.name.clone()) });
+
+    if let Some(r) = &raw.renderable {
+        eb = eb.with(renderable(r).expect("Raws are validated on load."));
+    }
+
+    //What every entity of this kind has.
+    match kind {
+        RawKind::Monster => {
+            eb = eb.with(Creature {})
+                .with(Hostile {})
+                .with(BlocksTile {})
+                .with(BasicAttack::default())
+                .with(Menuable::default())
+                .with(Viewshed {
+                    visible_tiles: Vec::new(),
+                    range: raw.vision.unwrap_or(DEFAULT_MONSTER_VISION),
+                    dirty: true,
+                })
+                .with(Energy { speed: raw.speed.unwrap_or(NORMAL_SPEED), current: 0 });
+        }
+        RawKind::Item => {
+            eb = eb.with(Item {}).with(Menuable::default());
+        }
+        RawKind::Trap | RawKind::Prop => {}
+    }
+
+    //What this one has.
+    if let Some(s) = raw.stats {
+        eb = eb.with(Stats { max_hp: s.hp, hp: s.hp,
+                             max_fp: s.fp, fp: s.fp,
+                             max_mp: s.mp, mp: s.mp,
+                             mind: s.mind, body: s.body, soul: s.soul });
+    }
+    if kind != RawKind::Monster {
+        if let Some(range) = raw.vision {
+            eb = eb.with(Viewshed { visible_tiles: Vec::new(), range, dirty: true });
+        }
+        if raw.blocks_tile {
+            eb = eb.with(BlocksTile {});
+        }
+    }
+    if let Some(menu_name) = &raw.useable {
+        eb = eb.with(Useable { menu_name: menu_name.clone() });
+    }
+    if raw.consumable {
+        eb = eb.with(Consumable {});
+    }
+    if let Some(range) = raw.ranged {
+        eb = eb.with(Ranged { range });
+    }
+    if let Some(h) = raw.heals {
+        eb = eb.with(Heals { duration: h.duration, amount: h.amount });
+    }
+    if let Some(dmg_atoms) = &raw.damage_on_use {
+        eb = eb.with(DamageOnUse { dmg_atoms: dmg_atoms.clone() });
+    }
+    if let Some(radius) = raw.aoe {
+        eb = eb.with(AoE { radius });
+    }
+    if let Some(turns) = raw.confusion {
+        eb = eb.with(Confusion { turns });
+    }
+    if raw.magic_mapper {
+        eb = eb.with(MagicMapper {});
+    }
+    if let Some(dmg) = raw.throwable {
+        eb = eb.with(Throwable { dmg });
+    }
+    if let Some(slot) = raw.equippable {
+        eb = eb.with(Equippable { slot });
+    }
+    if let Some(w) = raw.weapon {
+        eb = eb.with(Weapon { primary: w.primary, secondary: w.secondary, tertiary: w.tertiary });
+    }
+    if let Some(r) = raw.resistances {
+        eb = eb.with(Resistances {
+            bludgeon: DamageAtom::Bludgeon(r.bludgeon),
+            pierce: DamageAtom::Pierce(r.pierce),
+            slash: DamageAtom::Slash(r.slash),
+            thermal: DamageAtom::Thermal(r.thermal),
+        });
+    }
+    if let Some(b) = raw.blocks_attacks {
+        eb = eb.with(BlocksAttacks { chance: b.chance, coverage: b.coverage });
+    }
+    if raw.flammable {
+        eb = eb.with(Flammable {});
+    }
+    if raw.hidden {
+        eb = eb.with(Hidden {});
+    }
+    if let Some(t) = raw.entry_trigger {
+        eb = eb.with(EntryTrigger { repeatable: t.repeatable });
+    }
+    if let Some(d) = raw.door {
+        eb = eb.with(Door { open: false, locked: d.locked })
+            .with(BlocksTile {})
+            .with(BlocksVisibility {});
+    }
+    if raw.key {
+        eb = eb.with(Key {});
+    }
+
+    Some(eb.marked::<SimpleMarker<SerializeMe>>().build())
+}
@@ -0,0 +1,40 @@
+use serde::Deserialize;
+
+//One line of a spawn table: an entity or a nested table, and how likely it is.
+#[derive(Deserialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct RawSpawnEntry {
+    pub name: Option<String>,  //an entity from the raws...
+    pub table: Option<String>, //...or another spawn table; exactly one of the two.
+    pub weight: RawWeight,
+    pub min_depth: Option<i32>,
+    pub max_depth: Option<i32>,
+}
+
+//`weight: 4`, or `weight: { base: 4, per_depth: -1 }` for base + per_depth * depth.
+#[derive(Deserialize, Clone, Copy, Debug)]
+#[serde(untagged)]
+pub enum RawWeight {
+    Fixed(i32),
+    ByDepth {
+        #[serde(default)]
+        base: i32,
+        #[serde(default)]
+        per_depth: i32,
+    },
+}
+
+impl RawWeight {
+    pub fn at_depth(&self, depth: i32) -> i32 {
+        match self {
+            RawWeight::Fixed(weight) => *weight,
+            RawWeight::ByDepth { base, per_depth } => base + per_depth * depth,
+        }
+    }
+}
+
+impl RawSpawnEntry {
+    pub fn in_depth(&self, depth: i32) -> bool {
+        self.min_depth.map_or(true, |min| depth >= min) && self.max_depth.map_or(true, |max| depth <= max)
+    }
+}
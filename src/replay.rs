@@ -0,0 +1,279 @@
+use std::fmt;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Write};
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+use specs::prelude::*;
+
+use super::command::{Command, Commandable};
+use super::player::PlayerController;
+use super::user_input::UserInput;
+use super::{damage_system, Hostile, Map, Player, Position, RunState, Stats, TurnCounter};
+
+/* Run Recording & Replay
+ *
+ * Every batch of Commands the player commits (one PlayerController::ecs_process()
+ * call) is appended to a replay file along with the turn it was committed on.
+ * The file's first line holds the seed the run was started with.
+ *
+ * Because the whole simulation is seeded (see rng.rs) and only advances on its
+ * own between AwaitingInput frames, feeding those batches back through
+ * PlayerController::ecs_process() at each AwaitingInput reproduces the run
+ * frame-for-frame. The turn stored with each batch is checked on playback, so
+ * a replay that drifts reports a Desync instead of quietly playing a different game.
+ *
+ * File format, one JSON object per line, so a crash mid-run still leaves a
+ * readable file behind:
+ *     {"version":1,"seed":1234}
+ *     {"turn":0,"commands":[{"Move":{"dir":"UP"}}]}
+ *     {"turn":1,"commands":["Grab"]}
+ */
+
+pub const REPLAY_VERSION: u32 = 1;
+pub const DEFAULT_REPLAY_PATH: &str = "./lastrun.replay";
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub struct ReplayHeader {
+    pub version: u32,
+    pub seed: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct ReplayEntry {
+    pub turn: u64,
+    pub commands: Vec<Command>,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Replay {
+    pub header: ReplayHeader,
+    pub entries: Vec<ReplayEntry>,
+}
+
+#[derive(Debug)]
+pub enum ReplayError {
+    Io(std::io::Error),
+    Parse { line: usize, error: serde_json::Error },
+    MissingHeader,
+    UnsupportedVersion(u32),
+    Desync { entry: usize, expected_turn: u64, actual_turn: u64 },
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ReplayError::Io(e) => write!(f, "replay file i/o failed: {}", e),
+            ReplayError::Parse { line, error } => write!(f, "replay file line {}: {}", line, error),
+            ReplayError::MissingHeader => write!(f, "replay file has no header line"),
+            ReplayError::UnsupportedVersion(v) => {
+                write!(f, "replay file version {} is not supported (expected {})", v, REPLAY_VERSION)
+            }
+            ReplayError::Desync { entry, expected_turn, actual_turn } => write!(
+                f,
+                "replay desynced at entry {}: recorded on turn {}, but the simulation is on turn {}",
+                entry, expected_turn, actual_turn
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ReplayError {}
+
+impl From<std::io::Error> for ReplayError {
+    fn from(e: std::io::Error) -> Self {
+        ReplayError::Io(e)
+    }
+}
+
+impl Replay {
+    pub fn load(path: &str) -> Result<Replay, ReplayError> {
+        let reader = BufReader::new(File::open(path)?);
+        let mut lines = reader.lines().enumerate();
+
+        let header: ReplayHeader = match lines.next() {
+            Some((_, line)) => serde_json::from_str(&line?)
+                .map_err(|error| ReplayError::Parse { line: 1, error })?,
+            None => return Err(ReplayError::MissingHeader),
+        };
+        if header.version != REPLAY_VERSION {
+            return Err(ReplayError::UnsupportedVersion(header.version));
+        }
+
+        let mut entries = Vec::new();
+        for (i, line) in lines {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let entry = serde_json::from_str(&line)
+                .map_err(|error| ReplayError::Parse { line: i + 1, error })?;
+            entries.push(entry);
+        }
+
+        Ok(Replay { header, entries })
+    }
+}
+
+//ECS resource. While present, PlayerController::ecs_process() records into it.
+pub struct ReplayRecorder {
+    file: File,
+}
+
+impl ReplayRecorder {
+    pub fn create(path: &str, seed: u64) -> std::io::Result<Self> {
+        let mut file = File::create(path)?;
+        let header = ReplayHeader { version: REPLAY_VERSION, seed };
+        writeln!(file, "{}", serde_json::to_string(&header)?)?;
+        file.flush()?;
+        Ok(ReplayRecorder { file })
+    }
+
+    //Flushed on every call; the point is to survive the crash being reported.
+    fn write_entry(&mut self, entry: &ReplayEntry) -> std::io::Result<()> {
+        writeln!(self.file, "{}", serde_json::to_string(entry)?)?;
+        self.file.flush()
+    }
+}
+
+//Called by PlayerController::ecs_process() with the Commands it is about to execute.
+pub fn record(ecs: &World, commands: &[Command]) {
+    if commands.is_empty() {
+        return;
+    }
+    if let Some(mut recorder) = ecs.try_fetch_mut::<ReplayRecorder>() {
+        let entry = ReplayEntry {
+            turn: ecs.fetch::<TurnCounter>().0,
+            commands: commands.to_vec(),
+        };
+        if let Err(e) = recorder.write_entry(&entry) {
+            eprintln!("Unable to write to replay file: {}", e);
+        }
+    }
+}
+
+//Hands recorded Commands to a PlayerController one batch at a time, in place of UserInput.
+pub struct ReplayPlayer {
+    entries: Vec<ReplayEntry>,
+    next: usize,
+}
+
+impl ReplayPlayer {
+    pub fn new(replay: &Replay) -> Self {
+        ReplayPlayer {
+            entries: replay.entries.clone(),
+            next: 0,
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.next >= self.entries.len()
+    }
+
+    //Call at AwaitingInput, before PlayerController::ecs_process(). Queues the next
+    //batch on the controller; returns false once the replay has run out.
+    pub fn feed(&mut self, ecs: &World, controller: &PlayerController) -> Result<bool, ReplayError> {
+        let entry = match self.entries.get(self.next) {
+            Some(entry) => entry,
+            None => return Ok(false),
+        };
+
+        let actual_turn = ecs.fetch::<TurnCounter>().0;
+        if entry.turn != actual_turn {
+            return Err(ReplayError::Desync {
+                entry: self.next,
+                expected_turn: entry.turn,
+                actual_turn,
+            });
+        }
+
+        for cmd in entry.commands.iter() {
+            controller.send(*cmd);
+        }
+        self.next += 1;
+        Ok(true)
+    }
+}
+
+//Summary of where a run ended up; two runs of the same replay print the same thing.
+#[derive(PartialEq, Debug)]
+pub struct FinalState {
+    pub seed: u64,
+    pub entries_played: usize,
+    pub turn: u64,
+    pub runstate: RunState,
+    pub depth: i32,
+    pub player_pos: (i32, i32),
+    pub player_hp: (i32, i32),
+    pub hostiles_remaining: usize,
+}
+
+impl fmt::Display for FinalState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "seed:       {}", self.seed)?;
+        writeln!(f, "entries:    {}", self.entries_played)?;
+        writeln!(f, "turn:       {}", self.turn)?;
+        writeln!(f, "runstate:   {:?}", self.runstate)?;
+        writeln!(f, "depth:      {}", self.depth)?;
+        writeln!(f, "player pos: ({}, {})", self.player_pos.0, self.player_pos.1)?;
+        writeln!(f, "player hp:  {}/{}", self.player_hp.0, self.player_hp.1)?;
+        write!(f, "hostiles:   {}", self.hostiles_remaining)
+    }
+}
+
+//Plays a whole replay without a window, running the same per-frame steps as
+//GameState::tick(), until the replay runs out or the player dies.
+pub fn run_headless(replay: &Replay) -> Result<FinalState, ReplayError> {
+    let mut ecs = super::build_world(replay.header.seed, 1);
+    let user_input = Arc::new(UserInput::new());
+    let controller = PlayerController::new(&user_input);
+    let mut player = ReplayPlayer::new(replay);
+
+    loop {
+        let runstate = *ecs.fetch::<RunState>();
+        match runstate {
+            RunState::AwaitingInput => {
+                if !player.feed(&ecs, &controller)? {
+                    break;
+                }
+                let newrunstate = controller.ecs_process(&mut ecs, RunState::AwaitingInput);
+                *ecs.write_resource::<RunState>() = newrunstate;
+                damage_system::delete_the_dead(&mut ecs);
+            }
+            RunState::PreRun
+            | RunState::PlayerTurn
+            | RunState::GameworldTurn
+            | RunState::MagicMapReveal { .. }
+            | RunState::NextLevel
+            | RunState::PreviousLevel => {
+                super::step(&mut ecs);
+            }
+            _ => break, //GameOver, or a state that needs a window
+        }
+    }
+
+    Ok(final_state(&ecs, replay.header.seed, player.next))
+}
+
+pub fn final_state(ecs: &World, seed: u64, entries_played: usize) -> FinalState {
+    let player_entity = *ecs.fetch::<Entity>();
+    let positions = ecs.read_storage::<Position>();
+    let stats = ecs.read_storage::<Stats>();
+    let players = ecs.read_storage::<Player>();
+    let hostiles = ecs.read_storage::<Hostile>();
+
+    let player_pos = positions.get(player_entity).map_or((0, 0), |p| (p.x, p.y));
+    let player_hp = stats.get(player_entity).map_or((0, 0), |s| (s.hp, s.max_hp));
+    let hostiles_remaining = (&hostiles, !&players).join().count();
+
+    FinalState {
+        seed,
+        entries_played,
+        turn: ecs.fetch::<TurnCounter>().0,
+        runstate: *ecs.fetch::<RunState>(),
+        depth: ecs.fetch::<Map>().depth,
+        player_pos,
+        player_hp,
+        hostiles_remaining,
+    }
+}
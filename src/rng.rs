@@ -0,0 +1,92 @@
+use std::ops::{Deref, DerefMut};
+
+use bracket_lib::prelude::RandomNumberGenerator;
+use specs::prelude::*;
+
+/* Deterministic RNG
+ *
+ * Every random roll in the simulation comes from a stream derived from one u64
+ * seed, so a given seed plus a given input sequence always plays out the same.
+ *
+ * Each subsystem draws from its own stream. That way an extra combat roll does
+ * not shift the layout of the next level, and mapgen for any depth can be
+ * reproduced from the seed alone (see mapgen_rng()).
+ *
+ * Resources inserted by insert_rng_resources():
+ * - GameSeed                 the seed everything below was derived from
+ * - RandomNumberGenerator    the general "world" stream, e.g. spotting Hidden things
+ * - CombatRng                damage rolls, bleed rolls
+ * - AiRng                    monster decision making
+ */
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GameSeed(pub u64);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RngStream {
+    World,
+    MapGen,
+    Combat,
+    AI,
+}
+
+pub struct CombatRng(pub RandomNumberGenerator);
+
+pub struct AiRng(pub RandomNumberGenerator);
+
+impl Deref for CombatRng {
+    type Target = RandomNumberGenerator;
+    fn deref(&self) -> &RandomNumberGenerator {
+        &self.0
+    }
+}
+
+impl DerefMut for CombatRng {
+    fn deref_mut(&mut self) -> &mut RandomNumberGenerator {
+        &mut self.0
+    }
+}
+
+impl Deref for AiRng {
+    type Target = RandomNumberGenerator;
+    fn deref(&self) -> &RandomNumberGenerator {
+        &self.0
+    }
+}
+
+impl DerefMut for AiRng {
+    fn deref_mut(&mut self) -> &mut RandomNumberGenerator {
+        &mut self.0
+    }
+}
+
+//SplitMix64 finalizer; spreads nearby inputs (seed 1 vs seed 2) far apart.
+fn splitmix64(mut x: u64) -> u64 {
+    x = x.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    x ^ (x >> 31)
+}
+
+//Seed for one stream. `salt` distinguishes instances of the same stream, e.g. the depth for MapGen.
+pub fn derive_seed(seed: u64, stream: RngStream, salt: u64) -> u64 {
+    let stream_seed = splitmix64(seed ^ splitmix64(stream as u64 + 1));
+    splitmix64(stream_seed ^ salt)
+}
+
+pub fn stream_rng(seed: u64, stream: RngStream, salt: u64) -> RandomNumberGenerator {
+    RandomNumberGenerator::seeded(derive_seed(seed, stream, salt))
+}
+
+//The mapgen stream for one depth. The same seed always builds the same level at that depth.
+pub fn mapgen_rng(seed: u64, depth: i32) -> RandomNumberGenerator {
+    stream_rng(seed, RngStream::MapGen, depth as u64)
+}
+
+//Inserts (or replaces) every RNG resource, all derived from `seed`.
+pub fn insert_rng_resources(ecs: &mut World, seed: u64) {
+    ecs.insert(GameSeed(seed));
+    ecs.insert(stream_rng(seed, RngStream::World, 0));
+    ecs.insert(CombatRng(stream_rng(seed, RngStream::Combat, 0)));
+    ecs.insert(AiRng(stream_rng(seed, RngStream::AI, 0)));
+}
@@ -1,78 +1,314 @@
 use specs::prelude::*;
-use specs::saveload::{SimpleMarker, SimpleMarkerAllocator, SerializeComponents,
-                      DeserializeComponents, MarkedBuilder};
-use specs::error::NoError;
+use specs::saveload::{SimpleMarker, MarkedBuilder};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use super::component_registry::{deserialize_components, serialize_components};
 use super::components::*;
-use std::fs::File;
-use std::path::Path;
-use std::fs;
-
-macro_rules! serialize_individually {
-    ($ecs:expr, $ser:expr, $data:expr, $( $type:ty),*) => {
-        $(
-        SerializeComponents::<NoError, SimpleMarker<SerializeMe>>::serialize(
-            &( $ecs.read_storage::<$type>(), ),
-            &$data.0,
-            &$data.1,
-            &mut $ser,
-        )
-        .unwrap();
-        )*
+use super::rng::{self, GameSeed};
+use super::dungeon::LevelStore;
+use super::TurnCounter;
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::env;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/* Save format
+ *
+ * Saves live in slots: one file per slot, named after the slot, in the
+ * per-user save directory (see save_dir()).
+ *
+ * A save file is two JSON objects on separate lines. The first is the header:
+ *
+ *   { "format_version": 3, "game_version": "0.1.0",
+ *     "meta": { "character": "Player", "depth": 2, "turn": 140, ... } }
+ *
+ * and it can be read on its own (read_meta()), so listing the slots doesn't
+ * parse every world in them. The second is the body:
+ *
+ *   { "components": { "Position": [...], "Renderable": [...], ... } }
+ *
+ * Each component list is that storage's specs saveload dump. Storages are keyed
+ * by name rather than by position, so a component added later is simply absent
+ * from older saves.
+ * The maps aren't components, and travel inside the SerializationHelper's
+ * entry: the current level's Map and the LevelStore of every other visited one.
+ *
+ * format_version changes whenever the layout or an existing component's
+ * serialized shape does. Every such change appends a migration to MIGRATIONS
+ * that rewrites a save of the previous version (as plain JSON, header and body
+ * merged into one object) into the new shape; loading runs every migration
+ * from the save's version up to SAVE_FORMAT_VERSION.
+ *
+ * Saving writes a temp file and renames it over the slot, so a crash part way
+ * through leaves the previous save in place; the save it replaces is kept
+ * next to it as `<slot>.json.bak`, and loading falls back to that if the slot
+ * itself can't be read.
+ *
+ * Version 1 is the unversioned format from before the envelope: one bare JSON
+ * array per component, back to back, in the order the save list had then.
+ * Version 2 is the header and body as a single object, with no meta.
+ */
+
+//Where the single pre-slot save was written. main() moves it into a slot.
+pub const LEGACY_SAVE_PATH: &str = "./savegame.json";
+//Overrides save_dir(), e.g. for a portable install.
+pub const SAVE_DIR_VAR: &str = "MY_RL_SAVE_DIR";
+const SAVE_EXTENSION: &str = "json";
+
+//MIGRATIONS[n] upgrades a save of format n + 1 to format n + 2.
+type Migration = fn(Value) -> Result<Value, SaveError>;
+const MIGRATIONS: &[Migration] = &[migrate_v1_to_v2, migrate_v2_to_v3];
+
+pub const SAVE_FORMAT_VERSION: u32 = MIGRATIONS.len() as u32 + 1;
+
+//What the load screen shows for a slot.
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
+pub struct SaveMeta {
+    pub character: String,
+    pub depth: i32,
+    pub turn: u64,
+    pub timestamp: u64, //seconds since the Unix epoch
+    pub seed: u64,      //the run's GameSeed; 0 for saves from before format 3
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SaveFile {
+    pub format_version: u32,
+    pub game_version: String, //the build that wrote the save; informational only
+    pub meta: SaveMeta,
+    pub components: BTreeMap<String, Value>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SaveHeader {
+    format_version: u32,
+    game_version: String,
+    meta: SaveMeta,
+}
+
+#[derive(Serialize)]
+struct SaveBody<'a> {
+    components: &'a BTreeMap<String, Value>,
+}
+
+//Present in the World when changing level should autosave the run to its slot
+//(see goto_next_level()). The game inserts it; headless runs and replays don't.
+pub struct Autosave;
+
+pub struct SaveSlot {
+    pub name: String,
+    pub meta: Result<SaveMeta, SaveError>,
+}
+
+#[derive(Debug)]
+pub enum SaveError {
+    Io(std::io::Error),
+    Parse(serde_json::Error),
+    UnsupportedVersion { found: u64 },
+    Migration { from: u32, reason: String },
+    Component { name: &'static str, error: serde_json::Error },
+    Corrupt(String),
+    BadSlotName(String),
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SaveError::Io(e) => write!(f, "save file i/o failed: {}", e),
+            SaveError::Parse(e) => write!(f, "save file is malformed: {}", e),
+            SaveError::UnsupportedVersion { found } => {
+                write!(f, "save format {} is not supported (this build reads 1 to {})", found, SAVE_FORMAT_VERSION)
+            }
+            SaveError::Migration { from, reason } => {
+                write!(f, "unable to upgrade save from format {}: {}", from, reason)
+            }
+            SaveError::Component { name, error } => write!(f, "saved {} data is unreadable: {}", name, error),
+            SaveError::Corrupt(reason) => write!(f, "save file is corrupt: {}", reason),
+            SaveError::BadSlotName(name) => write!(f, "\"{}\" can't be used as a save slot name", name),
+        }
+    }
+}
+
+impl std::error::Error for SaveError {}
+
+impl From<std::io::Error> for SaveError {
+    fn from(e: std::io::Error) -> Self {
+        SaveError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for SaveError {
+    fn from(e: serde_json::Error) -> Self {
+        SaveError::Parse(e)
+    }
+}
+
+//The per-user save directory: $MY_RL_SAVE_DIR if set, else the platform's data
+//directory (XDG_DATA_HOME or ~/.local/share, ~/Library/Application Support,
+//%APPDATA%), else ./saves.
+pub fn save_dir() -> PathBuf {
+    if let Some(dir) = env::var_os(SAVE_DIR_VAR) {
+        return PathBuf::from(dir);
+    }
+    let data_dir = if cfg!(windows) {
+        env::var_os("APPDATA").map(PathBuf::from)
+    } else if cfg!(target_os = "macos") {
+        env::var_os("HOME").map(|home| PathBuf::from(home).join("Library/Application Support"))
+    } else {
+        env::var_os("XDG_DATA_HOME").map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share")))
     };
+    data_dir.map_or_else(|| PathBuf::from("./saves"), |dir| dir.join("my_rl").join("saves"))
 }
 
-macro_rules! deserialize_individually {
-    ($ecs:expr, $de:expr, $data:expr, $( $type:ty),*) => {
-        $(
-        DeserializeComponents::<NoError, _>::deserialize(
-            &mut ( &mut $ecs.write_storage::<$type>(), ),
-            &mut $data.0, // entities
-            &mut $data.1, // marker
-            &mut $data.2, // allocater
-            &mut $de,
-        )
-        .unwrap();
-        )*
+//Slot names become file names, so they're kept to a safe set of characters.
+pub fn slot_path(dir: &Path, slot: &str) -> Result<PathBuf, SaveError> {
+    let valid = !slot.is_empty()
+        && slot.len() <= 64
+        && slot.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == ' ');
+    if !valid {
+        return Err(SaveError::BadSlotName(slot.to_string()));
+    }
+    Ok(dir.join(format!("{}.{}", slot, SAVE_EXTENSION)))
+}
+
+//The slot a run is saved to: one per GameSeed, so saving again overwrites it.
+pub fn run_slot(ecs: &World) -> String {
+    format!("run-{}", ecs.fetch::<GameSeed>().0)
+}
+
+//Every slot in `dir`, most recently saved first. Slots whose header can't be
+//read are listed last, with the error.
+pub fn list_slots_in(dir: &Path) -> Vec<SaveSlot> {
+    let mut slots: Vec<SaveSlot> = match fs::read_dir(dir) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == SAVE_EXTENSION))
+            .filter_map(|path| {
+                let name = path.file_stem()?.to_str()?.to_string();
+                let meta = read_meta(&path).or_else(|e| read_meta(&backup_path(&path)).map_err(|_| e));
+                Some(SaveSlot { name, meta })
+            })
+            .collect(),
+        Err(_) => Vec::new(),
     };
+    slots.sort_by(|a, b| match (&a.meta, &b.meta) {
+        (Ok(a_meta), Ok(b_meta)) => b_meta.timestamp.cmp(&a_meta.timestamp).then_with(|| a.name.cmp(&b.name)),
+        (Ok(_), Err(_)) => Ordering::Less,
+        (Err(_), Ok(_)) => Ordering::Greater,
+        (Err(_), Err(_)) => a.name.cmp(&b.name),
+    });
+    slots
+}
+
+pub fn list_slots() -> Vec<SaveSlot> {
+    list_slots_in(&save_dir())
 }
 
+//True if any slot holds a save that can be loaded.
 pub fn does_save_exist() -> bool {
-    Path::new("./savegame.json").exists()
+    list_slots().iter().any(|slot| slot.meta.is_ok())
+}
+
+pub fn save_game(ecs: &mut World, slot: &str) -> Result<(), SaveError> {
+    let dir = save_dir();
+    let path = slot_path(&dir, slot)?;
+    fs::create_dir_all(&dir)?;
+    save_game_to(ecs, &path)
 }
 
 #[cfg(not(target_arch = "wasm32"))] //macro to prevent saving in web assembly since it's sandboxed anyway.
-pub fn save_game(ecs : &mut World) {
+pub fn save_game_to(ecs: &mut World, path: &Path) -> Result<(), SaveError> {
     // Create helper
     let mapcopy = ecs.get_mut::<super::map::Map>().unwrap().clone();
+    let levelscopy = ecs.get_mut::<LevelStore>().unwrap().clone();
     let savehelper = ecs
         .create_entity()
-        .with(SerializationHelper{ map : mapcopy })
+        .with(SerializationHelper{ map : mapcopy, levels : levelscopy })
         .marked::<SimpleMarker<SerializeMe>>()
         .build();
 
     // Actually serialize
-    {
-        let data = ( ecs.entities(), ecs.read_storage::<SimpleMarker<SerializeMe>>() );
-
-        let writer = File::create("./savegame.json").unwrap();
-        let mut serializer = serde_json::Serializer::new(writer);
-        serialize_individually!(ecs, serializer, data, Position, Renderable, Player, Viewshed, Hostile, 
-            Name, BlocksTile, Stats, MeleeIntent, Item, Consumable, Ranged, DamageOnUse, 
-            AoE, Confusion, Healing, InBackpack, PickUpIntent, UseItemIntent, Particle, Hunger,
-            DropItemIntent, EquipIntent, Equippable, Equipped, Bleeding, Weapon, SerializationHelper,
-            MagicMapper, Useable, ThrowIntent, Throwable, Flammable, Lightsource, Aflame, Hidden,
-            EntryTrigger, JustMoved, Door, BlocksVisibility, Info
-        );
-    }
+    let components = serialize_components(ecs);
 
     // Clean up
     ecs.delete_entity(savehelper).expect("Crash on cleanup");
+
+    let header = SaveHeader {
+        format_version: SAVE_FORMAT_VERSION,
+        game_version: env!("CARGO_PKG_VERSION").to_string(),
+        meta: current_meta(ecs),
+    };
+    let body = SaveBody { components: &components? };
+    write_atomically(path, &format!("{}\n{}\n", serde_json::to_string(&header)?, serde_json::to_string(&body)?))
+}
+
+//The copy of the save that the last save to `path` replaced.
+pub fn backup_path(path: &Path) -> PathBuf {
+    path.with_extension(format!("{}.bak", SAVE_EXTENSION))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn write_atomically(path: &Path, contents: &str) -> Result<(), SaveError> {
+    let temp = path.with_extension(format!("{}.tmp", SAVE_EXTENSION));
+    let result = (|| -> Result<(), SaveError> {
+        let mut file = File::create(&temp)?;
+        file.write_all(contents.as_bytes())?;
+        file.sync_all()?;
+        if path.exists() {
+            fs::copy(path, backup_path(path))?;
+        }
+        fs::rename(&temp, path)?;
+        Ok(())
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(&temp);
+    }
+    result
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn current_meta(ecs: &World) -> SaveMeta {
+    let player = *ecs.fetch::<Entity>();
+    SaveMeta {
+        character: ecs.read_storage::<Name>().get(player).map_or("Unknown".to_string(), |n| n.name.clone()),
+        depth: ecs.fetch::<super::map::Map>().depth,
+        turn: ecs.fetch::<TurnCounter>().0,
+        timestamp: SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs()),
+        seed: ecs.fetch::<GameSeed>().0,
+    }
 }
 
 #[cfg(target_arch = "wasm32")] //fn stub to allow compilation in web assemply.
-pub fn save_game(_ecs: &mut World) {}
+pub fn save_game_to(_ecs: &mut World, _path: &Path) -> Result<(), SaveError> {
+    Ok(())
+}
+
+//Loads the slot, or its backup if the slot can't be loaded. The error is the slot's own.
+pub fn load_game(ecs: &mut World, slot: &str) -> Result<(), SaveError> {
+    let path = slot_path(&save_dir(), slot)?;
+    match load_game_from(ecs, &path) {
+        Err(e) if backup_path(&path).exists() => load_game_from(ecs, &backup_path(&path)).map_err(|_| e),
+        result => result,
+    }
+}
+
+//Reading, upgrading and sanity-checking the save all happen before the world is
+//touched, so those failures leave the current game as it was. A component that
+//fails to deserialize is only found once the world has been cleared; the world
+//is left empty then, and the caller has to start a new game.
+pub fn load_game_from(ecs: &mut World, path: &Path) -> Result<(), SaveError> {
+    let save = parse_save(&fs::read_to_string(path)?)?;
+    for required in ["SerializationHelper", "Player"].iter() {
+        if first_with(&save.components, required).is_none() {
+            return Err(SaveError::Corrupt(format!("no {} entity", required)));
+        }
+    }
 
-pub fn load_game(ecs: &mut World) {
     {
         // Delete everything
         let mut to_delete = Vec::new();
@@ -84,21 +320,7 @@ pub fn load_game(ecs: &mut World) {
         }
     }
 
-    let data = fs::read_to_string("./savegame.json").unwrap();
-    let mut de = serde_json::Deserializer::from_str(&data);
-
-    {
-        let mut d = (&mut ecs.entities(), &mut ecs.write_storage::<SimpleMarker<SerializeMe>>(),
-                     &mut ecs.write_resource::<SimpleMarkerAllocator<SerializeMe>>());
-
-        deserialize_individually!(ecs, de, d, Position, Renderable, Player, Viewshed, Hostile, 
-            Name, BlocksTile, Stats, MeleeIntent, Item, Consumable, Ranged, DamageOnUse, 
-            AoE, Confusion, Healing, InBackpack, PickUpIntent, UseItemIntent, Particle, Hunger,
-            DropItemIntent, EquipIntent, Equippable, Equipped, Bleeding, Weapon, SerializationHelper,
-            MagicMapper, Useable, ThrowIntent, Throwable, Flammable, Lightsource, Aflame, Hidden,
-            EntryTrigger, JustMoved, Door, BlocksVisibility, Info
-        );
-    }
+    deserialize_components(ecs, &save.components)?;
 
     let mut deleteme : Option<Entity> = None;
     {
@@ -110,6 +332,7 @@ pub fn load_game(ecs: &mut World) {
             let mut worldmap = ecs.write_resource::<super::map::Map>();
             *worldmap = h.map.clone();
             worldmap.tile_content = vec![Vec::new(); (worldmap.width * worldmap.height) as usize];
+            *ecs.write_resource::<LevelStore>() = h.levels.clone();
             deleteme = Some(e);
         }
         for (e,_p,pos) in (&entities, &player, &position).join() {
@@ -119,11 +342,144 @@ pub fn load_game(ecs: &mut World) {
             *player_resource = e;
         }
     }
-    ecs.delete_entity(deleteme.unwrap()).expect("Unable to delete helper");
+    match deleteme {
+        Some(helper) => ecs.delete_entity(helper).expect("Unable to delete helper"),
+        None => return Err(SaveError::Corrupt("no map".to_string())),
+    }
+
+    //Levels generated from here on come from the run's seed, as they would have unsaved.
+    rng::insert_rng_resources(ecs, save.meta.seed);
+    ecs.insert(TurnCounter(save.meta.turn));
+    Ok(())
+}
+
+//The slot's header, without reading the world that follows it. Saves from
+//before format 3 have no header of their own and are read (and upgraded) whole.
+pub fn read_meta(path: &Path) -> Result<SaveMeta, SaveError> {
+    let mut first_line = String::new();
+    BufReader::new(File::open(path)?).read_line(&mut first_line)?;
+    if let Ok(header) = serde_json::from_str::<SaveHeader>(&first_line) {
+        if header.format_version == SAVE_FORMAT_VERSION {
+            return Ok(header.meta);
+        }
+    }
+    Ok(parse_save(&fs::read_to_string(path)?)?.meta)
+}
+
+//The first entry in a component list that actually has the component, if any.
+//Every list holds an entry per saved entity, with null where it's absent.
+fn first_with<'a>(components: &'a BTreeMap<String, Value>, name: &str) -> Option<&'a Value> {
+    let list = components.get(name)?.as_array()?;
+    list.iter().find(|entry| !entry["components"][0].is_null())
+}
+
+//Reads a save of any supported format and upgrades it to SAVE_FORMAT_VERSION.
+pub fn parse_save(text: &str) -> Result<SaveFile, SaveError> {
+    let mut values = serde_json::Deserializer::from_str(text)
+        .into_iter::<Value>()
+        .collect::<Result<Vec<Value>, _>>()?;
+
+    let mut save = if !values.is_empty() && values.iter().all(Value::is_object) {
+        //The header and body (or, before format 3, the one envelope) merged into one object.
+        let mut merged = serde_json::Map::new();
+        for value in values.drain(..) {
+            if let Value::Object(fields) = value {
+                merged.extend(fields);
+            }
+        }
+        Value::Object(merged)
+    } else if !values.is_empty() && values.iter().all(Value::is_array) {
+        json!({ "format_version": 1, "game_version": "unknown", "components": values })
+    } else {
+        return Err(SaveError::Corrupt("not a save file".to_string()));
+    };
+
+    let found = save.get("format_version")
+        .and_then(Value::as_u64)
+        .ok_or_else(|| SaveError::Corrupt("no format_version".to_string()))?;
+    if found == 0 || found > SAVE_FORMAT_VERSION as u64 {
+        return Err(SaveError::UnsupportedVersion { found });
+    }
+
+    for (from, migration) in MIGRATIONS.iter().enumerate().skip(found as usize - 1) {
+        save = migration(save)?;
+        save["format_version"] = json!(from as u32 + 2);
+    }
+    Ok(serde_json::from_value(save)?)
+}
+
+//The format 1 save list: the components serialize_individually! wrote, in order.
+const V1_LAYOUT: &[&str] = &["Position", "Renderable", "Player", "Viewshed", "Hostile",
+    "Name", "BlocksTile", "Stats", "MeleeIntent", "Item", "Consumable", "Ranged", "DamageOnUse",
+    "AoE", "Confusion", "Healing", "InBackpack", "PickUpIntent", "UseItemIntent", "Particle", "Hunger",
+    "DropItemIntent", "EquipIntent", "Equippable", "Equipped", "Bleeding", "Weapon", "SerializationHelper",
+    "MagicMapper", "Useable", "ThrowIntent", "Throwable", "Flammable", "Lightsource", "Aflame", "Hidden",
+    "EntryTrigger", "JustMoved", "Door", "BlocksVisibility", "Info"];
+
+//Format 1 -> 2: wrap the bare component arrays in the envelope, keyed by name.
+fn migrate_v1_to_v2(mut save: Value) -> Result<Value, SaveError> {
+    let arrays = match save["components"].take() {
+        Value::Array(arrays) => arrays,
+        _ => return Err(SaveError::Migration { from: 1, reason: "no component lists".to_string() }),
+    };
+    if arrays.len() != V1_LAYOUT.len() {
+        return Err(SaveError::Migration {
+            from: 1,
+            reason: format!("{} component lists, expected {}", arrays.len(), V1_LAYOUT.len()),
+        });
+    }
+
+    let components: serde_json::Map<String, Value> = V1_LAYOUT.iter()
+        .map(|name| name.to_string())
+        .zip(arrays)
+        .collect();
+    save["components"] = Value::Object(components);
+    Ok(save)
+}
+
+//Format 2 -> 3: add the meta header, from what the save itself records.
+fn migrate_v2_to_v3(mut save: Value) -> Result<Value, SaveError> {
+    let components: BTreeMap<String, Value> = serde_json::from_value(save["components"].clone())
+        .map_err(|e| SaveError::Migration { from: 2, reason: e.to_string() })?;
+
+    let player_marker = first_with(&components, "Player").map(|entry| &entry["marker"]);
+    let character = components.get("Name")
+        .and_then(Value::as_array)
+        .and_then(|names| names.iter().find(|entry| Some(&entry["marker"]) == player_marker))
+        .and_then(|entry| entry["components"][0]["name"].as_str())
+        .unwrap_or("Unknown");
+    let depth = first_with(&components, "SerializationHelper")
+        .and_then(|entry| entry["components"][0]["map"]["depth"].as_i64())
+        .unwrap_or(1);
+
+    save["meta"] = json!({ "character": character, "depth": depth, "turn": 0, "timestamp": 0, "seed": 0 });
+    Ok(save)
+}
+
+//Removes the slot and its backup.
+pub fn delete_save(slot: &str) -> Result<(), SaveError> {
+    let path = slot_path(&save_dir(), slot)?;
+    for file in [backup_path(&path), path].iter() {
+        if file.exists() {
+            fs::remove_file(file)?;
+        }
+    }
+    Ok(())
 }
 
-pub fn delete_save() {
-    if Path::new("./savegame.json").exists() {
-        std::fs::remove_file("./savegame.json").expect("Unable to delete file.");
+//Moves the save from before slots existed into the slot "savegame", unless that's taken.
+pub fn import_legacy_save() -> Result<(), SaveError> {
+    let legacy = Path::new(LEGACY_SAVE_PATH);
+    if !legacy.exists() {
+        return Ok(());
+    }
+    let dir = save_dir();
+    let target = slot_path(&dir, "savegame")?;
+    if target.exists() {
+        return Ok(());
     }
+    fs::create_dir_all(&dir)?;
+    fs::copy(legacy, &target)?;
+    fs::remove_file(legacy)?;
+    Ok(())
 }
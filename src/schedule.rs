@@ -0,0 +1,171 @@
+use std::cell::RefCell;
+
+use specs::prelude::*;
+
+use super::bleed_system::BleedSystem;
+use super::c_menu_system::ContextMenuSystem;
+use super::damage_system::DamageSystem;
+use super::door_system::DoorSystem;
+use super::effects::EffectSystem;
+use super::equip_system::EquipSystem;
+use super::events;
+use super::healing_system::HealingSystem;
+use super::hostile_ai_system::HostileAI;
+use super::hunger_system::HungerSystem;
+use super::initiative_system::InitiativeSystem;
+use super::inventory_system::{ItemCollectionSystem, ItemDropSystem, ItemUseSystem};
+use super::light_system::LightSystem;
+use super::map_indexing_system::MapIndexingSystem;
+use super::melee_combat_system::MeleeCombatSystem;
+use super::particle_system::ParticleSpawnSystem;
+use super::throw_system::ThrowSystem;
+use super::trigger_system::TriggerSystem;
+use super::visibility_system::VisibilitySystem;
+
+/* System Schedule
+ *
+ * THE place to look for which systems run when, and in what order.
+ *
+ * Each turn is a specs Dispatcher split into stages by barriers; every system
+ * in a stage sees everything the previous stages wrote. Within a stage, specs
+ * runs systems that don't touch the same storages in parallel, and orders the
+ * rest deterministically.
+ *
+ *               PreRun          PlayerTurn        GameworldTurn (once per tick)
+ * Time          -               -                 initiative
+ * Intent        context_menu    context_menu      context_menu, hostile_ai
+ * Actions       -               item_use, item_drop, pick_up, equip, triggers, doors
+ * Attacks       -               melee, throw      melee, throw
+ * Effects       -               effects           effects
+ * Status        light           light             light, healing, hunger, bleed
+ * Damage        -               damage            damage
+ * Indexing      map_indexing (always)
+ * Visibility    visibility (always)
+ *
+ * After every dispatch, queued GameEvents are published to their subscribers
+ * (see events.rs), then the particles requested along the way are spawned.
+ *
+ * The PlayerTurn resolves the player's action. The GameworldTurn is one tick of
+ * game time, and is run repeatedly until the player can act again; see
+ * initiative_system.rs. Healing, hunger and bleeding tick once per turn of game
+ * time.
+ */
+
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum Turn {
+    PreRun,
+    PlayerTurn,
+    GameworldTurn,
+}
+
+fn build_dispatcher(turn: Turn) -> Dispatcher<'static, 'static> {
+    let acting = turn != Turn::PreRun;
+    let gameworld = turn == Turn::GameworldTurn;
+    let mut builder = DispatcherBuilder::new();
+
+    //Time: one tick passes, and everyone gains energy.
+    if gameworld {
+        builder.add(InitiativeSystem {}, "initiative", &[]);
+        builder.add_barrier();
+    }
+
+    //Intent: decide what everyone wants to do.
+    builder.add(ContextMenuSystem {}, "context_menu", &[]);
+    if gameworld {
+        builder.add(HostileAI {}, "hostile_ai", &[]);
+    }
+    builder.add_barrier();
+
+    //Actions: resolve non-violent intents, and whatever was stepped on.
+    if acting {
+        builder.add(ItemUseSystem {}, "item_use", &[]);
+        builder.add(ItemDropSystem {}, "item_drop", &[]);
+        builder.add(ItemCollectionSystem {}, "pick_up", &[]);
+        builder.add(EquipSystem {}, "equip", &["item_drop"]);
+        builder.add(TriggerSystem {}, "triggers", &[]);
+        builder.add(DoorSystem {}, "doors", &[]);
+        builder.add_barrier();
+
+        //Attacks: with equipment settled, queue damage.
+        builder.add(MeleeCombatSystem {}, "melee", &[]);
+        builder.add(ThrowSystem {}, "throw", &[]);
+        builder.add_barrier();
+
+        //Effects: resolve everything the stages above queued (see effects.rs).
+        builder.add(EffectSystem {}, "effects", &[]);
+        builder.add_barrier();
+    }
+
+    //Status: lights, heal-over-time and damage-over-time.
+    builder.add(LightSystem {}, "light", &[]);
+    if gameworld {
+        builder.add(HealingSystem {}, "healing", &[]);
+        builder.add(HungerSystem {}, "hunger", &[]);
+        builder.add(BleedSystem {}, "bleed", &[]);
+    }
+    builder.add_barrier();
+
+    //Damage: apply everything queued above.
+    if acting {
+        builder.add(DamageSystem {}, "damage", &[]);
+        builder.add_barrier();
+    }
+
+    //Indexing: rebuild the tile index.
+    builder.add(MapIndexingSystem {}, "map_indexing", &[]);
+    builder.add_barrier();
+
+    //Visibility: recompute viewsheds against the fresh index.
+    builder.add(VisibilitySystem {}, "visibility", &[]);
+
+    builder.build()
+}
+
+pub struct Schedule {
+    pre_run: Dispatcher<'static, 'static>,
+    player_turn: Dispatcher<'static, 'static>,
+    gameworld_turn: Dispatcher<'static, 'static>,
+}
+
+impl Schedule {
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        Schedule {
+            pre_run: build_dispatcher(Turn::PreRun),
+            player_turn: build_dispatcher(Turn::PlayerTurn),
+            gameworld_turn: build_dispatcher(Turn::GameworldTurn),
+        }
+    }
+
+    pub fn run(&mut self, ecs: &mut World, turn: Turn) {
+        if !ecs.has_value::<SetUp>() {
+            self.pre_run.setup(ecs);
+            self.player_turn.setup(ecs);
+            self.gameworld_turn.setup(ecs);
+            ecs.insert(SetUp);
+        }
+        match turn {
+            Turn::PreRun => self.pre_run.dispatch(ecs),
+            Turn::PlayerTurn => self.player_turn.dispatch(ecs),
+            Turn::GameworldTurn => self.gameworld_turn.dispatch(ecs),
+        }
+        events::publish(ecs);
+        ParticleSpawnSystem {}.run_now(ecs);
+        ecs.maintain();
+    }
+}
+
+//Marks a World the dispatchers have already been set up against.
+struct SetUp;
+
+//Dispatchers own non-Send parts, so they can't live in the World or a lazy_static;
+//each thread that runs the simulation builds its own Schedule once. One thread can
+//run several Worlds (the tests build one each), so run() sets the dispatchers up
+//against every World the first time it sees it.
+thread_local! {
+    static SCHEDULE: RefCell<Schedule> = RefCell::new(Schedule::new());
+}
+
+pub fn run(ecs: &mut World, turn: Turn) {
+    SCHEDULE.with(|schedule| schedule.borrow_mut().run(ecs, turn));
+}
@@ -1,41 +1,42 @@
 use specs::prelude::*;
-use super::{gui::gamelog, ThrowIntent, Position, InBackpack, Equipped, Name, Weapon,
-            DamageQueue, BasicAttack, Throwable, Map};
+use super::{ThrowIntent, Position, InBackpack, Equipped, Weapon, events::{EventQueue, GameEvent},
+            effects::{EffectQueue, EffectType, Targets}, BasicAttack, Throwable, Map, Energy, initiative_system::THROW_COST};
 
 pub struct ThrowSystem {}
 
 impl<'a> System<'a> for ThrowSystem {
     #[allow(clippy::type_complexity)]
     type SystemData = ( Entities<'a>,
+                        WriteExpect<'a, EventQueue>,
+                        WriteExpect<'a, EffectQueue>,
                         WriteStorage<'a, ThrowIntent>,
                         WriteStorage<'a, Position>,
                         WriteStorage<'a, InBackpack>,
                         WriteStorage<'a, BasicAttack>,
-                        WriteStorage<'a, DamageQueue>,
                         WriteStorage<'a, Equipped>,
                         ReadStorage<'a, Throwable>,
                         ReadStorage<'a, Weapon>,
-                        ReadStorage<'a, Name>,
                         ReadExpect<'a, Map>,
+                        WriteStorage<'a, Energy>,
                       );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (entities, mut throw_intents, mut positions, mut in_backpack, mut basic_attacks,
-             mut damage_queue, mut equipped_storage, throwables, weapons, names, map) = data;
-
-        let mut logger = gamelog::Logger::new();
+        let (entities, mut events, mut effects, mut throw_intents, mut positions, mut in_backpack,
+             mut basic_attacks, mut equipped_storage, throwables, weapons, map, mut energies) = data;
 
         for (ent, throw_intent) in (&entities, &mut throw_intents).join() {
 
             if let Some(target) = throw_intent.target {
+                Energy::spend(&mut energies, ent, THROW_COST);
                 let throwable = throwables.get(throw_intent.item);
                 let dmg = throwable.unwrap().dmg;
-                let target_ent = None;
+                let mut target_ent = None;
 
                 let idx = map.xy_idx(target.x, target.y);
                 if !map.tile_content[idx].is_empty() {
-                    let target_ent = Some(map.tile_content[idx][0]);
-                    DamageQueue::queue_damage(&mut damage_queue, target_ent.unwrap(), dmg);
+                    target_ent = Some(map.tile_content[idx][0]);
+                    effects.push(Some(ent), EffectType::Damage { atoms: vec![dmg] },
+                        Targets::Entities { list: vec![map.tile_content[idx][0]] });
                 }
                 
                 if let Some(pos) = positions.get_mut(throw_intent.item) {
@@ -60,20 +61,10 @@ impl<'a> System<'a> for ThrowSystem {
                     }
                 }
 
-                if let Some(target_ent) = target_ent {
-                    logger.append(format!("{} threw a {} at {}.",
-                            names.get(ent).unwrap().name,
-                            names.get(throw_intent.item).unwrap().name,
-                            names.get(target_ent).unwrap().name));
-                } else {
-                    logger.append(format!("{} threw a {}.",
-                            names.get(ent).unwrap().name,
-                            names.get(throw_intent.item).unwrap().name));
-                }
+                events.push(GameEvent::Thrown { item: throw_intent.item, thrower: ent, target: target_ent });
             }
         }
 
         throw_intents.clear();
-        logger.log();
     }
 }
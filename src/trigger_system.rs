@@ -1,26 +1,23 @@
 use specs::prelude::*;
-use super::{Position, JustMoved, EntryTrigger, Hidden, Map, Name, gui::gamelog, DamageOnUse,
-            DamageQueue, };
+use super::{Position, JustMoved, EntryTrigger, Hidden, Map, events::{EventQueue, GameEvent},
+            effects::{EffectQueue, EffectType, Targets}};
 
 pub struct TriggerSystem {}
 
 impl<'a> System<'a> for TriggerSystem {
     type SystemData = ( Entities<'a>,
                         ReadExpect<'a, Map>,
+                        WriteExpect<'a, EventQueue>,
+                        WriteExpect<'a, EffectQueue>,
                         WriteStorage<'a, JustMoved>,
                         WriteStorage<'a, Hidden>,
-                        WriteStorage<'a, DamageQueue>,
                         WriteStorage<'a, EntryTrigger>,
                         ReadStorage<'a, Position>,
-                        ReadStorage<'a, DamageOnUse>,
-                        ReadStorage<'a, Name>,
                       );
     
     fn run(&mut self, data: Self::SystemData) {
-       let (entities, map, mut moved_storage, mut hidden_storage, mut damage_queue,
-            mut triggers, positions, damage_on_use, names) = data;
-
-       let mut logger = gamelog::Logger::new();
+       let (entities, map, mut events, mut effects, mut moved_storage, mut hidden_storage,
+            mut triggers, positions) = data;
 
        for (ent, pos, _ms) in (&entities, &positions, &mut moved_storage).join() {
             let idx = map.xy_idx(pos.x, pos.y);
@@ -30,17 +27,11 @@ impl<'a> System<'a> for TriggerSystem {
                     match trigger {
                         None => {},
                         Some(trigger) => {
-                            let name = names.get(*entity);
-                            if let Some(name) = name {
-                                logger.append(format!("Triggered a {}!", &name.name));
-                            }
+                            events.push(GameEvent::TrapTriggered { trap: *entity, victim: ent });
 
-                            let damage = damage_on_use.get(*entity);
-                            if let Some(d) = damage {
-                                for atom in d.dmg_atoms.iter() {
-                                    DamageQueue::queue_damage(&mut damage_queue, ent, *atom);
-                                }
-                            }
+                            //The trap goes off on whoever stepped on it.
+                            effects.push(None, EffectType::Use { item: *entity },
+                                Targets::Entities { list: vec![ent] });
 
                             if !trigger.repeatable {
                                 triggers.remove(*entity).expect("Unable to remove EntryTrigger component.");
@@ -54,6 +45,5 @@ impl<'a> System<'a> for TriggerSystem {
        }
 
        moved_storage.clear();
-       logger.log();
     }
 }
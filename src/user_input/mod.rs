@@ -45,6 +45,9 @@ pub enum InputEvent {
     TOOLTIPS,
     ESC,
     ENTER,
+    STAIRS,
+    CLOSE,
+    SEARCH,
 }
 
 impl UserInput {
@@ -98,6 +101,9 @@ impl UserInput {
                 VirtualKeyCode::T => new_input = Some(InputEvent::TOOLTIPS),
                 VirtualKeyCode::Escape => new_input = Some(InputEvent::ESC),
                 VirtualKeyCode::Return => new_input = Some(InputEvent::ENTER),
+                VirtualKeyCode::Period => new_input = Some(InputEvent::STAIRS),
+                VirtualKeyCode::C => new_input = Some(InputEvent::CLOSE),
+                VirtualKeyCode::F => new_input = Some(InputEvent::SEARCH),
                 _ => {}
             }
         };
@@ -1,5 +1,5 @@
 use specs::prelude::*;
-use super::{Viewshed, Position, Map, Player, Name, Hidden, BlocksVisibility, gui::gamelog};
+use super::{Viewshed, Position, Map, Player, Hidden, BlocksVisibility, events::{EventQueue, GameEvent}};
 use bracket_lib::prelude::{field_of_view, Point};
 
 pub struct VisibilitySystem {}
@@ -7,20 +7,18 @@ pub struct VisibilitySystem {}
 impl<'a> System<'a> for VisibilitySystem {
     type SystemData = ( WriteExpect<'a, Map>,
                         WriteExpect<'a, bracket_lib::prelude::RandomNumberGenerator>,
+                        WriteExpect<'a, EventQueue>,
                         Entities<'a>,
                         WriteStorage<'a, Viewshed>,
                         WriteStorage<'a, Hidden>,
                         ReadStorage<'a, Position>,
                         ReadStorage<'a, Player>,
-                        ReadStorage<'a, Name>,
                         ReadStorage<'a, BlocksVisibility>,
                       );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (mut map, mut rng, entities, mut viewshed, mut hidden_storage,
-             pos, player, names, blocks_vis) = data;
-
-        let mut logger = gamelog::Logger::new();
+        let (mut map, mut rng, mut events, entities, mut viewshed, mut hidden_storage,
+             pos, player, blocks_vis) = data;
 
         map.view_blocked.clear();
         for (pos, _) in (&pos, &blocks_vis).join() {
@@ -32,7 +30,7 @@ impl<'a> System<'a> for VisibilitySystem {
             if viewshed.dirty {
                 viewshed.dirty = false;
                 viewshed.visible_tiles = field_of_view(Point::new(pos.x, pos.y),
-                                                        viewshed.range, &*map);
+                                                        map.biome.sight_range(viewshed.range), &*map);
                 viewshed.visible_tiles.retain(|p| p.x >= 0 && p.x < map.width - 1 &&
                                                   p.y >= 0 && p.y < map.height - 1 );
                 
@@ -64,10 +62,7 @@ impl<'a> System<'a> for VisibilitySystem {
                             let hidden = hidden_storage.get(*e);
                             if let Some(_) = hidden {
                                 if rng.roll_dice(1, 50) == 1 {
-                                    let name = names.get(*e);
-                                    if let Some(name) = name {
-                                        logger.append(format!("{} spotted!", &name.name));
-                                    }
+                                    events.push(GameEvent::Spotted { entity: *e });
                                     hidden_storage.remove(*e);
                                 }
                             }
@@ -76,7 +71,5 @@ impl<'a> System<'a> for VisibilitySystem {
                 }
             }
         }
-
-        logger.log();
     }
 }
@@ -0,0 +1,65 @@
+use bracket_lib::prelude::{RandomNumberGenerator, RGB};
+use specs::prelude::*;
+
+use my_rl::map_builders::{random_builder, themed_builder, PrefabLibrary, RecipeBook};
+use my_rl::{Biome, Map, TileType};
+
+fn biome(extra: &str) -> String {
+    format!("recipes:\n  plain:\n    starter: simple_map\nbiomes:\n  marsh:\n    recipes: [{{ recipe: plain, weight: 1 }}]\n{}depths:\n  random: [{{ biome: marsh, weight: 1 }}]\n", extra)
+}
+
+#[test]
+fn every_depth_has_a_builtin_biome() {
+    let book = RecipeBook::builtin();
+    let names: Vec<&str> = book.biomes().map(|b| b.name.as_str()).collect();
+    assert_eq!(names, vec!["ancient_ruins", "flooded_caverns", "goblin_warrens"]);
+    let mut rng = RandomNumberGenerator::seeded(1234);
+    for depth in 1..10 {
+        let biome = book.biome_for(depth, &mut rng).unwrap();
+        assert!(book.recipe_in(&biome, depth, &mut rng).is_some(), "{} at depth {}", biome, depth);
+    }
+
+    //The first depth is pinned, and the game's level carries its biome.
+    let ecs = my_rl::build_world(1234, 1);
+    assert_eq!(ecs.fetch::<Map>().biome.name, "ancient_ruins");
+    let chain = random_builder(1, &mut RandomNumberGenerator::seeded(1), 64, 64);
+    assert_eq!(chain.build_data.map.biome.spawn_table, "ruins");
+}
+
+#[test]
+fn a_biome_themes_the_level_it_builds() {
+    let mut rng = RandomNumberGenerator::seeded(1234);
+    let mut chain = themed_builder("flooded_caverns", None, 4, &mut rng, 64, 64).unwrap();
+    chain.build_map(&mut rng);
+    let biome = &chain.build_data.map.biome;
+    assert_eq!((biome.name.as_str(), biome.spawn_table.as_str()), ("flooded_caverns", "caverns"));
+    assert_eq!(biome.colour(TileType::Wall), RGB::from_hex("#3F6F8F").unwrap());
+    assert_eq!(biome.colour(TileType::Lava), TileType::Lava.glyph().1);
+    assert_eq!(biome.sight_range(5), 3);
+    assert_eq!(biome.sight_range(1), 1);
+    assert!(chain.steps.contains(&"terrain_features: flooded".to_string()));
+
+    //A recipe from outside the biome still gets the biome's theme.
+    let chain = themed_builder("goblin_warrens", Some("bsp_interior"), 4, &mut rng, 64, 64).unwrap();
+    assert_eq!(chain.steps[0], "bsp_interior");
+    assert_eq!(chain.build_data.map.biome.name, "goblin_warrens");
+    assert!(themed_builder("no_such_biome", None, 4, &mut rng, 64, 64).is_none());
+    assert_eq!(Biome::default().sight_range(5), 5);
+}
+
+#[test]
+fn bad_biomes_are_rejected() {
+    let prefabs = PrefabLibrary::builtin();
+    assert!(RecipeBook::parse(&biome(""), &prefabs).is_ok());
+    let mistakes = [
+        "    palette: { Wall: \"#12345\" }\n",
+        "    palette: { Lawn: \"#123456\" }\n",
+        "    ambient_light: 0\n",
+        "    ambient_light: 1.5\n",
+        "  empty:\n    recipes: []\n",
+        "  lost:\n    recipes: [{ recipe: no_such_recipe, weight: 1 }]\n",
+    ];
+    for extra in mistakes.iter() {
+        assert!(RecipeBook::parse(&biome(extra), &prefabs).is_err(), "{}", extra);
+    }
+}
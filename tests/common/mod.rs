@@ -0,0 +1,41 @@
+use specs::prelude::*;
+
+use bracket_lib::prelude::Point;
+use my_rl::{BasicAttack, Energy, Hostile, Map, Name, Position, Stats, Viewshed};
+
+//A depth 1 world, past its PreRun, with every hostile removed and one "Test Monster"
+//of the given speed standing next to the player, who has 50 hp. Returns the monster.
+pub fn world_with_adjacent_monster(speed: i32) -> (World, Entity) {
+    let mut ecs = my_rl::build_world(1234, 1);
+    my_rl::step(&mut ecs); //PreRun
+
+    let hostiles: Vec<Entity> = (&ecs.entities(), &ecs.read_storage::<Hostile>())
+        .join()
+        .map(|(e, _)| e)
+        .collect();
+    ecs.delete_entities(&hostiles).unwrap();
+
+    let player = *ecs.fetch::<Entity>();
+    let player_pos = *ecs.fetch::<Point>();
+    ecs.write_storage::<Stats>().get_mut(player).unwrap().hp = 50;
+
+    let (x, y) = {
+        let map = ecs.fetch::<Map>();
+        let neighbours = [(-1, 0), (1, 0), (0, -1), (0, 1), (-1, -1), (1, -1), (-1, 1), (1, 1)];
+        neighbours.iter()
+            .map(|(dx, dy)| (player_pos.x + dx, player_pos.y + dy))
+            .find(|&(x, y)| !map.blocked[map.xy_idx(x, y)])
+            .expect("no open tile next to the player")
+    };
+    let monster = ecs.create_entity()
+        .with(Position { x, y })
+        .with(Hostile {})
+        .with(Name { name: "Test Monster".to_string() })
+        .with(Viewshed { visible_tiles: Vec::new(), range: 5, dirty: true })
+        .with(Stats { max_hp: 4, hp: 4, max_fp: 8, fp: 8, max_mp: 2, mp: 2, mind: 1, body: 1, soul: 1 })
+        .with(BasicAttack::default())
+        .with(Energy { speed, current: 0 })
+        .build();
+
+    (ecs, monster)
+}
@@ -0,0 +1,75 @@
+use std::collections::BTreeSet;
+
+use specs::prelude::*;
+
+use my_rl::component_registry::{SAVED_COMPONENTS, TRANSIENT_COMPONENTS};
+use my_rl::saveload_system;
+use my_rl::{BasicAttack, DamageAtom, Resistances};
+
+//Every `#[derive(.. Component ..)]` struct in components.rs, skipping commented-out blocks.
+fn declared_components() -> Vec<String> {
+    let source = include_str!("../src/components.rs");
+    let mut live = String::new();
+    let mut rest = source;
+    while let Some(start) = rest.find("/*") {
+        live.push_str(&rest[..start]);
+        rest = rest[start..].find("*/").map_or("", |end| &rest[start + end + 2..]);
+    }
+    live.push_str(rest);
+
+    let mut declared = Vec::new();
+    let mut derives_component = false;
+    for line in live.lines().map(str::trim) {
+        if line.starts_with("#[derive(") {
+            derives_component = line.contains("Component");
+        } else if let Some(decl) = line.strip_prefix("pub struct ") {
+            if derives_component {
+                let name: String = decl.chars().take_while(|c| c.is_alphanumeric() || *c == '_').collect();
+                declared.push(name);
+            }
+            derives_component = false;
+        }
+    }
+    declared
+}
+
+#[test]
+fn every_component_is_saved_or_marked_transient() {
+    let saved: BTreeSet<&str> = SAVED_COMPONENTS.iter().copied().collect();
+    let transient: BTreeSet<&str> = TRANSIENT_COMPONENTS.iter().copied().collect();
+    assert_eq!(saved.len(), SAVED_COMPONENTS.len(), "a component is listed twice");
+    assert!(saved.is_disjoint(&transient), "a component is both saved and transient");
+
+    let declared = declared_components();
+    assert!(declared.len() > 40, "components.rs scan found only {:?}", declared);
+    for name in declared.iter() {
+        assert!(saved.contains(name.as_str()) || transient.contains(name.as_str()),
+            "{} is neither saved nor transient in component_registry.rs", name);
+    }
+}
+
+#[test]
+fn saves_hold_every_saved_component() {
+    let path = std::env::temp_dir().join(format!("my_rl_registry_{}.json", std::process::id()));
+    let mut ecs = my_rl::build_world(1234, 1);
+    my_rl::step(&mut ecs); //PreRun
+    {
+        let player = *ecs.fetch::<Entity>();
+        ecs.write_storage::<Resistances>().get_mut(player).unwrap().slash = DamageAtom::Slash(2);
+    }
+    saveload_system::save_game_to(&mut ecs, &path).unwrap();
+
+    let save = saveload_system::parse_save(&std::fs::read_to_string(&path).unwrap()).unwrap();
+    let keys: Vec<&str> = save.components.keys().map(String::as_str).collect();
+    let mut expected = SAVED_COMPONENTS.to_vec();
+    expected.sort_unstable();
+    assert_eq!(keys, expected);
+
+    //Resistances and BasicAttack used to be dropped by save/load.
+    let mut loaded = my_rl::build_world(99, 1);
+    saveload_system::load_game_from(&mut loaded, &path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    let player = *loaded.fetch::<Entity>();
+    assert_eq!(loaded.read_storage::<Resistances>().get(player).unwrap().slash, DamageAtom::Slash(2));
+    assert!(loaded.read_storage::<BasicAttack>().get(player).is_some());
+}
@@ -0,0 +1,145 @@
+use bracket_lib::prelude::Point;
+use specs::prelude::*;
+
+use my_rl::dijkstra::{DijkstraMap, Flow, FlowFields, UNREACHABLE};
+use my_rl::{raws, Hostile, Map, Player, Position, Stats, TileType, Viewshed};
+
+//An 8x8 room with floor inside a wall, ready for pathing.
+fn open_map() -> Map {
+    let mut map = Map::new(1, 8, 8);
+    for x in 1..7 {
+        for y in 1..7 {
+            let idx = map.xy_idx(x, y);
+            map.tiles[idx] = TileType::Floor;
+        }
+    }
+    map.populate_blocked();
+    map
+}
+
+#[test]
+fn costs_come_from_the_nearest_source_weighted_by_terrain() {
+    let mut map = open_map();
+    let mud = map.xy_idx(2, 1);
+    map.tiles[mud] = TileType::Mud;
+    let sources = [map.xy_idx(1, 1), map.xy_idx(6, 6)];
+    let dijkstra = DijkstraMap::new(&map, &sources, UNREACHABLE);
+
+    assert_eq!(dijkstra.cost(sources[1]), Some(0.0));
+    assert_eq!(dijkstra.cost(map.xy_idx(6, 5)), Some(1.0));
+    assert_eq!(dijkstra.cost(mud), Some(2.0));
+    assert_eq!(dijkstra.cost(map.xy_idx(0, 0)), None);
+    assert_eq!(dijkstra.farthest(|_| true).map(|idx| dijkstra.cost(idx).unwrap()), Some(5.0));
+
+    let near = DijkstraMap::new(&map, &sources, 1.0);
+    assert!(near.reachable(map.xy_idx(5, 6)) && !near.reachable(map.xy_idx(4, 6)));
+}
+
+#[test]
+fn blocked_tiles_are_walked_around_but_closed_doors_are_not() {
+    let mut map = open_map();
+    for y in 1..7 {
+        let idx = map.xy_idx(4, y);
+        map.blocked[idx] = true;
+    }
+    let (start, far_side) = (map.xy_idx(2, 3), map.xy_idx(6, 3));
+    assert!(!DijkstraMap::new(&map, &[start], UNREACHABLE).reachable(far_side));
+
+    map.closed_doors.insert(map.xy_idx(4, 3));
+    assert!(DijkstraMap::new(&map, &[start], UNREACHABLE).reachable(far_side));
+
+    //over() goes by what it's told, here the terrain alone.
+    let terrain = DijkstraMap::over(&map, &[start], UNREACHABLE, |idx| map.tiles[idx].is_walkable());
+    assert_eq!(terrain.cost(far_side), Some(4.0));
+}
+
+#[test]
+fn downhill_closes_in_and_the_flee_map_gets_away() {
+    let map = open_map();
+    let target = map.xy_idx(1, 3);
+    let toward = DijkstraMap::new(&map, &[target], UNREACHABLE);
+    let mut idx = map.xy_idx(6, 3);
+    for _ in 0..5 {
+        idx = toward.downhill(&map, idx).unwrap();
+    }
+    assert_eq!(idx, target);
+    assert_eq!(toward.downhill(&map, target), None);
+
+    let away = toward.flee(&map);
+    let step = away.downhill(&map, map.xy_idx(2, 3)).unwrap();
+    assert!(toward.cost(step).unwrap() > 1.0);
+}
+
+#[test]
+fn flow_fields_are_kept_until_the_terrain_changes() {
+    let mut map = open_map();
+    let mut fields = FlowFields::default();
+    let (source, corner) = (map.xy_idx(1, 1), map.xy_idx(6, 1));
+    assert_eq!(fields.get(Flow::ToPlayer, &map, &[source]).cost(corner), Some(5.0));
+
+    //A wall down the middle with one gap, low down.
+    for y in 1..6 {
+        let idx = map.xy_idx(3, y);
+        map.tiles[idx] = TileType::Wall;
+    }
+    map.populate_blocked();
+    assert_eq!(fields.get(Flow::ToPlayer, &map, &[source]).cost(corner), Some(5.0));
+    map.terrain_changed();
+    let cost = fields.get(Flow::ToPlayer, &map, &[source]).cost(corner).unwrap();
+    assert!(cost > 5.0);
+    assert_eq!(fields.get(Flow::ToPlayer, &map, &[corner]).cost(corner), Some(0.0));
+}
+
+#[test]
+fn creatures_are_stepped_around_not_walled_off() {
+    let mut map = open_map();
+    let (target, start) = (map.xy_idx(1, 3), map.xy_idx(3, 3));
+    let in_the_way = map.xy_idx(2, 3);
+    map.blocked[in_the_way] = true;
+    map.occupied.insert(in_the_way);
+
+    let toward = DijkstraMap::new(&map, &[target], UNREACHABLE);
+    assert_eq!(toward.cost(start), Some(2.0));
+    let step = toward.downhill(&map, start).unwrap();
+    assert_ne!(step, in_the_way);
+    assert!(toward.cost(step).unwrap() < 2.0);
+}
+
+#[test]
+fn badly_hurt_monsters_run_away() {
+    let mut ecs = my_rl::build_world(1234, 1);
+    my_rl::step(&mut ecs); //PreRun
+    let others: Vec<Entity> = (&ecs.entities(), &ecs.read_storage::<Position>(), !&ecs.read_storage::<Player>())
+        .join()
+        .map(|(e, _, _)| e)
+        .collect();
+    ecs.delete_entities(&others).unwrap();
+
+    let mut map = Map::new(1, 16, 8);
+    for x in 1..15 {
+        for y in 1..7 {
+            let idx = map.xy_idx(x, y);
+            map.tiles[idx] = TileType::Floor;
+        }
+    }
+    map.populate_blocked();
+    ecs.insert(map);
+    let player = *ecs.fetch::<Entity>();
+    *ecs.write_storage::<Position>().get_mut(player).unwrap() = Position { x: 3, y: 3 };
+    *ecs.write_resource::<Point>() = Point::new(3, 3);
+    ecs.write_storage::<Viewshed>().get_mut(player).unwrap().dirty = true;
+
+    let goblin = raws::spawn_named_entity(&mut ecs, "Goblin", 5, 3).unwrap();
+    assert!(ecs.read_storage::<Hostile>().get(goblin).is_some());
+    {
+        let mut stats = ecs.write_storage::<Stats>();
+        let goblin_stats = stats.get_mut(goblin).unwrap();
+        goblin_stats.hp = goblin_stats.max_hp / 4;
+    }
+    my_rl::run_systems(&mut ecs, my_rl::schedule::Turn::PreRun);
+
+    for _ in 0..20 { //ticks, enough for a few goblin turns
+        my_rl::run_systems(&mut ecs, my_rl::schedule::Turn::GameworldTurn);
+    }
+    assert!(ecs.read_storage::<Position>().get(goblin).unwrap().x > 5);
+}
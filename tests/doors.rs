@@ -0,0 +1,119 @@
+use std::sync::Arc;
+
+use bracket_lib::prelude::{a_star_search, Point};
+use specs::prelude::*;
+
+use my_rl::command::{Command, Commandable};
+use my_rl::gui::look_n_feel::Dir;
+use my_rl::player::PlayerController;
+use my_rl::user_input::UserInput;
+use my_rl::{raws, BlocksTile, Door, InBackpack, Map, Player, Position, RunState, TileType, Viewshed};
+
+//The player at 4, 5 on an open floor split by a wall down x = 6, with `door` in
+//the wall at 6, 5 and nothing else about.
+fn setup(door: &str) -> (World, Entity) {
+    let mut ecs = my_rl::build_world(1234, 1);
+    my_rl::step(&mut ecs); //PreRun
+
+    let player = *ecs.fetch::<Entity>();
+    let others: Vec<Entity> = (&ecs.entities(), &ecs.read_storage::<Position>(), !&ecs.read_storage::<Player>())
+        .join()
+        .map(|(e, _, _)| e)
+        .collect();
+    ecs.delete_entities(&others).unwrap();
+
+    let mut map = Map::new(1, 12, 12);
+    for y in 1..11 {
+        for x in 1..11 {
+            let idx = map.xy_idx(x, y);
+            map.tiles[idx] = if x == 6 && y != 5 { TileType::Wall } else { TileType::Floor };
+        }
+    }
+    map.populate_blocked();
+    ecs.insert(map);
+    *ecs.write_storage::<Position>().get_mut(player).unwrap() = Position { x: 4, y: 5 };
+    *ecs.write_resource::<Point>() = Point::new(4, 5);
+    ecs.write_storage::<Viewshed>().get_mut(player).unwrap().dirty = true;
+
+    let door = raws::spawn_named_entity(&mut ecs, door, 6, 5).unwrap();
+    my_rl::run_systems(&mut ecs, my_rl::schedule::Turn::PreRun);
+    (ecs, door)
+}
+
+//Plays one command as GameState::tick() would, then runs the world until the player's next turn.
+fn play(ecs: &mut World, cmd: Command) {
+    let controller = PlayerController::new(&Arc::new(UserInput::new()));
+    controller.send(cmd);
+    let runstate = controller.ecs_process(ecs, RunState::AwaitingInput);
+    *ecs.write_resource::<RunState>() = runstate;
+    while *ecs.fetch::<RunState>() != RunState::AwaitingInput {
+        my_rl::step(ecs);
+    }
+}
+
+fn player_at(ecs: &World) -> Point {
+    *ecs.fetch::<Point>()
+}
+
+fn is_open(ecs: &World, door: Entity) -> bool {
+    ecs.read_storage::<Door>().get(door).unwrap().open
+}
+
+fn player_sees(ecs: &World, x: i32, y: i32) -> bool {
+    let player = *ecs.fetch::<Entity>();
+    ecs.read_storage::<Viewshed>().get(player).unwrap().visible_tiles.contains(&Point::new(x, y))
+}
+
+#[test]
+fn bumping_opens_a_door_and_closing_shuts_it() {
+    let (mut ecs, door) = setup("Door");
+    assert!(!player_sees(&ecs, 8, 5));
+
+    play(&mut ecs, Command::Move { dir: Dir::RIGHT });
+    play(&mut ecs, Command::Move { dir: Dir::RIGHT });
+    assert_eq!(player_at(&ecs), Point::new(5, 5)); //the bump opened it, and didn't move
+    assert!(is_open(&ecs, door));
+    assert!(ecs.read_storage::<BlocksTile>().get(door).is_none());
+    assert!(player_sees(&ecs, 8, 5));
+
+    play(&mut ecs, Command::Move { dir: Dir::RIGHT });
+    assert_eq!(player_at(&ecs), Point::new(6, 5));
+    play(&mut ecs, Command::Close); //standing in the doorway
+    assert!(is_open(&ecs, door));
+
+    play(&mut ecs, Command::Move { dir: Dir::RIGHT });
+    play(&mut ecs, Command::Close);
+    assert!(!is_open(&ecs, door));
+    assert!(ecs.read_storage::<BlocksTile>().get(door).is_some());
+    assert!(!player_sees(&ecs, 4, 5));
+}
+
+#[test]
+fn a_locked_door_takes_a_key_and_uses_it_up() {
+    let (mut ecs, door) = setup("Locked Door");
+    play(&mut ecs, Command::Move { dir: Dir::RIGHT });
+    play(&mut ecs, Command::Move { dir: Dir::RIGHT });
+    assert!(!is_open(&ecs, door));
+
+    let player = *ecs.fetch::<Entity>();
+    let key = raws::spawn_named_entity(&mut ecs, "Key", 1, 1).unwrap();
+    ecs.write_storage::<Position>().remove(key);
+    ecs.write_storage::<InBackpack>().insert(key, InBackpack { owner: player }).unwrap();
+
+    play(&mut ecs, Command::Move { dir: Dir::RIGHT });
+    assert!(is_open(&ecs, door));
+    assert!(!ecs.read_storage::<Door>().get(door).unwrap().locked);
+    assert!(!ecs.is_alive(key));
+}
+
+#[test]
+fn paths_go_through_closed_doors_but_not_locked_ones() {
+    let (ecs, _) = setup("Door");
+    let map = ecs.fetch::<Map>();
+    assert!(map.blocked[map.xy_idx(6, 5)]);
+    assert!(a_star_search(map.xy_idx(4, 5), map.xy_idx(8, 5), &*map).success);
+
+    let (ecs, _) = setup("Locked Door");
+    let map = ecs.fetch::<Map>();
+    assert!(!a_star_search(map.xy_idx(4, 5), map.xy_idx(8, 5), &*map).success);
+}
@@ -0,0 +1,108 @@
+use std::sync::Arc;
+
+use specs::prelude::*;
+
+use bracket_lib::prelude::Point;
+use my_rl::command::{Command, Commandable};
+use my_rl::dungeon::LevelStore;
+use my_rl::player::PlayerController;
+use my_rl::saveload_system;
+use my_rl::user_input::UserInput;
+use my_rl::{Hostile, Map, OtherLevelPosition, Position, RunState, TileType};
+
+fn started_world(seed: u64) -> World {
+    let mut ecs = my_rl::build_world(seed, 1);
+    my_rl::step(&mut ecs); //PreRun
+    ecs
+}
+
+fn player_tile(ecs: &World) -> TileType {
+    let map = ecs.fetch::<Map>();
+    let pos = *ecs.fetch::<Point>();
+    map.tiles[map.xy_idx(pos.x, pos.y)]
+}
+
+fn depth(ecs: &World) -> i32 {
+    ecs.fetch::<Map>().depth
+}
+
+fn take_stairs(ecs: &mut World) -> RunState {
+    let user_input = Arc::new(UserInput::new());
+    let controller = PlayerController::new(&user_input);
+    controller.send(Command::Stairs);
+    controller.ecs_process(ecs, RunState::AwaitingInput)
+}
+
+#[test]
+fn the_stairs_key_only_works_on_stairs() {
+    let mut ecs = started_world(1234);
+    assert!(player_tile(&ecs) != TileType::StairsDown);
+    assert!(take_stairs(&mut ecs) == RunState::AwaitingInput);
+
+    let pos = *ecs.fetch::<Point>();
+    {
+        let mut map = ecs.fetch_mut::<Map>();
+        let idx = map.xy_idx(pos.x, pos.y);
+        map.tiles[idx] = TileType::StairsDown;
+    }
+    assert!(take_stairs(&mut ecs) == RunState::NextLevel);
+}
+
+#[test]
+fn levels_are_kept_when_the_player_leaves_them() {
+    let mut ecs = started_world(1234);
+    let first_tiles = ecs.fetch::<Map>().tiles.clone();
+    let (monster, monster_pos) = {
+        let entities = ecs.entities();
+        let positions = ecs.read_storage::<Position>();
+        let hostiles = ecs.read_storage::<Hostile>();
+        let (entity, pos, _) = (&entities, &positions, &hostiles).join().next().expect("no monsters on depth 1");
+        (entity, *pos)
+    };
+
+    my_rl::goto_previous_level(&mut ecs);
+    assert_eq!(depth(&ecs), 1, "there is nothing above depth 1");
+
+    my_rl::goto_next_level(&mut ecs);
+    assert_eq!(depth(&ecs), 2);
+    assert!(player_tile(&ecs) == TileType::StairsUp);
+    assert!(ecs.read_storage::<Position>().get(monster).is_none());
+    assert_eq!(ecs.read_storage::<OtherLevelPosition>().get(monster).unwrap().depth, 1);
+    let second_tiles = ecs.fetch::<Map>().tiles.clone();
+    my_rl::step(&mut ecs); //PreRun
+
+    my_rl::goto_previous_level(&mut ecs);
+    assert_eq!(depth(&ecs), 1);
+    assert!(ecs.fetch::<Map>().tiles == first_tiles);
+    assert!(player_tile(&ecs) == TileType::StairsDown);
+    let pos = *ecs.read_storage::<Position>().get(monster).unwrap();
+    assert_eq!((pos.x, pos.y), (monster_pos.x, monster_pos.y));
+    assert!(ecs.read_storage::<OtherLevelPosition>().get(monster).is_none());
+    my_rl::step(&mut ecs); //PreRun
+
+    my_rl::goto_next_level(&mut ecs);
+    assert!(ecs.fetch::<Map>().tiles == second_tiles, "depth 2 was generated again");
+}
+
+#[test]
+fn the_level_store_is_saved_with_the_game() {
+    let path = std::env::temp_dir().join(format!("my_rl_dungeon_{}.json", std::process::id()));
+    let mut ecs = started_world(1234);
+    let first_tiles = ecs.fetch::<Map>().tiles.clone();
+    my_rl::goto_next_level(&mut ecs);
+    my_rl::step(&mut ecs); //PreRun
+    saveload_system::save_game_to(&mut ecs, &path).unwrap();
+
+    let mut loaded = started_world(99);
+    saveload_system::load_game_from(&mut loaded, &path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(loaded.fetch::<LevelStore>().depths(), vec![1]);
+    let frozen = loaded.read_storage::<OtherLevelPosition>().join().count();
+    assert!(frozen > 0);
+
+    my_rl::goto_previous_level(&mut loaded);
+    assert_eq!(depth(&loaded), 1);
+    assert!(loaded.fetch::<Map>().tiles == first_tiles);
+    assert!(player_tile(&loaded) == TileType::StairsDown);
+    assert_eq!(loaded.fetch::<LevelStore>().depths(), vec![2]);
+}
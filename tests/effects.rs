@@ -0,0 +1,83 @@
+use specs::prelude::*;
+
+use bracket_lib::prelude::Point;
+use my_rl::effects::{EffectQueue, EffectType, Targets};
+use my_rl::events::Statistics;
+use my_rl::{AoE, Consumable, DamageAtom, DamageOnUse, Hostile, MagicMapper, Name, Position, RunState, Stats, UseItemIntent};
+
+//A world with every hostile removed and one test monster two tiles from the player.
+fn setup() -> (World, Entity, Point) {
+    let mut ecs = my_rl::build_world(1234, 1);
+    my_rl::step(&mut ecs); //PreRun
+
+    let hostiles: Vec<Entity> = (&ecs.entities(), &ecs.read_storage::<Hostile>())
+        .join()
+        .map(|(e, _)| e)
+        .collect();
+    ecs.delete_entities(&hostiles).unwrap();
+
+    let player_pos = *ecs.fetch::<Point>();
+    let monster = ecs.create_entity()
+        .with(Position { x: player_pos.x, y: player_pos.y })
+        .with(Name { name: "Test Monster".to_string() })
+        .with(Stats { max_hp: 10, hp: 10, max_fp: 8, fp: 8, max_mp: 2, mp: 2, mind: 1, body: 1, soul: 1 })
+        .build();
+    my_rl::run_systems(&mut ecs, my_rl::schedule::Turn::PreRun); //index the monster
+
+    (ecs, monster, player_pos)
+}
+
+fn player_turn(ecs: &mut World) {
+    *ecs.write_resource::<RunState>() = RunState::PlayerTurn;
+    my_rl::step(ecs);
+}
+
+#[test]
+fn area_item_hits_the_blast_and_is_used_up() {
+    let (mut ecs, monster, pos) = setup();
+    let player = *ecs.fetch::<Entity>();
+    let bomb = ecs.create_entity()
+        .with(Name { name: "Test Bomb".to_string() })
+        .with(Consumable {})
+        .with(AoE { radius: 1 })
+        .with(DamageOnUse { dmg_atoms: vec![DamageAtom::Bludgeon(3)] })
+        .build();
+    ecs.write_storage::<UseItemIntent>()
+        .insert(player, UseItemIntent { item: bomb, target: Some(pos) })
+        .unwrap();
+
+    player_turn(&mut ecs);
+
+    assert_eq!(ecs.read_storage::<Stats>().get(monster).unwrap().hp, 7);
+    assert!(!ecs.is_alive(bomb));
+}
+
+#[test]
+fn queued_damage_lands_on_the_targeted_tile() {
+    let (mut ecs, monster, pos) = setup();
+    ecs.fetch_mut::<EffectQueue>().push(None, EffectType::Damage { atoms: vec![DamageAtom::Bludgeon(2)] },
+        Targets::Tile { pt: pos });
+
+    player_turn(&mut ecs);
+
+    assert_eq!(ecs.read_storage::<Stats>().get(monster).unwrap().hp, 8);
+}
+
+#[test]
+fn magic_mapping_reveals_the_map_and_counts_as_a_use() {
+    let (mut ecs, _, _) = setup();
+    let player = *ecs.fetch::<Entity>();
+    let scroll = ecs.create_entity()
+        .with(Name { name: "Test Scroll".to_string() })
+        .with(Consumable {})
+        .with(MagicMapper {})
+        .build();
+    ecs.write_storage::<UseItemIntent>()
+        .insert(player, UseItemIntent { item: scroll, target: None })
+        .unwrap();
+
+    player_turn(&mut ecs);
+
+    assert!(matches!(*ecs.fetch::<RunState>(), RunState::MagicMapReveal { .. }));
+    assert_eq!(ecs.fetch::<Statistics>().items_used, 1);
+}
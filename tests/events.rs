@@ -0,0 +1,66 @@
+use std::sync::{Arc, Mutex};
+
+use specs::prelude::*;
+
+use my_rl::command::{Command, Commandable};
+use my_rl::events::{Achievement, Achievements, DamageKind, GameEvent, Statistics, Subscriber, Subscribers};
+use my_rl::player::PlayerController;
+use my_rl::user_input::UserInput;
+use my_rl::{DamageAtom, DamageQueue, RunState};
+
+mod common;
+
+struct Recorder {
+    events: Arc<Mutex<Vec<GameEvent>>>,
+}
+
+impl Subscriber for Recorder {
+    fn notify(&mut self, _ecs: &World, event: &GameEvent) {
+        self.events.lock().unwrap().push(event.clone());
+    }
+}
+
+//common::world_with_adjacent_monster() with a Recorder subscribed to the event stream.
+fn setup() -> (World, Entity, Arc<Mutex<Vec<GameEvent>>>) {
+    let (ecs, monster) = common::world_with_adjacent_monster(10);
+
+    let events = Arc::new(Mutex::new(Vec::new()));
+    ecs.fetch_mut::<Subscribers>().add(Box::new(Recorder { events: events.clone() }));
+    (ecs, monster, events)
+}
+
+fn wait_one_turn(ecs: &mut World) {
+    let user_input = Arc::new(UserInput::new());
+    let controller = PlayerController::new(&user_input);
+    controller.send(Command::Wait);
+    let runstate = controller.ecs_process(ecs, RunState::AwaitingInput);
+    *ecs.write_resource::<RunState>() = runstate;
+    while my_rl::step(ecs) != RunState::AwaitingInput {}
+}
+
+#[test]
+fn monster_attack_emits_damage_event() {
+    let (mut ecs, _monster, events) = setup();
+    let player = *ecs.fetch::<Entity>();
+
+    wait_one_turn(&mut ecs);
+
+    let events = events.lock().unwrap();
+    assert!(events.contains(&GameEvent::Damaged { target: player, amount: 1, kind: DamageKind::Health }));
+    assert_eq!(ecs.fetch::<Statistics>().damage_taken, 1);
+}
+
+#[test]
+fn killing_a_monster_emits_died_and_counts_the_kill() {
+    let (mut ecs, monster, events) = setup();
+    DamageQueue::queue_damage(&mut ecs.write_storage::<DamageQueue>(), monster, DamageAtom::Bludgeon(10));
+
+    wait_one_turn(&mut ecs);
+
+    let events = events.lock().unwrap();
+    assert!(events.contains(&GameEvent::Damaged { target: monster, amount: 10, kind: DamageKind::Health }));
+    assert!(events.contains(&GameEvent::Died { entity: monster }));
+    assert!(events.contains(&GameEvent::AchievementUnlocked { achievement: Achievement::FirstBlood }));
+    assert_eq!(ecs.fetch::<Statistics>().kills, 1);
+    assert_eq!(ecs.fetch::<Achievements>().unlocked, vec![Achievement::FirstBlood]);
+}
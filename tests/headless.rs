@@ -0,0 +1,35 @@
+use specs::prelude::*;
+
+use bracket_lib::prelude::Point;
+use my_rl::{Map, Player, Position, RunState, TileType};
+
+#[test]
+fn build_world_places_player_on_map() {
+    let ecs = my_rl::build_world(1234, 1);
+
+    let map = ecs.fetch::<Map>();
+    let player_pos = *ecs.fetch::<Point>();
+    let idx = map.xy_idx(player_pos.x, player_pos.y);
+    assert_eq!(map.depth, 1);
+    assert!(map.tiles[idx] != TileType::Wall);
+
+    let player = ecs.fetch::<Entity>();
+    let positions = ecs.read_storage::<Position>();
+    let pos = positions.get(*player).unwrap();
+    assert_eq!((pos.x, pos.y), (player_pos.x, player_pos.y));
+    assert!(ecs.read_storage::<Player>().get(*player).is_some());
+}
+
+#[test]
+fn step_runs_systems_without_a_window() {
+    let mut ecs = my_rl::build_world(1234, 1);
+    assert!(my_rl::step(&mut ecs) == RunState::AwaitingInput);
+
+    //A PlayerTurn is always followed by a GameworldTurn, then back to input.
+    *ecs.write_resource::<RunState>() = RunState::PlayerTurn;
+    assert!(my_rl::step(&mut ecs) == RunState::GameworldTurn);
+    assert!(my_rl::step(&mut ecs) == RunState::AwaitingInput);
+
+    let map = ecs.fetch::<Map>();
+    assert!(map.revealed_tiles.iter().any(|t| *t));
+}
@@ -0,0 +1,76 @@
+use std::sync::Arc;
+
+use specs::prelude::*;
+
+use my_rl::command::{Command, Commandable};
+use my_rl::player::PlayerController;
+use my_rl::user_input::UserInput;
+use my_rl::{Energy, Healing, Hostile, RunState, Stats};
+
+mod common;
+
+//Hp the player loses to a single adjacent monster of the given speed while waiting one turn.
+fn damage_taken_in_one_wait(speed: i32) -> i32 {
+    let (mut ecs, _monster) = common::world_with_adjacent_monster(speed);
+    let player = *ecs.fetch::<Entity>();
+
+    let user_input = Arc::new(UserInput::new());
+    let controller = PlayerController::new(&user_input);
+    controller.send(Command::Wait);
+    let runstate = controller.ecs_process(&mut ecs, RunState::AwaitingInput);
+    assert!(runstate == RunState::PlayerTurn);
+    *ecs.write_resource::<RunState>() = runstate;
+    while my_rl::step(&mut ecs) != RunState::AwaitingInput {}
+
+    let hp = ecs.read_storage::<Stats>().get(player).unwrap().hp;
+    50 - hp
+}
+
+#[test]
+fn faster_creatures_act_more_often() {
+    assert_eq!(damage_taken_in_one_wait(10), 1);
+    assert_eq!(damage_taken_in_one_wait(20), 2);
+    assert_eq!(damage_taken_in_one_wait(5), 0);
+}
+
+#[test]
+fn player_acts_again_after_one_turn() {
+    let mut ecs = my_rl::build_world(1234, 1);
+    my_rl::step(&mut ecs);
+    let player = *ecs.fetch::<Entity>();
+
+    let user_input = Arc::new(UserInput::new());
+    let controller = PlayerController::new(&user_input);
+    controller.send(Command::Wait);
+    let runstate = controller.ecs_process(&mut ecs, RunState::AwaitingInput);
+    *ecs.write_resource::<RunState>() = runstate;
+    assert!(ecs.read_storage::<Energy>().get(player).unwrap().current < 100);
+
+    while my_rl::step(&mut ecs) != RunState::AwaitingInput {}
+    assert!(ecs.read_storage::<Energy>().get(player).unwrap().current >= 100);
+}
+
+#[test]
+fn healing_over_time_ticks_once_per_turn() {
+    let mut ecs = my_rl::build_world(1234, 1);
+    my_rl::step(&mut ecs);
+    let hostiles: Vec<Entity> = (&ecs.entities(), &ecs.read_storage::<Hostile>())
+        .join()
+        .map(|(e, _)| e)
+        .collect();
+    ecs.delete_entities(&hostiles).unwrap(); //nothing in sight, so waiting also rests
+    let player = *ecs.fetch::<Entity>();
+    ecs.write_storage::<Stats>().get_mut(player).unwrap().hp = 1;
+    ecs.write_storage::<Healing>().insert(player, Healing { duration: 5, amount: 1 }).unwrap();
+
+    let user_input = Arc::new(UserInput::new());
+    let controller = PlayerController::new(&user_input);
+    controller.send(Command::Wait);
+    let runstate = controller.ecs_process(&mut ecs, RunState::AwaitingInput);
+    *ecs.write_resource::<RunState>() = runstate;
+    while my_rl::step(&mut ecs) != RunState::AwaitingInput {}
+
+    //One hp for resting, one for the single tick of healing.
+    assert_eq!(ecs.read_storage::<Healing>().get(player).unwrap().duration, 4);
+    assert_eq!(ecs.read_storage::<Stats>().get(player).unwrap().hp, 3);
+}
@@ -0,0 +1,71 @@
+use std::process::Command;
+
+use specs::prelude::*;
+
+use my_rl::mapgen_visualizer::MapgenRecorder;
+use my_rl::{Map, TileType};
+
+fn mapgen(args: &[&str]) -> (bool, String) {
+    let output = Command::new(env!("CARGO_BIN_EXE_mapgen")).args(args).output().unwrap();
+    (output.status.success(), String::from_utf8(output.stdout).unwrap())
+}
+
+#[test]
+fn prints_the_level_the_game_builds() {
+    let (ok, out) = mapgen(&["--seed", "1234", "--depth", "1"]);
+    assert!(ok);
+    let ecs = my_rl::build_world(1234, 1);
+    let map = ecs.fetch::<Map>();
+
+    let rows: Vec<&str> = out.lines().skip_while(|line| !line.starts_with("seed 1234, depth 1")).skip(1)
+        .take(map.height as usize).collect();
+    assert_eq!(rows.len(), map.height as usize);
+    for (y, row) in rows.iter().enumerate() {
+        for (x, ch) in row.chars().enumerate() {
+            let tile = map.tiles[map.xy_idx(x as i32, y as i32)];
+            assert_eq!(ch == '#', tile == TileType::Wall, "differs at {}, {}", x, y);
+        }
+    }
+    assert!(out.lines().any(|line| line.starts_with("start: ")));
+    assert!(out.lines().any(|line| line.starts_with("spawns: ")));
+}
+
+#[test]
+fn snapshots_and_recipes_on_request() {
+    let (ok, out) = mapgen(&["--seed", "1234", "--depth", "5", "--recipe", "bsp_interior", "--snapshots"]);
+    assert!(ok);
+    assert!(out.contains("recipe bsp_interior"));
+    assert!(out.lines().any(|line| line.starts_with("snapshot 1 of ") && line.ends_with(": bsp_interior")));
+
+    let (ok, _) = mapgen(&["--seed", "1234", "--recipe", "no_such_recipe"]);
+    assert!(!ok);
+    let (ok, _) = mapgen(&["--depth", "2"]);
+    assert!(!ok);
+}
+
+#[test]
+fn stats_cover_every_recipe() {
+    let (ok, out) = mapgen(&["--stats", "--seeds", "3", "--depth", "2"]);
+    assert!(ok);
+    for recipe in ["rooms", "bsp_interior", "shapes"].iter() {
+        let row = out.lines().find(|line| line.split_whitespace().next() == Some(*recipe)).unwrap();
+        let maps: Vec<&str> = row.split_whitespace().skip(1).take(2).collect();
+        assert_eq!(maps, vec!["3", "0"], "{}", row);
+    }
+}
+
+#[test]
+fn the_visualizer_records_without_changing_the_level() {
+    let mut ecs = my_rl::new_world(1234);
+    ecs.write_resource::<MapgenRecorder>().enabled = true;
+    my_rl::generate_world_map(&mut ecs, 2);
+    let recording = ecs.write_resource::<MapgenRecorder>().take().unwrap();
+    assert_eq!((recording.seed, recording.depth), (1234, 2));
+    assert!(!recording.snapshots.is_empty());
+    assert_eq!(recording.snapshots.last().unwrap().step, "up_stairs");
+    assert!(ecs.write_resource::<MapgenRecorder>().take().is_none());
+
+    let recorded = ecs.fetch::<Map>().tiles.clone();
+    let plain = my_rl::build_world(1234, 2);
+    assert_eq!(plain.fetch::<Map>().tiles, recorded);
+}
@@ -0,0 +1,96 @@
+use std::panic;
+
+use bracket_lib::prelude::RandomNumberGenerator;
+
+use my_rl::map_builders::{invariants, named_builder, random_builder, BuilderChain, RecipeBook};
+use my_rl::{rng, TileType, MAP_HEIGHT, MAP_WIDTH};
+
+const SEEDS: u64 = 16;
+const DEPTHS: i32 = 3;
+
+//Builds one level, and lists what's wrong with it (a panic included).
+fn problems(build: impl FnOnce() -> BuilderChain + panic::UnwindSafe) -> Vec<String> {
+    match panic::catch_unwind(|| {
+        let chain = build();
+        (invariants::violations(&chain.build_data), chain.steps)
+    }) {
+        Ok((problems, _)) if problems.is_empty() => Vec::new(),
+        Ok((problems, steps)) => vec![format!("{} [{}]", problems.join("; "), steps.join(", "))],
+        Err(_) => vec!["panicked".to_string()],
+    }
+}
+
+fn report(failures: Vec<String>) {
+    assert!(failures.is_empty(), "{} bad levels:\n{}", failures.len(),
+            failures.iter().take(10).cloned().collect::<Vec<_>>().join("\n"));
+}
+
+#[test]
+fn every_recipe_keeps_the_invariants() {
+    let recipes: Vec<String> = RecipeBook::builtin().names().cloned().collect();
+    let mut failures = Vec::new();
+    for recipe in recipes.iter() {
+        for seed in 0..SEEDS {
+            for depth in 1..=DEPTHS {
+                for problem in problems(|| {
+                    let mut rng = RandomNumberGenerator::seeded(seed);
+                    let mut chain = named_builder(recipe, depth, &mut rng, MAP_WIDTH, MAP_HEIGHT).unwrap();
+                    chain.build_map(&mut rng);
+                    chain
+                }) {
+                    failures.push(format!("{} seed {} depth {}: {}", recipe, seed, depth, problem));
+                }
+            }
+        }
+    }
+    report(failures);
+}
+
+#[test]
+fn the_games_levels_keep_the_invariants() {
+    let mut failures = Vec::new();
+    for seed in 0..SEEDS {
+        for depth in 1..=DEPTHS {
+            for problem in problems(|| {
+                let mut rng = rng::mapgen_rng(seed, depth);
+                let mut chain = random_builder(depth, &mut rng, MAP_WIDTH, MAP_HEIGHT);
+                chain.build_map(&mut rng);
+                chain
+            }) {
+                failures.push(format!("seed {} depth {}: {}", seed, depth, problem));
+            }
+        }
+    }
+    report(failures);
+}
+
+#[test]
+fn broken_levels_are_caught() {
+    let build = || {
+        let mut rng = RandomNumberGenerator::seeded(1234);
+        let mut chain = named_builder("bsp_interior", 2, &mut rng, MAP_WIDTH, MAP_HEIGHT).unwrap();
+        chain.build_map(&mut rng);
+        chain
+    };
+    assert!(invariants::violations(&build().build_data).is_empty());
+
+    //Wall in the stairs down.
+    let mut chain = build();
+    let map = &mut chain.build_data.map;
+    let stairs = map.tiles.iter().position(|tile| *tile == TileType::StairsDown).unwrap();
+    let width = map.width as usize;
+    for idx in [stairs - width - 1, stairs - width, stairs - width + 1, stairs - 1, stairs + 1,
+                stairs + width - 1, stairs + width, stairs + width + 1].iter() {
+        map.tiles[*idx] = TileType::Wall;
+    }
+    assert!(invariants::violations(&chain.build_data).iter().any(|p| p.contains("can't be reached")));
+
+    //Spawn something in rock, and a door in the open.
+    let mut chain = build();
+    let start = chain.build_data.starting_position.as_ref().map(|p| chain.build_data.map.xy_idx(p.x, p.y)).unwrap();
+    chain.build_data.spawn_list.push((0, "Goblin".to_string()));
+    chain.build_data.spawn_list.push((start, "Door".to_string()));
+    let problems = invariants::violations(&chain.build_data);
+    assert!(problems.iter().any(|p| p.starts_with("Goblin spawns in a Wall")));
+    assert!(problems.iter().any(|p| p.starts_with("door at")));
+}
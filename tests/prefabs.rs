@@ -0,0 +1,91 @@
+use std::fs::File;
+
+use bracket_lib::prelude::rex::{XpFile, XpLayer};
+use my_rl::map_builders::{PrefabKind, PrefabLibrary};
+use my_rl::raws::{RawMaster, RawsError};
+use my_rl::TileType;
+
+fn parse(yaml: &str) -> Result<PrefabLibrary, RawsError> {
+    PrefabLibrary::parse(&[("test".to_string(), yaml.to_string())], None)
+}
+
+#[test]
+fn the_builtin_prefabs_load() {
+    let library = PrefabLibrary::builtin();
+    assert_eq!(library.of_kind(PrefabKind::Room).count(), 4);
+    let fort = library.get("underground_fort").unwrap();
+    assert_eq!((fort.kind, fort.width, fort.height), (PrefabKind::Section, 15, 43));
+    let level = library.get("wfc_populated").unwrap();
+    assert_eq!((level.width, level.height), (80, 43));
+    assert!(level.cell(5, 2).start);
+}
+
+#[test]
+fn legends_come_before_the_shared_glyphs() {
+    let library = parse("kind: room\nlegend: { g: Orc, x: Bear Trap }\nmap: |\n  #g#\n  .x~\n").unwrap();
+    let room = library.get("test").unwrap();
+    assert_eq!(room.cell(1, 0).spawn.as_deref(), Some("Orc"));
+    assert_eq!(room.cell(1, 1).spawn.as_deref(), Some("Bear Trap"));
+    assert_eq!(room.cell(1, 1).tile, TileType::Floor);
+    assert_eq!(room.cell(2, 1).tile, TileType::ShallowWater);
+
+    let mistakes = [
+        "kind: room\nmap: |\n  #?#\n",                  //unknown glyph
+        "kind: room\nmap: |\n  ###\n  ##\n",             //ragged
+        "kind: section\nmap: |\n  ###\n",                //no placement
+        "kind: room\nmin_depth: 5\nmax_depth: 2\nmap: |\n  .\n",
+    ];
+    for yaml in mistakes.iter() {
+        assert!(matches!(parse(yaml), Err(RawsError::Invalid { .. })), "{}", yaml);
+    }
+}
+
+#[test]
+fn turning_and_flipping() {
+    let library = parse("kind: room\nmap: |\n  g.#\n  ...\n").unwrap();
+    let room = library.get("test").unwrap();
+
+    let turned = room.transformed(1, false);
+    assert_eq!((turned.width, turned.height), (2, 3));
+    assert_eq!(turned.cell(1, 0).spawn.as_deref(), Some("Goblin"));
+    assert_eq!(turned.cell(1, 2).tile, TileType::Wall);
+
+    let flipped = room.transformed(0, true);
+    assert_eq!(flipped.cell(2, 0).spawn.as_deref(), Some("Goblin"));
+    assert_eq!(flipped.cell(0, 0).tile, TileType::Wall);
+    assert_eq!(room.transformed(4, false).cell(0, 0), room.cell(0, 0));
+}
+
+#[test]
+fn rex_layers_split_terrain_from_entities() {
+    let dir = std::env::temp_dir().join(format!("my_rl_prefabs_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let mut xp = XpFile::new(3, 1);
+    for (x, ch) in "#.>".chars().enumerate() {
+        xp.layers[0].get_mut(x, 0).unwrap().ch = ch as u32;
+    }
+    xp.layers.push(XpLayer::new(3, 1));
+    xp.layers[1].get_mut(1, 0).unwrap().ch = 'k' as u32;
+    xp.write(&mut File::create(dir.join("keep.xp")).unwrap()).unwrap();
+
+    let files = [("keep".to_string(), "kind: room\nrex: keep.xp\nlegend: { k: Knife }\n".to_string())];
+    let library = PrefabLibrary::parse(&files, Some(&dir)).unwrap();
+    let keep = library.get("keep").unwrap();
+    assert_eq!(keep.cell(0, 0).tile, TileType::Wall);
+    assert_eq!(keep.cell(1, 0).spawn.as_deref(), Some("Knife"));
+    assert_eq!(keep.cell(2, 0).tile, TileType::StairsDown);
+
+    //An entity on a wall is a mistake, not a spawn.
+    xp.layers[1].get_mut(0, 0).unwrap().ch = 'k' as u32;
+    xp.write(&mut File::create(dir.join("keep.xp")).unwrap()).unwrap();
+    assert!(PrefabLibrary::parse(&files, Some(&dir)).is_err());
+
+    //So is a legend naming something the raws don't have. The directory's prefabs are
+    //loaded over the built-in ones, which the built-in recipes go on naming.
+    std::fs::write(dir.join("bad.yaml"), "kind: room\nlegend: { u: Unicorn }\nmap: |\n  u\n").unwrap();
+    my_rl::map_builders::load_prefabs(dir.to_str().unwrap()).unwrap();
+    assert!(my_rl::map_builders::PREFABS.lock().unwrap().get("underground_fort").is_some());
+    let problems: Vec<String> = RawMaster::builtin().validate().iter().map(|p| p.to_string()).collect();
+    assert!(problems.contains(&"prefab \"bad\": unknown entity \"Unicorn\"".to_string()), "{:?}", problems);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,93 @@
+use specs::prelude::*;
+
+use my_rl::raws::{self, RawMaster, RawsError};
+use my_rl::{Energy, Hostile, Name, Stats, Weapon};
+
+#[test]
+fn spawns_are_built_from_definitions() {
+    let mut ecs = my_rl::new_world(1);
+
+    let goblin = raws::spawn_named_entity(&mut ecs, "Goblin", 1, 1).unwrap();
+    assert!(ecs.read_storage::<Hostile>().get(goblin).is_some());
+    assert_eq!(ecs.read_storage::<Stats>().get(goblin).unwrap().max_hp, 4);
+    assert_eq!(ecs.read_storage::<Energy>().get(goblin).unwrap().speed, 15);
+
+    let scroll = raws::spawn_named_entity(&mut ecs, "Fireball Scroll", 1, 1).unwrap();
+    assert_eq!(ecs.read_storage::<Name>().get(scroll).unwrap().name, "Scroll of Fireball");
+
+    let knife = raws::spawn_named_entity(&mut ecs, "Knife", 1, 1).unwrap();
+    assert!(ecs.read_storage::<Weapon>().get(knife).unwrap().primary.is_some());
+
+    assert!(raws::spawn_named_entity(&mut ecs, "No Such Thing", 1, 1).is_none());
+}
+
+#[test]
+fn bad_definitions_are_rejected() {
+    let duplicate = "props:\n  - name: Door\n  - name: Door\n";
+    assert!(matches!(RawMaster::parse(duplicate), Err(RawsError::DuplicateName(_))));
+
+    let bad_color = "props:\n  - name: Door\n    renderable: { glyph: \"+\", fg: brown }\n";
+    assert!(matches!(RawMaster::parse(bad_color), Err(RawsError::Invalid { .. })));
+
+    let no_stats = "monsters:\n  - name: Ghost\n";
+    assert!(matches!(RawMaster::parse(no_stats), Err(RawsError::Invalid { .. })));
+
+    let typo = "items:\n  - name: Rock\n    throwabel: { Bludgeon: 1 }\n";
+    assert!(matches!(RawMaster::parse(typo), Err(RawsError::Parse(_))));
+}
+
+#[test]
+fn builtin_raws_report_the_rations_nothing_defines() {
+    //Prefabs spawn Rations for '%', but nothing in the raws builds them.
+    let problems: Vec<String> = RawMaster::builtin().validate().iter().map(|p| p.to_string()).collect();
+    assert_eq!(problems, vec![
+        "prefab glyph '%': unknown entity \"Rations\"".to_string(),
+        "prefab \"checkerboard\": unknown entity \"Rations\"".to_string(),
+        "prefab \"locked_cache\": unknown entity \"Rations\"".to_string(),
+        "prefab \"wfc_populated\": unknown entity \"Rations\"".to_string(),
+    ]);
+}
+
+#[test]
+fn spawn_tables_nest_and_follow_depth() {
+    let yaml = "
+items:
+  - name: Knife
+  - name: Longsword
+spawn_tables:
+  room:
+    - { table: weapons, weight: 1 }
+  weapons:
+    - { name: Knife, weight: { base: 4, per_depth: -1 } }
+    - { name: Longsword, weight: 1, min_depth: 3 }
+";
+    let raws = RawMaster::parse(yaml).unwrap();
+    let mut rng = bracket_lib::prelude::RandomNumberGenerator::seeded(1);
+
+    let shallow = raws.spawn_table("room", 1);
+    for _ in 0..20 {
+        assert_eq!(shallow.roll(&mut rng).as_deref(), Some("Knife"));
+    }
+    let deep = raws.spawn_table("room", 4); //Knife's weight is 0 down here.
+    for _ in 0..20 {
+        assert_eq!(deep.roll(&mut rng).as_deref(), Some("Longsword"));
+    }
+}
+
+#[test]
+fn validation_reports_unknown_references() {
+    let yaml = "
+spawn_tables:
+  room:
+    - { name: Rations, weight: 1 }
+    - { table: undead, weight: 1 }
+";
+    let problems = RawMaster::parse(yaml).unwrap().validate();
+    let messages: Vec<String> = problems.iter().map(|p| p.to_string()).collect();
+    assert!(messages.contains(&"spawn table \"room\", entry 1: unknown entity \"Rations\"".to_string()));
+    assert!(messages.contains(&"spawn table \"room\", entry 2: unknown spawn table \"undead\"".to_string()));
+    assert!(messages.contains(&"prefab glyph '%': unknown entity \"Rations\"".to_string()));
+
+    let cycle = "spawn_tables:\n  a:\n    - { table: b, weight: 1 }\n  b:\n    - { table: a, weight: 1 }\n";
+    assert!(matches!(RawMaster::parse(cycle), Err(RawsError::Invalid { .. })));
+}
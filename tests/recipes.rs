@@ -0,0 +1,89 @@
+use bracket_lib::prelude::RandomNumberGenerator;
+
+use my_rl::map_builders::{named_builder, PrefabLibrary, RecipeBook};
+use my_rl::TileType;
+
+const RECIPES: &str = "
+recipes:
+  caves:
+    starter: cellular_automata
+    builders:
+      - area_starting_position: { x: center, y: random }
+      - cull_unreachable
+      - distant_exit
+  vaulted:
+    starter: bsp_interior
+    builders:
+      - room_based_starting_position
+      - room_based_stairs
+      - { chance: 50, then: { one_of: [prefab_vaults, none] } }
+biomes:
+  caverns:
+    recipes: [{ recipe: caves, weight: 1 }]
+  crypts:
+    recipes: [{ recipe: vaulted, weight: 1 }]
+depths:
+  pinned:
+    5: crypts
+  random:
+    - { biome: caverns, weight: 1 }
+    - { biome: crypts, weight: 1, min_depth: 7 }
+";
+
+fn with_builders(builders: &str) -> String {
+    format!("recipes:\n  broken:\n    starter: simple_map\n    builders: {}\nbiomes:\n  b:\n    recipes: [{{ recipe: broken, weight: 1 }}]\ndepths:\n  random: [{{ biome: b, weight: 1 }}]\n", builders)
+}
+
+#[test]
+fn builtin_recipes_can_be_picked_by_name() {
+    let book = RecipeBook::builtin();
+    for name in ["rooms", "bsp_interior", "shapes", "wfc_template"].iter() {
+        assert!(book.contains(name), "no {} recipe", name);
+        assert!(named_builder(name, 3, &mut RandomNumberGenerator::seeded(1234), 80, 43).is_some());
+    }
+    assert!(named_builder("no_such_recipe", 3, &mut RandomNumberGenerator::seeded(1234), 80, 43).is_none());
+
+    let mut rng = RandomNumberGenerator::seeded(1234);
+    let mut chain = named_builder("bsp_interior", 5, &mut rng, 80, 43).unwrap();
+    chain.build_map(&mut rng);
+    assert!(chain.build_data.starting_position.is_some());
+    assert!(chain.build_data.map.tiles.contains(&TileType::StairsDown));
+    assert!(chain.build_data.map.tiles.contains(&TileType::StairsUp));
+}
+
+#[test]
+fn pinned_depths_always_get_their_biome() {
+    let book = RecipeBook::parse(RECIPES, &PrefabLibrary::builtin()).unwrap();
+    let mut rng = RandomNumberGenerator::seeded(1234);
+    for _ in 0..20 {
+        assert_eq!(book.biome_for(5, &mut rng).as_deref(), Some("crypts"));
+        assert_eq!(book.biome_for(4, &mut rng).as_deref(), Some("caverns"));
+    }
+    let deep: Vec<String> = (0..40).filter_map(|_| book.biome_for(8, &mut rng)).collect();
+    assert!(deep.iter().any(|b| b == "crypts") && deep.iter().any(|b| b == "caverns"));
+    assert_eq!(book.recipe_in("crypts", 8, &mut rng).as_deref(), Some("vaulted"));
+    assert_eq!(book.recipe_in("no_such_biome", 8, &mut rng), None);
+}
+
+#[test]
+fn bad_recipes_are_rejected() {
+    let prefabs = PrefabLibrary::builtin();
+    assert!(RecipeBook::parse(&with_builders("[room_drawer, dogleg_corridors]"), &prefabs).is_ok());
+    assert!(RecipeBook::parse(&with_builders("[no_such_builder]"), &prefabs).is_err());
+    assert!(RecipeBook::parse(&with_builders("[{ room_sorter: sideways }]"), &prefabs).is_err());
+    assert!(RecipeBook::parse(&with_builders("[{ room_drawer: 3 }]"), &prefabs).is_err());
+    assert!(RecipeBook::parse(&with_builders("[{ one_of: [room_drawer, bsp_dungeon] }]"), &prefabs).is_err());
+    assert!(RecipeBook::parse(&with_builders("[{ chance: 150, then: room_drawer }]"), &prefabs).is_err());
+    assert!(RecipeBook::parse(&RECIPES.replace("5: crypts", "5: vaulted"), &prefabs).is_err());
+    assert!(RecipeBook::parse(&RECIPES.replace("recipe: vaulted", "recipe: vaults"), &prefabs).is_err());
+    assert!(RecipeBook::parse(&RECIPES.replace("starter: cellular_automata", "starter: none"), &prefabs).is_err());
+    assert!(RecipeBook::parse(&with_builders("[{ prefab_sectional: no_such_prefab }]"), &prefabs).is_err());
+}
+
+#[test]
+fn recipes_are_checked_against_the_prefabs_given() {
+    let none = PrefabLibrary::parse(&[], None).unwrap();
+    assert!(RecipeBook::builtin().check(&PrefabLibrary::builtin()).is_ok());
+    assert!(RecipeBook::builtin().check(&none).is_err());
+    assert!(RecipeBook::parse(&with_builders("[{ prefab_sectional: underground_fort }]"), &none).is_err());
+}
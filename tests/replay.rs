@@ -0,0 +1,87 @@
+use std::sync::Arc;
+
+use specs::prelude::*;
+
+use my_rl::command::{Command, Commandable};
+use my_rl::gui::look_n_feel::Dir;
+use my_rl::player::PlayerController;
+use my_rl::replay::{self, Replay, ReplayRecorder};
+use my_rl::user_input::UserInput;
+use my_rl::RunState;
+
+//Plays `script` the way GameState::tick() would, recording to `path`.
+fn play_and_record(seed: u64, path: &str, script: &[Command]) -> replay::FinalState {
+    let mut ecs = my_rl::build_world(seed, 1);
+    ecs.insert(ReplayRecorder::create(path, seed).unwrap());
+    let user_input = Arc::new(UserInput::new());
+    let controller = PlayerController::new(&user_input);
+
+    let mut script = script.iter();
+    loop {
+        let runstate = *ecs.fetch::<RunState>();
+        match runstate {
+            RunState::AwaitingInput => {}
+            RunState::GameOver => break,
+            _ => {
+                my_rl::step(&mut ecs);
+                continue;
+            }
+        }
+        match script.next() {
+            Some(cmd) => controller.send(*cmd),
+            None => break,
+        }
+        let newrunstate = controller.ecs_process(&mut ecs, RunState::AwaitingInput);
+        *ecs.write_resource::<RunState>() = newrunstate;
+        my_rl::damage_system::delete_the_dead(&mut ecs);
+    }
+
+    let entries = Replay::load(path).unwrap().entries.len();
+    replay::final_state(&ecs, seed, entries)
+}
+
+#[test]
+fn replay_reproduces_recorded_run() {
+    let path = std::env::temp_dir().join("my_rl_replay_test.replay");
+    let path = path.to_str().unwrap();
+
+    let dirs = [Dir::UP, Dir::LEFT, Dir::DOWN, Dir::RIGHT];
+    let mut script = Vec::new();
+    for i in 0..60 {
+        script.push(Command::Move { dir: dirs[(i / 5) % 4] });
+        if i % 7 == 0 {
+            script.push(Command::Grab);
+        }
+    }
+
+    let recorded = play_and_record(4321, path, &script);
+    let loaded = Replay::load(path).unwrap();
+    assert_eq!(loaded.header.seed, 4321);
+    //Fewer entries than the script if the player died partway through.
+    assert!(!loaded.entries.is_empty() && loaded.entries.len() <= script.len());
+
+    let replayed = replay::run_headless(&loaded).unwrap();
+    assert_eq!(recorded, replayed);
+
+    let _ = std::fs::remove_file(path);
+}
+
+#[test]
+fn replay_reports_desync() {
+    let path = std::env::temp_dir().join("my_rl_replay_desync.replay");
+    let path = path.to_str().unwrap();
+    std::fs::write(
+        path,
+        "{\"version\":1,\"seed\":1}\n{\"turn\":5,\"commands\":[\"Wait\"]}\n",
+    )
+    .unwrap();
+
+    let loaded = Replay::load(path).unwrap();
+    match replay::run_headless(&loaded) {
+        Err(replay::ReplayError::Desync { entry: 0, expected_turn: 5, actual_turn: 0 }) => {}
+        Err(e) => panic!("unexpected error: {}", e),
+        Ok(_) => panic!("desynced replay played to the end"),
+    }
+
+    let _ = std::fs::remove_file(path);
+}
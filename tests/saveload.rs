@@ -0,0 +1,179 @@
+use std::path::PathBuf;
+
+use specs::prelude::*;
+
+use bracket_lib::prelude::Point;
+use my_rl::rng::GameSeed;
+use my_rl::saveload_system::{self, SaveError, SAVE_FORMAT_VERSION};
+use my_rl::{Map, Player, Position, Stats, TurnCounter};
+
+fn scratch_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("my_rl_{}_{}.json", name, std::process::id()))
+}
+
+fn started_world(seed: u64) -> World {
+    let mut ecs = my_rl::build_world(seed, 1);
+    my_rl::step(&mut ecs); //PreRun
+    ecs
+}
+
+fn player_hp(ecs: &World) -> i32 {
+    ecs.read_storage::<Stats>().get(*ecs.fetch::<Entity>()).unwrap().hp
+}
+
+#[test]
+fn a_saved_game_loads_back() {
+    let path = scratch_path("roundtrip");
+    let mut ecs = started_world(1234);
+    {
+        let player = *ecs.fetch::<Entity>();
+        ecs.write_storage::<Stats>().get_mut(player).unwrap().hp = 3;
+    }
+    saveload_system::save_game_to(&mut ecs, &path).unwrap();
+
+    let save = saveload_system::parse_save(&std::fs::read_to_string(&path).unwrap()).unwrap();
+    assert_eq!(save.format_version, SAVE_FORMAT_VERSION);
+    assert_eq!(save.game_version, env!("CARGO_PKG_VERSION"));
+
+    let mut loaded = started_world(99);
+    saveload_system::load_game_from(&mut loaded, &path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(player_hp(&loaded), 3);
+    assert_eq!(loaded.fetch::<GameSeed>().0, 1234);
+    assert_eq!(*loaded.fetch::<Point>(), *ecs.fetch::<Point>());
+    assert!(loaded.fetch::<Map>().tiles == ecs.fetch::<Map>().tiles);
+    assert_eq!(loaded.read_storage::<Position>().join().count(), ecs.read_storage::<Position>().join().count());
+}
+
+#[test]
+fn unversioned_saves_are_migrated() {
+    let path = scratch_path("legacy");
+    let mut ecs = started_world(1234);
+    saveload_system::save_game_to(&mut ecs, &path).unwrap();
+    let save = saveload_system::parse_save(&std::fs::read_to_string(&path).unwrap()).unwrap();
+
+    //Rewrite it the way the pre-envelope builds did: bare arrays in the old list's order.
+    let legacy_order = ["Position", "Renderable", "Player", "Viewshed", "Hostile",
+        "Name", "BlocksTile", "Stats", "MeleeIntent", "Item", "Consumable", "Ranged", "DamageOnUse",
+        "AoE", "Confusion", "Healing", "InBackpack", "PickUpIntent", "UseItemIntent", "Particle", "Hunger",
+        "DropItemIntent", "EquipIntent", "Equippable", "Equipped", "Bleeding", "Weapon", "SerializationHelper",
+        "MagicMapper", "Useable", "ThrowIntent", "Throwable", "Flammable", "Lightsource", "Aflame", "Hidden",
+        "EntryTrigger", "JustMoved", "Door", "BlocksVisibility", "Info"];
+    let legacy: String = legacy_order.iter()
+        .map(|name| save.components.get(*name).map_or("[]".to_string(), |list| list.to_string()))
+        .collect();
+    std::fs::write(&path, legacy).unwrap();
+
+    let mut loaded = started_world(99);
+    saveload_system::load_game_from(&mut loaded, &path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(*loaded.fetch::<Point>(), *ecs.fetch::<Point>());
+    assert_eq!(loaded.read_storage::<Player>().join().count(), 1);
+}
+
+#[test]
+fn unreadable_saves_are_errors_not_panics() {
+    let mut ecs = started_world(1234);
+    let missing = saveload_system::load_game_from(&mut ecs, &scratch_path("missing"));
+    assert!(matches!(missing, Err(SaveError::Io(_))));
+
+    let future = format!("{{\"format_version\": {}, \"game_version\": \"9.9.9\", \"components\": {{}}}}",
+        SAVE_FORMAT_VERSION + 1);
+    assert!(matches!(saveload_system::parse_save(&future), Err(SaveError::UnsupportedVersion { .. })));
+    assert!(matches!(saveload_system::parse_save("{\"format_"), Err(SaveError::Parse(_))));
+    assert!(matches!(saveload_system::parse_save("[][]"), Err(SaveError::Migration { from: 1, .. })));
+
+    //Nothing was touched by the failed loads.
+    assert!(ecs.read_storage::<Player>().get(*ecs.fetch::<Entity>()).is_some());
+}
+
+#[test]
+fn pre_header_saves_get_their_meta_from_the_world() {
+    let path = scratch_path("envelope");
+    let mut ecs = started_world(1234);
+    saveload_system::save_game_to(&mut ecs, &path).unwrap();
+    let save = saveload_system::parse_save(&std::fs::read_to_string(&path).unwrap()).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    //The format 2 envelope: one object, no meta.
+    let envelope = serde_json::json!({
+        "format_version": 2,
+        "game_version": "0.1.0",
+        "components": save.components,
+    });
+    let migrated = saveload_system::parse_save(&envelope.to_string()).unwrap();
+    assert_eq!(migrated.meta.character, "Player");
+    assert_eq!(migrated.meta.depth, 1);
+    assert_eq!(migrated.meta.seed, 0);
+}
+
+#[test]
+fn slots_are_listed_from_their_headers() {
+    let dir = std::env::temp_dir().join(format!("my_rl_slots_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+
+    let mut ecs = started_world(1234);
+    ecs.insert(TurnCounter(7));
+    saveload_system::save_game_to(&mut ecs, &saveload_system::slot_path(&dir, "alpha").unwrap()).unwrap();
+    std::fs::write(saveload_system::slot_path(&dir, "beta").unwrap(), "not a save").unwrap();
+    std::fs::write(dir.join("notes.txt"), "not a slot").unwrap();
+
+    let slots = saveload_system::list_slots_in(&dir);
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    let names: Vec<&str> = slots.iter().map(|slot| slot.name.as_str()).collect();
+    assert_eq!(names, vec!["alpha", "beta"]); //unreadable slots go last
+    let meta = slots[0].meta.as_ref().unwrap();
+    assert_eq!((meta.character.as_str(), meta.depth, meta.turn, meta.seed), ("Player", 1, 7, 1234));
+    assert!(slots[1].meta.is_err());
+
+    assert!(matches!(saveload_system::slot_path(&dir, "../up"), Err(SaveError::BadSlotName(_))));
+}
+
+//Slot-based calls go through save_dir(); every test in this file shares one scratch directory.
+fn use_scratch_save_dir() -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("my_rl_save_dir_{}", std::process::id()));
+    std::env::set_var(saveload_system::SAVE_DIR_VAR, &dir);
+    dir
+}
+
+#[test]
+fn a_corrupted_slot_falls_back_to_its_backup() {
+    let dir = use_scratch_save_dir();
+    let path = saveload_system::slot_path(&dir, "crashed").unwrap();
+    let mut ecs = started_world(1234);
+    saveload_system::save_game(&mut ecs, "crashed").unwrap();
+    {
+        let player = *ecs.fetch::<Entity>();
+        ecs.write_storage::<Stats>().get_mut(player).unwrap().hp = 3;
+    }
+    saveload_system::save_game(&mut ecs, "crashed").unwrap();
+    assert!(saveload_system::backup_path(&path).exists());
+
+    std::fs::write(&path, "{\"format_version\": 3, \"game_ver").unwrap();
+    let listed = saveload_system::list_slots();
+    assert!(listed.iter().any(|slot| slot.name == "crashed" && slot.meta.is_ok()));
+
+    let mut loaded = started_world(99);
+    saveload_system::load_game(&mut loaded, "crashed").unwrap();
+    assert_eq!(player_hp(&loaded), 8); //the save before the last one
+
+    saveload_system::delete_save("crashed").unwrap();
+    assert!(!path.exists() && !saveload_system::backup_path(&path).exists());
+}
+
+#[test]
+fn changing_level_autosaves_the_run() {
+    let dir = use_scratch_save_dir();
+    let mut ecs = started_world(4321);
+    my_rl::goto_next_level(&mut ecs);
+    assert!(!saveload_system::slot_path(&dir, "run-4321").unwrap().exists());
+
+    ecs.insert(saveload_system::Autosave);
+    my_rl::goto_next_level(&mut ecs);
+    let path = saveload_system::slot_path(&dir, "run-4321").unwrap();
+    assert_eq!(saveload_system::read_meta(&path).unwrap().depth, 3);
+    saveload_system::delete_save("run-4321").unwrap();
+}
@@ -0,0 +1,105 @@
+use bracket_lib::prelude::{Point, RandomNumberGenerator};
+use specs::prelude::*;
+
+use my_rl::command::{Command, Commandable};
+use my_rl::map_builders::named_builder;
+use my_rl::player::PlayerController;
+use my_rl::saveload_system;
+use my_rl::user_input::UserInput;
+use my_rl::{Door, Map, Player, Position, RunState, Stats, TileType, Viewshed, MAP_HEIGHT, MAP_WIDTH};
+
+//The player at 4, 5 in a room walled in at x = 6, where 6, 5 is a secret door and
+//6, 4 a hidden passage; the player's mind is `mind`.
+fn setup(mind: i32) -> World {
+    let mut ecs = my_rl::build_world(1234, 1);
+    my_rl::step(&mut ecs); //PreRun
+
+    let player = *ecs.fetch::<Entity>();
+    let others: Vec<Entity> = (&ecs.entities(), &ecs.read_storage::<Position>(), !&ecs.read_storage::<Player>())
+        .join()
+        .map(|(e, _, _)| e)
+        .collect();
+    ecs.delete_entities(&others).unwrap();
+
+    let mut map = Map::new(1, 12, 12);
+    for y in 1..11 {
+        for x in 1..11 {
+            let idx = map.xy_idx(x, y);
+            map.tiles[idx] = if x == 6 { TileType::Wall } else { TileType::Floor };
+        }
+    }
+    let (door, passage) = (map.xy_idx(6, 5), map.xy_idx(6, 4));
+    map.tiles[door] = TileType::SecretDoor;
+    map.tiles[passage] = TileType::HiddenPassage;
+    map.populate_blocked();
+    ecs.insert(map);
+    *ecs.write_storage::<Position>().get_mut(player).unwrap() = Position { x: 4, y: 5 };
+    *ecs.write_resource::<Point>() = Point::new(4, 5);
+    ecs.write_storage::<Stats>().get_mut(player).unwrap().mind = mind;
+    ecs.write_storage::<Viewshed>().get_mut(player).unwrap().dirty = true;
+    my_rl::run_systems(&mut ecs, my_rl::schedule::Turn::PreRun);
+    ecs
+}
+
+fn search(ecs: &mut World) {
+    let controller = PlayerController::new(&std::sync::Arc::new(UserInput::new()));
+    controller.send(Command::Search);
+    let runstate = controller.ecs_process(ecs, RunState::AwaitingInput);
+    *ecs.write_resource::<RunState>() = runstate;
+    while *ecs.fetch::<RunState>() != RunState::AwaitingInput {
+        my_rl::step(ecs);
+    }
+}
+
+fn tile(ecs: &World, x: i32, y: i32) -> TileType {
+    let map = ecs.fetch::<Map>();
+    map.tiles[map.xy_idx(x, y)]
+}
+
+fn door_at(ecs: &World, x: i32, y: i32) -> bool {
+    (&ecs.read_storage::<Door>(), &ecs.read_storage::<Position>()).join()
+        .any(|(door, pos)| !door.open && pos.x == x && pos.y == y)
+}
+
+#[test]
+fn secrets_look_like_wall_until_found() {
+    let mut ecs = setup(-100);
+    assert_eq!(TileType::SecretDoor.looks_like(), TileType::Wall);
+    let turn = ecs.fetch::<my_rl::TurnCounter>().0;
+    search(&mut ecs);
+    assert_eq!(ecs.fetch::<my_rl::TurnCounter>().0, turn + 1); //searching takes a turn, found or not
+    assert_eq!(tile(&ecs, 6, 5), TileType::SecretDoor);
+    assert_eq!(tile(&ecs, 6, 4), TileType::HiddenPassage);
+    assert!(ecs.fetch::<Map>().blocked[ecs.fetch::<Map>().xy_idx(6, 4)]);
+}
+
+#[test]
+fn a_sharp_mind_finds_them_and_they_stay_found() {
+    let mut ecs = setup(100);
+    search(&mut ecs);
+    assert_eq!(tile(&ecs, 6, 4), TileType::Floor);
+    assert_eq!(tile(&ecs, 6, 5), TileType::Floor);
+    assert!(door_at(&ecs, 6, 5));
+
+    let path = std::env::temp_dir().join(format!("my_rl_secrets_{}.json", std::process::id()));
+    saveload_system::save_game_to(&mut ecs, &path).unwrap();
+    let mut loaded = my_rl::build_world(99, 1);
+    saveload_system::load_game_from(&mut loaded, &path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(tile(&loaded, 6, 4), TileType::Floor);
+    assert!(door_at(&loaded, 6, 5));
+}
+
+#[test]
+fn builders_hide_things_but_never_the_way_down() {
+    let mut secrets = 0;
+    for seed in 0..20 {
+        let mut rng = RandomNumberGenerator::seeded(seed);
+        let mut chain = named_builder("rooms", 2, &mut rng, MAP_WIDTH, MAP_HEIGHT).unwrap();
+        chain.build_map(&mut rng);
+        let map = &chain.build_data.map;
+        secrets += map.tiles.iter().filter(|tile| tile.looks_like() != **tile).count();
+        assert!(my_rl::map_builders::invariants::violations(&chain.build_data).is_empty());
+    }
+    assert!(secrets > 0);
+}
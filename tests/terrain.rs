@@ -0,0 +1,72 @@
+use bracket_lib::prelude::{a_star_search, BaseMap, DijkstraMap, Point};
+
+use my_rl::{Map, TileType};
+
+fn open_map() -> Map {
+    let mut map = Map::new(1, 8, 8);
+    for x in 1..7 {
+        for y in 1..7 {
+            let idx = map.xy_idx(x, y);
+            map.tiles[idx] = TileType::Floor;
+        }
+    }
+    map
+}
+
+#[test]
+fn terrain_decides_walkability_sight_and_cost() {
+    let mut map = open_map();
+    let water = map.xy_idx(3, 2);
+    let mud = map.xy_idx(3, 3);
+    let chasm = map.xy_idx(3, 4);
+    map.tiles[water] = TileType::DeepWater;
+    map.tiles[mud] = TileType::Mud;
+    map.tiles[chasm] = TileType::Chasm;
+    map.populate_blocked();
+
+    assert!(map.blocked[water] && map.blocked[chasm] && !map.blocked[mud]);
+    assert!(!map.is_opaque(water) && !map.is_opaque(chasm));
+    assert!(map.is_opaque(map.xy_idx(0, 0)));
+
+    let exits = map.get_available_exits(map.xy_idx(2, 3));
+    assert_eq!(exits.iter().find(|(idx, _)| *idx == mud).unwrap().1, 2.0);
+    assert!(exits.iter().all(|(idx, _)| *idx != water && *idx != chasm));
+}
+
+#[test]
+fn paths_go_around_costly_terrain() {
+    //A wall down the middle, with a muddy gap and an equally near dry one.
+    let mut map = open_map();
+    for y in 1..7 {
+        let idx = map.xy_idx(4, y);
+        map.tiles[idx] = TileType::Wall;
+    }
+    let (muddy_gap, dry_gap) = (map.xy_idx(4, 2), map.xy_idx(4, 4));
+    map.tiles[muddy_gap] = TileType::Mud;
+    map.tiles[dry_gap] = TileType::Floor;
+    map.populate_blocked();
+
+    let path = a_star_search(map.xy_idx(2, 3), map.xy_idx(6, 3), &map);
+    assert!(path.success);
+    assert!(path.steps.contains(&dry_gap));
+}
+
+#[test]
+fn terrain_features_leave_impassable_terrain_and_reachable_stairs() {
+    let mut impassable = 0;
+    for seed in 6..=14 {
+        let ecs = my_rl::build_world(seed, 5);
+        let mut map = (*ecs.fetch::<Map>()).clone();
+        map.populate_blocked();
+        let start = *ecs.fetch::<Point>();
+        let stairs = map.tiles.iter().position(|tile| *tile == TileType::StairsDown).unwrap();
+
+        let starts = [map.xy_idx(start.x, start.y)];
+        let dijkstra_map = DijkstraMap::new(map.width as usize, map.height as usize, &starts, &map, 1000.0);
+        assert!(dijkstra_map.map[stairs] < f32::MAX, "seed {}", seed);
+        impassable += map.tiles.iter()
+            .filter(|tile| matches!(tile, TileType::DeepWater | TileType::Chasm | TileType::Lava))
+            .count();
+    }
+    assert!(impassable > 0);
+}
@@ -0,0 +1,32 @@
+use bracket_lib::prelude::RandomNumberGenerator;
+
+use my_rl::map_builders::{BuilderChain, WaveformCollapseBuilder, PREFABS};
+use my_rl::{Map, TileType};
+
+fn remix(seed: u64) -> Map {
+    let mut rng = RandomNumberGenerator::seeded(seed);
+    let mut chain = BuilderChain::new(1, 64, 64);
+    let level = PREFABS.lock().unwrap().get("wfc_populated").unwrap().clone();
+    chain.start_with(WaveformCollapseBuilder::from_template(level));
+    chain.build_map(&mut rng);
+    chain.build_data.map
+}
+
+#[test]
+fn touching_chunks_share_their_edges() {
+    let map = remix(1234);
+    let tile = |x: i32, y: i32| map.tiles[map.xy_idx(x, y)];
+    for boundary in (8..64).step_by(8) {
+        for along in 0..64 {
+            assert!(tile(boundary - 1, along) == tile(boundary, along), "seam at x = {}, y = {}", boundary, along);
+            assert!(tile(along, boundary - 1) == tile(along, boundary), "seam at x = {}, y = {}", along, boundary);
+        }
+    }
+    assert!(map.tiles.iter().any(|tile| *tile == TileType::Floor));
+}
+
+#[test]
+fn a_remix_is_new_but_repeatable() {
+    assert!(remix(1234).tiles == remix(1234).tiles);
+    assert!(remix(1234).tiles != remix(4321).tiles);
+}